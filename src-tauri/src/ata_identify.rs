@@ -0,0 +1,612 @@
+/// # ATA IDENTIFYモジュール
+///
+/// このモジュールは、`hdparm -I`の出力またはATA PASS-THROUGHで取得したIDENTIFY DEVICEのデータ（256ワード）を
+/// 構造化された情報に変換します。
+/// Security機能セットの状態と消去時間の見積もり、Sanitize機能セット、TRIMの動作、
+/// HPA/DCOの状態、セクタサイズ、シリアル番号などを取得し、消去方式の選択に使用します。
+use crate::EraseCapabilities;
+use serde::Serialize;
+
+/// ATA IDENTIFY DEVICEの解析結果
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AtaIdentify {
+    /// モデル名
+    pub model: Option<String>,
+    /// シリアル番号
+    pub serial: Option<String>,
+    /// ファームウェアのリビジョン
+    pub firmware: Option<String>,
+    /// WWN（`0x`で始まる16進数）
+    pub wwn: Option<String>,
+    /// 48ビットLBAでアクセスできるセクタ数
+    pub lba48_sectors: Option<u64>,
+    /// 論理セクタサイズ（バイト）
+    pub logical_sector_size: Option<u32>,
+    /// 物理セクタサイズ（バイト）
+    pub physical_sector_size: Option<u32>,
+    /// 回転速度
+    pub rotation: Option<RotationRate>,
+    /// Security機能セット
+    pub security: AtaSecurity,
+    /// Sanitize機能セット
+    pub sanitize: AtaSanitize,
+    /// TRIM（Data Set Management）
+    pub trim: TrimSupport,
+    /// HPA（Host Protected Area）
+    pub hpa: FeatureState,
+    /// DCO（Device Configuration Overlay）
+    pub dco: FeatureState,
+}
+
+/// ディスクの回転速度
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "type", content = "rpm", rename_all = "snake_case")]
+pub enum RotationRate {
+    /// 回転しない（SSD）
+    SolidState,
+    /// 回転速度（rpm）
+    Rpm(u32),
+}
+
+/// 機能セットの対応状況
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeatureState {
+    /// 対応しているか
+    pub supported: bool,
+    /// 有効になっているか
+    pub enabled: bool,
+}
+
+/// Security機能セットの状態
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AtaSecurity {
+    /// Security機能セットに対応しているか
+    pub supported: bool,
+    /// ユーザーパスワードが設定されているか
+    pub enabled: bool,
+    /// ロックされているか（アンロックするまでデータにアクセスできない）
+    pub locked: bool,
+    /// frozen状態か（電源を入れ直すまでSecurityコマンドを受け付けない）
+    pub frozen: bool,
+    /// パスワードの試行回数の上限に達したか（電源を入れ直すまでアンロックできない）
+    pub count_expired: bool,
+    /// Enhanced Secure Eraseに対応しているか
+    pub enhanced_erase_supported: bool,
+    /// Secure Eraseの所要時間の見積もり
+    pub erase_time: Option<EraseTimeEstimate>,
+    /// Enhanced Secure Eraseの所要時間の見積もり
+    pub enhanced_erase_time: Option<EraseTimeEstimate>,
+}
+
+/// ドライブが報告する消去の所要時間
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EraseTimeEstimate {
+    /// 所要時間（分）
+    pub minutes: u32,
+    /// 所要時間が報告できる上限（508分）を超えるため、`minutes`は下限であるか
+    pub more_than: bool,
+}
+
+/// Sanitize機能セットの状態
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AtaSanitize {
+    /// Sanitize機能セットに対応しているか
+    pub supported: bool,
+    /// BLOCK ERASE EXTに対応しているか
+    pub block_erase: bool,
+    /// CRYPTO SCRAMBLE EXTに対応しているか
+    pub crypto_scramble: bool,
+    /// OVERWRITE EXTに対応しているか
+    pub overwrite: bool,
+    /// SANITIZE ANTIFREEZE LOCK EXTに対応しているか
+    pub antifreeze_lock: bool,
+}
+
+/// TRIMの対応状況
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TrimSupport {
+    /// TRIMに対応しているか
+    pub supported: bool,
+    /// TRIM後の読み出し結果が一定か（DRAT）
+    pub deterministic_read: bool,
+    /// TRIM後にゼロが読み出されるか（RZAT）
+    pub read_zeros: bool,
+}
+
+impl AtaIdentify {
+    /// 消去方式の選択に使用する消去機能を取得する
+    pub fn erase_capabilities(&self) -> EraseCapabilities {
+        EraseCapabilities {
+            ata_security_supported: self.security.supported,
+            ata_enhanced_erase_supported: self.security.enhanced_erase_supported,
+            ata_security_frozen: self.security.frozen,
+            ata_security_enabled: self.security.enabled,
+            ata_security_locked: self.security.locked,
+            ata_sanitize_block_supported: self.sanitize.block_erase,
+            ata_sanitize_crypto_supported: self.sanitize.crypto_scramble,
+            ata_sanitize_overwrite_supported: self.sanitize.overwrite,
+            ..Default::default()
+        }
+    }
+}
+
+/// `hdparm -I`の出力のセクション
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Other,
+    Features,
+    Security,
+}
+
+/// `hdparm -I`の出力を解析する関数
+///
+/// # 引数
+///
+/// * `output` - `hdparm -I`の標準出力
+///
+/// # 戻り値
+///
+/// * `Option<AtaIdentify>` - ATA IDENTIFYの内容が含まれていない場合（ATAコマンドを通さないUSB変換アダプタなど）は`None`
+pub fn parse(output: &str) -> Option<AtaIdentify> {
+    let mut identify = AtaIdentify::default();
+    let mut section = Section::Other;
+    let mut found = false;
+
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // インデントのない行はセクションの開始
+        if !line.starts_with(char::is_whitespace) {
+            section = match line.trim_end() {
+                "Commands/features:" => Section::Features,
+                "Security:" => Section::Security,
+                _ => Section::Other,
+            };
+            if line.starts_with("ATA device") {
+                found = true;
+            }
+            if let Some(wwn) = line.strip_prefix("Logical Unit WWN Device Identifier:") {
+                identify.wwn = Some(format!("0x{}", wwn.trim().to_lowercase()));
+            }
+            continue;
+        }
+
+        match section {
+            Section::Features => parse_feature(line, &mut identify),
+            Section::Security => parse_security(line, &mut identify.security),
+            Section::Other => parse_field(line, &mut identify),
+        }
+    }
+
+    found.then_some(identify)
+}
+
+/// 「項目: 値」形式の行を解析する
+fn parse_field(line: &str, identify: &mut AtaIdentify) {
+    let Some((key, value)) = line.split_once(':') else {
+        return;
+    };
+    // "Logical  Sector size"のように空白の数が揃っていない項目名を正規化する
+    let key = key.split_whitespace().collect::<Vec<_>>().join(" ");
+    let value = value.trim();
+    let text = || (!value.is_empty()).then(|| value.to_string());
+    let number = || value.split_whitespace().next().and_then(|v| v.parse::<u64>().ok());
+
+    match key.as_str() {
+        "Model Number" => identify.model = text(),
+        "Serial Number" => identify.serial = text(),
+        "Firmware Revision" => identify.firmware = text(),
+        "LBA48 user addressable sectors" => identify.lba48_sectors = number(),
+        "Logical Sector size" => identify.logical_sector_size = number().map(|n| n as u32),
+        "Physical Sector size" => identify.physical_sector_size = number().map(|n| n as u32),
+        "Nominal Media Rotation Rate" => {
+            identify.rotation = if value == "Solid State Device" {
+                Some(RotationRate::SolidState)
+            } else {
+                number().map(|rpm| RotationRate::Rpm(rpm as u32))
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Commands/featuresセクションの行を解析する
+///
+/// 行頭の`*`は機能が有効であることを示します。
+///
+/// ```text
+///    *    SANITIZE feature set
+///         Security Mode feature set
+/// ```
+fn parse_feature(line: &str, identify: &mut AtaIdentify) {
+    let line = line.trim();
+    let (enabled, name) = match line.strip_prefix('*') {
+        Some(name) => (true, name.trim()),
+        None => (false, line),
+    };
+    let state = FeatureState {
+        supported: true,
+        enabled,
+    };
+
+    match name {
+        "Host Protected Area feature set" => identify.hpa = state,
+        "Device Configuration Overlay feature set" => identify.dco = state,
+        "SANITIZE feature set" => identify.sanitize.supported = true,
+        "BLOCK_ERASE_EXT command" => identify.sanitize.block_erase = true,
+        "CRYPTO_SCRAMBLE_EXT command" => identify.sanitize.crypto_scramble = true,
+        "OVERWRITE_EXT command" => identify.sanitize.overwrite = true,
+        "SANITIZE_ANTIFREEZE_LOCK_EXT command" => identify.sanitize.antifreeze_lock = true,
+        "Deterministic read data after TRIM" => identify.trim.deterministic_read = true,
+        "Deterministic read ZEROs after TRIM" => {
+            identify.trim.deterministic_read = true;
+            identify.trim.read_zeros = true;
+        }
+        _ if name.starts_with("Data Set Management TRIM supported") => identify.trim.supported = true,
+        _ => {}
+    }
+}
+
+/// Securityセクションの行を解析する
+///
+/// 状態は行頭の`not`の有無で示されます。
+///
+/// ```text
+///     Master password revision code = 65534
+///         supported
+///     not    enabled
+///     not    locked
+///         frozen
+///     not    expired: security count
+///         supported: enhanced erase
+///     2min for SECURITY ERASE UNIT. 8min for ENHANCED SECURITY ERASE UNIT.
+/// ```
+fn parse_security(line: &str, security: &mut AtaSecurity) {
+    if line.contains("SECURITY ERASE UNIT") {
+        for sentence in line.split('.') {
+            let sentence = sentence.trim();
+            if let Some(time) = sentence.strip_suffix("for ENHANCED SECURITY ERASE UNIT") {
+                security.enhanced_erase_time = parse_erase_time(time);
+            } else if let Some(time) = sentence.strip_suffix("for SECURITY ERASE UNIT") {
+                security.erase_time = parse_erase_time(time);
+            }
+        }
+        return;
+    }
+
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (negated, words) = match words.as_slice() {
+        ["not", rest @ ..] => (true, rest),
+        rest => (false, rest),
+    };
+    let value = !negated;
+
+    match words {
+        ["supported"] => security.supported = value,
+        ["enabled"] => security.enabled = value,
+        ["locked"] => security.locked = value,
+        ["frozen"] => security.frozen = value,
+        ["expired:", "security", "count"] => security.count_expired = value,
+        ["supported:", "enhanced", "erase"] => security.enhanced_erase_supported = value,
+        _ => {}
+    }
+}
+
+/// 「2min」「more than 508min」形式の所要時間を解析する
+fn parse_erase_time(text: &str) -> Option<EraseTimeEstimate> {
+    let text = text.trim();
+    let (more_than, text) = match text.strip_prefix("more than") {
+        Some(rest) => (true, rest.trim()),
+        None => (false, text),
+    };
+    let minutes = text.strip_suffix("min")?.trim().parse().ok()?;
+    Some(EraseTimeEstimate { minutes, more_than })
+}
+
+/// IDENTIFY DEVICEのデータ（256ワード）を解析する関数
+///
+/// 各項目のワード位置はACS-3に従います。
+///
+/// # 引数
+///
+/// * `words` - IDENTIFY DEVICEのデータ
+///
+/// # 戻り値
+///
+/// * `Option<AtaIdentify>` - ATAデバイスのデータではない場合（ATAPIデバイスや空のデータ）は`None`
+pub fn from_words(words: &[u16]) -> Option<AtaIdentify> {
+    let words: &[u16; 256] = words.get(..256)?.try_into().ok()?;
+    // ワード0のbit 15はATAPIデバイスを示す
+    if words[0] & 0x8000 != 0 || words.iter().all(|w| *w == 0) {
+        return None;
+    }
+    let bit = |word: usize, bit: u32| words[word] & (1 << bit) != 0;
+
+    let lba48_sectors = bit(83, 10).then(|| {
+        (0..4).fold(0u64, |sectors, i| sectors | u64::from(words[100 + i]) << (16 * i))
+    });
+
+    // ワード106はbit 15:14が01の場合のみ有効
+    let sector_info = (words[106] & 0xc000 == 0x4000).then_some(words[106]);
+    let logical_sector_size = match sector_info {
+        // 論理セクタが256ワードより大きい場合はワード117〜118にワード数が格納されている
+        Some(info) if info & (1 << 12) != 0 => {
+            Some((u32::from(words[117]) | u32::from(words[118]) << 16) * 2)
+        }
+        _ => Some(512),
+    };
+    let physical_sector_size = match sector_info {
+        Some(info) if info & (1 << 13) != 0 => logical_sector_size.map(|size| size << (info & 0xf)),
+        _ => logical_sector_size,
+    };
+
+    let rotation = match words[217] {
+        0x0001 => Some(RotationRate::SolidState),
+        rpm @ 0x0401..=0xfffe => Some(RotationRate::Rpm(u32::from(rpm))),
+        _ => None,
+    };
+
+    let wwn = (words[84] & 0xc100 == 0x4100).then(|| {
+        format!("0x{:04x}{:04x}{:04x}{:04x}", words[108], words[109], words[110], words[111])
+    });
+
+    Some(AtaIdentify {
+        model: identify_string(&words[27..47]),
+        serial: identify_string(&words[10..20]),
+        firmware: identify_string(&words[23..27]),
+        wwn,
+        lba48_sectors,
+        logical_sector_size,
+        physical_sector_size,
+        rotation,
+        security: AtaSecurity {
+            supported: bit(82, 1) || bit(128, 0),
+            enabled: bit(128, 1),
+            locked: bit(128, 2),
+            frozen: bit(128, 3),
+            count_expired: bit(128, 4),
+            enhanced_erase_supported: bit(128, 5),
+            erase_time: erase_time_from_word(words[89]),
+            enhanced_erase_time: erase_time_from_word(words[90]),
+        },
+        sanitize: AtaSanitize {
+            supported: bit(59, 12),
+            block_erase: bit(59, 15),
+            crypto_scramble: bit(59, 13),
+            overwrite: bit(59, 14),
+            antifreeze_lock: bit(59, 10),
+        },
+        trim: TrimSupport {
+            supported: bit(169, 0),
+            deterministic_read: bit(69, 14),
+            read_zeros: bit(69, 14) && bit(69, 5),
+        },
+        hpa: FeatureState {
+            supported: bit(82, 10),
+            enabled: bit(85, 10),
+        },
+        dco: FeatureState {
+            supported: bit(83, 11),
+            enabled: bit(86, 11),
+        },
+    })
+}
+
+/// IDENTIFY DEVICEの文字列を取得する（各ワードは上位バイトが先の文字）
+fn identify_string(words: &[u16]) -> Option<String> {
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    let text = String::from_utf8_lossy(&bytes)
+        .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+        .to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// ワード89/90の消去時間を解析する
+///
+/// bit 15が1の場合はbit 14:0、0の場合はbit 7:0が2分単位の時間で、
+/// 最大値は1つ手前の値（508分または65532分）を超えることを示します。
+fn erase_time_from_word(word: u16) -> Option<EraseTimeEstimate> {
+    let (value, max) = if word & 0x8000 != 0 { (word & 0x7fff, 0x7fff) } else { (word & 0xff, 0xff) };
+    match value {
+        0 => None,
+        _ if value == max => Some(EraseTimeEstimate { minutes: u32::from(max - 1) * 2, more_than: true }),
+        _ => Some(EraseTimeEstimate { minutes: u32::from(value) * 2, more_than: false }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> AtaIdentify {
+        let path = format!("{}/tests/fixtures/hdparm/{}", env!("CARGO_MANIFEST_DIR"), name);
+        let output = std::fs::read_to_string(&path).unwrap();
+        parse(&output).unwrap()
+    }
+
+    #[test]
+    fn parses_frozen_ssd_with_enhanced_erase() {
+        let identify = fixture("samsung_860_evo.txt");
+
+        assert_eq!(identify.model.as_deref(), Some("Samsung SSD 860 EVO 500GB"));
+        assert_eq!(identify.serial.as_deref(), Some("S3Z2NB0KA12345X"));
+        assert_eq!(identify.firmware.as_deref(), Some("RVT03B6Q"));
+        assert_eq!(identify.wwn.as_deref(), Some("0x5002538e40a1b2c3"));
+        assert_eq!(identify.lba48_sectors, Some(976_773_168));
+        assert_eq!(identify.logical_sector_size, Some(512));
+        assert_eq!(identify.physical_sector_size, Some(512));
+        assert_eq!(identify.rotation, Some(RotationRate::SolidState));
+
+        let security = &identify.security;
+        assert!(security.supported);
+        assert!(!security.enabled);
+        assert!(!security.locked);
+        assert!(security.frozen);
+        assert!(!security.count_expired);
+        assert!(security.enhanced_erase_supported);
+        assert_eq!(
+            security.erase_time,
+            Some(EraseTimeEstimate { minutes: 2, more_than: false })
+        );
+        assert_eq!(
+            security.enhanced_erase_time,
+            Some(EraseTimeEstimate { minutes: 8, more_than: false })
+        );
+
+        assert_eq!(identify.sanitize, AtaSanitize::default());
+        assert_eq!(
+            identify.trim,
+            TrimSupport { supported: true, deterministic_read: true, read_zeros: true }
+        );
+        assert_eq!(identify.hpa, FeatureState { supported: true, enabled: true });
+        assert_eq!(identify.dco, FeatureState { supported: true, enabled: true });
+
+        let capabilities = identify.erase_capabilities();
+        assert!(capabilities.ata_security_supported);
+        assert!(capabilities.ata_enhanced_erase_supported);
+        assert!(capabilities.ata_security_frozen);
+    }
+
+    #[test]
+    fn parses_hdd_without_enhanced_erase() {
+        let identify = fixture("wd_blue_wd10ezex.txt");
+
+        assert_eq!(identify.model.as_deref(), Some("WDC WD10EZEX-08WN4A0"));
+        assert_eq!(identify.serial.as_deref(), Some("WD-WCC6Y3KX1234"));
+        assert_eq!(identify.lba48_sectors, Some(1_953_525_168));
+        assert_eq!(identify.logical_sector_size, Some(512));
+        assert_eq!(identify.physical_sector_size, Some(4096));
+        assert_eq!(identify.rotation, Some(RotationRate::Rpm(7200)));
+
+        let security = &identify.security;
+        assert!(security.supported);
+        assert!(!security.frozen);
+        assert!(!security.enhanced_erase_supported);
+        assert_eq!(
+            security.erase_time,
+            Some(EraseTimeEstimate { minutes: 508, more_than: true })
+        );
+        assert_eq!(security.enhanced_erase_time, None);
+
+        assert_eq!(identify.trim, TrimSupport::default());
+        assert_eq!(identify.sanitize, AtaSanitize::default());
+        assert_eq!(identify.hpa, FeatureState { supported: true, enabled: true });
+        assert_eq!(identify.dco, FeatureState { supported: true, enabled: false });
+    }
+
+    #[test]
+    fn parses_sanitize_feature_set() {
+        let identify = fixture("intel_ssdsc2kb480g8.txt");
+
+        assert_eq!(identify.model.as_deref(), Some("INTEL SSDSC2KB480G8"));
+        assert_eq!(
+            identify.sanitize,
+            AtaSanitize {
+                supported: true,
+                block_erase: true,
+                crypto_scramble: true,
+                overwrite: false,
+                antifreeze_lock: true,
+            }
+        );
+        assert_eq!(
+            identify.trim,
+            TrimSupport { supported: true, deterministic_read: true, read_zeros: false }
+        );
+        assert!(!identify.security.frozen);
+        assert_eq!(
+            identify.security.enhanced_erase_time,
+            Some(EraseTimeEstimate { minutes: 4, more_than: false })
+        );
+    }
+
+    #[test]
+    fn parses_locked_drive_with_expired_count() {
+        let identify = fixture("crucial_mx500_locked.txt");
+
+        let security = &identify.security;
+        assert!(security.supported);
+        assert!(security.enabled);
+        assert!(security.locked);
+        assert!(!security.frozen);
+        assert!(security.count_expired);
+        assert!(identify.sanitize.block_erase);
+        assert!(!identify.sanitize.crypto_scramble);
+        assert_eq!(identify.hpa, FeatureState::default());
+    }
+
+    #[test]
+    fn decodes_identify_words() {
+        let mut words = [0u16; 256];
+        let put = |words: &mut [u16; 256], start: usize, text: &str| {
+            for (i, pair) in text.as_bytes().chunks(2).enumerate() {
+                words[start + i] = u16::from_be_bytes([pair[0], pair[1]]);
+            }
+        };
+        put(&mut words, 10, "    S3Z2NB0KA12345X ");
+        put(&mut words, 23, "RVT03B6Q");
+        put(&mut words, 27, "Samsung SSD 860 EVO 500GB               ");
+        words[0] = 0x0040;
+        words[59] = 0xb400;
+        words[69] = 0x4020;
+        words[82] = 0x0402;
+        words[83] = 0x0c00;
+        words[84] = 0x4100;
+        words[85] = 0x0400;
+        words[89] = 0x0001;
+        words[90] = 0x00ff;
+        words[100] = 0x6030;
+        words[101] = 0x3a38;
+        words[106] = 0x6003;
+        words[108..112].copy_from_slice(&[0x5002, 0x538e, 0x40a1, 0xb2c3]);
+        words[128] = 0x0029;
+        words[169] = 0x0001;
+        words[217] = 0x0001;
+
+        let identify = from_words(&words).unwrap();
+        assert_eq!(identify.model.as_deref(), Some("Samsung SSD 860 EVO 500GB"));
+        assert_eq!(identify.serial.as_deref(), Some("S3Z2NB0KA12345X"));
+        assert_eq!(identify.firmware.as_deref(), Some("RVT03B6Q"));
+        assert_eq!(identify.wwn.as_deref(), Some("0x5002538e40a1b2c3"));
+        assert_eq!(identify.lba48_sectors, Some(976_773_168));
+        assert_eq!(identify.logical_sector_size, Some(512));
+        assert_eq!(identify.physical_sector_size, Some(4096));
+        assert_eq!(identify.rotation, Some(RotationRate::SolidState));
+        assert!(identify.security.supported && identify.security.frozen);
+        assert!(identify.security.enhanced_erase_supported);
+        assert!(!identify.security.enabled);
+        assert_eq!(identify.security.erase_time, Some(EraseTimeEstimate { minutes: 2, more_than: false }));
+        assert_eq!(
+            identify.security.enhanced_erase_time,
+            Some(EraseTimeEstimate { minutes: 508, more_than: true })
+        );
+        assert_eq!(
+            identify.sanitize,
+            AtaSanitize {
+                supported: true,
+                block_erase: true,
+                crypto_scramble: true,
+                overwrite: false,
+                antifreeze_lock: true,
+            }
+        );
+        assert_eq!(identify.trim, TrimSupport { supported: true, deterministic_read: true, read_zeros: true });
+        assert_eq!(identify.hpa, FeatureState { supported: true, enabled: true });
+        assert_eq!(identify.dco, FeatureState { supported: true, enabled: false });
+
+        // ATAPIデバイスと空のデータは対象外
+        words[0] = 0x8580;
+        assert_eq!(from_words(&words), None);
+        assert_eq!(from_words(&[0u16; 256]), None);
+        assert_eq!(from_words(&[0u16; 16]), None);
+    }
+
+    #[test]
+    fn rejects_output_without_identify_data() {
+        let output = "\n/dev/sde:\nSG_IO: bad/missing sense data, sb[]:  70 00 05 00 00 00 00 0a\n";
+        assert_eq!(parse(output), None);
+        assert_eq!(parse(""), None);
+    }
+}
@@ -0,0 +1,737 @@
+/// # ATA PASS-THROUGHモジュール
+///
+/// このモジュールは、SCSI/ATA Translation（SAT）のATA PASS-THROUGH(16)コマンドでATAコマンドを発行します。
+/// `/dev/sdX`に対するSG_IOのioctlでCDBを送信し、CK_CONDを指定して返されるセンスデータの
+/// ATA Status Return記述子から、コマンド完了時のレジスタ（Status、Error、Count、LBA）を取得します。
+///
+/// CDBの送信は`AtaTransport`トレイトで抽象化しており、テストでは偽のトランスポートに置き換えます。
+use crate::ata_identify::{self, AtaIdentify};
+use crate::ata_sanitize::{self, SanitizeState, SanitizeStatus};
+use crate::device_backend::{self, AtaBackend};
+use crate::erase_method::SanitizeAction;
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+/// ATA PASS-THROUGH(16)のCDBの長さ
+pub const CDB_LEN: usize = 16;
+
+/// ATA PASS-THROUGH(16)の操作コード
+const ATA_PASS_THROUGH_16: u8 = 0x85;
+
+/// データ転送の単位（1セクタ）
+const SECTOR_SIZE: usize = 512;
+
+/// 消去以外のコマンドのタイムアウト
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// IDENTIFY DEVICE
+const IDENTIFY_DEVICE: u8 = 0xec;
+/// SECURITY SET PASSWORD
+const SECURITY_SET_PASSWORD: u8 = 0xf1;
+/// SECURITY UNLOCK
+const SECURITY_UNLOCK: u8 = 0xf2;
+/// SECURITY ERASE PREPARE
+const SECURITY_ERASE_PREPARE: u8 = 0xf3;
+/// SECURITY ERASE UNIT
+const SECURITY_ERASE_UNIT: u8 = 0xf4;
+/// SECURITY DISABLE PASSWORD
+const SECURITY_DISABLE_PASSWORD: u8 = 0xf6;
+/// SANITIZE DEVICE
+const SANITIZE_DEVICE: u8 = 0xb4;
+/// STANDBY IMMEDIATE
+const STANDBY_IMMEDIATE: u8 = 0xe0;
+
+/// SANITIZE DEVICEのFEATUREに指定するサブコマンド
+const SANITIZE_STATUS_EXT: u16 = 0x0000;
+const CRYPTO_SCRAMBLE_EXT: u16 = 0x0011;
+const BLOCK_ERASE_EXT: u16 = 0x0012;
+const OVERWRITE_EXT: u16 = 0x0014;
+
+/// 誤発行を防ぐためにLBAに指定するキー（ACS-3）
+const CRYPTO_SCRAMBLE_KEY: u64 = 0x4372_7970; // "Cryp"
+const BLOCK_ERASE_KEY: u64 = 0x426b_4572; // "BkEr"
+const OVERWRITE_KEY: u64 = 0x4f57 << 32; // "OW"
+
+/// Statusレジスタ: ERR
+const STATUS_ERR: u8 = 0x01;
+/// Statusレジスタ: DF（デバイスの故障）
+const STATUS_DF: u8 = 0x20;
+/// Errorレジスタ: ABRT（コマンドの中止）
+const ERROR_ABRT: u8 = 0x04;
+
+/// ATA PASS-THROUGHのプロトコル
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// データ転送なし
+    NonData = 3,
+    /// PIOでデバイスから読み出す
+    PioDataIn = 4,
+    /// PIOでデバイスに書き込む
+    PioDataOut = 5,
+}
+
+/// 発行するATAコマンド
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtaCommand {
+    /// コマンドの操作コード
+    pub command: u8,
+    /// FEATUREフィールド
+    pub features: u16,
+    /// COUNTフィールド
+    pub count: u16,
+    /// LBAフィールド（48ビット）
+    pub lba: u64,
+    /// DEVICEフィールド
+    pub device: u8,
+    /// データ転送のプロトコル
+    pub protocol: Protocol,
+    /// 48ビットのコマンド（EXT）か
+    pub extend: bool,
+}
+
+impl AtaCommand {
+    /// データ転送のない28ビットのコマンド
+    fn non_data(command: u8) -> Self {
+        AtaCommand {
+            command,
+            features: 0,
+            count: 0,
+            lba: 0,
+            device: 0,
+            protocol: Protocol::NonData,
+            extend: false,
+        }
+    }
+
+    /// 1セクタのデータを転送する28ビットのコマンド
+    fn pio(command: u8, protocol: Protocol) -> Self {
+        AtaCommand {
+            count: 1,
+            protocol,
+            ..Self::non_data(command)
+        }
+    }
+
+    /// SANITIZE DEVICEのサブコマンド
+    fn sanitize(features: u16, lba: u64, count: u16) -> Self {
+        AtaCommand {
+            command: SANITIZE_DEVICE,
+            features,
+            count,
+            lba,
+            device: 0x40,
+            protocol: Protocol::NonData,
+            extend: true,
+        }
+    }
+}
+
+/// コマンドに伴うデータ転送
+pub enum Transfer<'a> {
+    /// 転送なし
+    None,
+    /// デバイスから読み出す
+    FromDevice(&'a mut [u8]),
+    /// デバイスに書き込む
+    ToDevice(&'a [u8]),
+}
+
+/// ATA PASS-THROUGHのCDBをデバイスに送信するトランスポート
+pub trait AtaTransport: Send {
+    /// CDBを送信し、返されたセンスデータを取得する
+    ///
+    /// # 引数
+    ///
+    /// * `cdb` - ATA PASS-THROUGH(16)のCDB
+    /// * `transfer` - データ転送のバッファ
+    /// * `timeout` - コマンドのタイムアウト
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<Vec<u8>, String>` - センスデータ（返されなかった場合は空）、送信に失敗した場合は`Err`
+    fn pass_through(&mut self, cdb: &[u8; CDB_LEN], transfer: Transfer<'_>, timeout: Duration)
+        -> Result<Vec<u8>, String>;
+}
+
+/// コマンド完了時のATAレジスタ
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AtaRegisters {
+    /// Errorレジスタ
+    pub error: u8,
+    /// Statusレジスタ
+    pub status: u8,
+    /// Deviceレジスタ
+    pub device: u8,
+    /// COUNTフィールド
+    pub count: u16,
+    /// LBAフィールド
+    pub lba: u64,
+    /// 48ビットのフィールドの上位バイトまで取得できたか（固定形式のセンスデータでは取得できない場合がある）
+    pub complete: bool,
+}
+
+impl AtaRegisters {
+    /// コマンドがエラーで終了したか
+    pub fn failed(&self) -> bool {
+        self.status & (STATUS_ERR | STATUS_DF) != 0
+    }
+
+    /// エラーで終了した場合に`Err`を返す
+    fn check(self, name: &str) -> Result<Self, String> {
+        if !self.failed() {
+            return Ok(self);
+        }
+        let reason = if self.error & ERROR_ABRT != 0 { "コマンドが中止されました" } else { "エラーが発生しました" };
+        Err(format!(
+            "{}で{}（Status 0x{:02x}、Error 0x{:02x}）。",
+            name, reason, self.status, self.error
+        ))
+    }
+}
+
+/// ATA PASS-THROUGH(16)のCDBを作成する関数
+///
+/// CK_CONDを常に指定し、完了時のレジスタをセンスデータとして返すよう要求します。
+/// データを転送するコマンドは、COUNTフィールドで指定したセクタ数を転送します。
+pub fn build_cdb(command: &AtaCommand) -> [u8; CDB_LEN] {
+    let mut cdb = [0u8; CDB_LEN];
+    cdb[0] = ATA_PASS_THROUGH_16;
+    cdb[1] = (command.protocol as u8) << 1 | u8::from(command.extend);
+    // CK_COND(bit 5)、T_DIR(bit 3)、BYTE_BLOCK(bit 2)、T_LENGTH(bit 1:0)
+    cdb[2] = match command.protocol {
+        Protocol::NonData => 0x20,
+        Protocol::PioDataIn => 0x2e,
+        Protocol::PioDataOut => 0x26,
+    };
+    let [features_high, features_low] = command.features.to_be_bytes();
+    let [count_high, count_low] = command.count.to_be_bytes();
+    let lba = command.lba.to_le_bytes();
+    cdb[3] = features_high;
+    cdb[4] = features_low;
+    cdb[5] = count_high;
+    cdb[6] = count_low;
+    // 48ビットのLBAは上位と下位のバイトを交互に並べる
+    cdb[7] = lba[3];
+    cdb[8] = lba[0];
+    cdb[9] = lba[4];
+    cdb[10] = lba[1];
+    cdb[11] = lba[5];
+    cdb[12] = lba[2];
+    cdb[13] = command.device;
+    cdb[14] = command.command;
+    cdb
+}
+
+/// センスデータからコマンド完了時のレジスタを取得する関数
+///
+/// 記述子形式（72h/73h）はATA Status Return記述子（09h）から、
+/// 固定形式（70h/71h）はINFORMATIONとCOMMAND-SPECIFIC INFORMATIONのフィールドから取得します。
+///
+/// # 戻り値
+///
+/// * `Option<AtaRegisters>` - センスデータにATAのレジスタが含まれていない場合は`None`
+pub fn parse_sense(sense: &[u8]) -> Option<AtaRegisters> {
+    match sense.first()? & 0x7f {
+        0x72 | 0x73 => {
+            let end = (8 + usize::from(*sense.get(7)?)).min(sense.len());
+            let mut offset = 8;
+            while offset + 2 <= end {
+                let length = usize::from(sense[offset + 1]);
+                let descriptor = sense.get(offset..offset + 2 + length)?;
+                if descriptor[0] == 0x09 && length >= 12 {
+                    let lba = [
+                        descriptor[7],
+                        descriptor[9],
+                        descriptor[11],
+                        descriptor[6],
+                        descriptor[8],
+                        descriptor[10],
+                        0,
+                        0,
+                    ];
+                    return Some(AtaRegisters {
+                        error: descriptor[3],
+                        status: descriptor[13],
+                        device: descriptor[12],
+                        count: u16::from_be_bytes([descriptor[4], descriptor[5]]),
+                        lba: u64::from_le_bytes(lba),
+                        complete: true,
+                    });
+                }
+                offset += 2 + length;
+            }
+            None
+        }
+        0x70 | 0x71 => {
+            let fixed = sense.get(..12)?;
+            // バイト8のbit 6、bit 5はCOUNTとLBAの上位バイトが0でないことを示す
+            Some(AtaRegisters {
+                error: fixed[3],
+                status: fixed[4],
+                device: fixed[5],
+                count: u16::from(fixed[6]),
+                lba: u64::from_le_bytes([fixed[9], fixed[10], fixed[11], 0, 0, 0, 0, 0]),
+                complete: fixed[8] & 0x60 == 0,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// ATA PASS-THROUGHでATAコマンドを発行するバックエンド
+pub struct SatBackend<T: AtaTransport = SgIoTransport> {
+    transport: T,
+}
+
+impl SatBackend {
+    /// SG_IOでデバイスを開き、IDENTIFY DEVICEに応答することを確認する
+    pub fn open(device: &str) -> Result<Self, String> {
+        SatBackend::probe(SgIoTransport::open(device)?)
+    }
+}
+
+impl<T: AtaTransport> SatBackend<T> {
+    /// トランスポートを指定してバックエンドを作成する
+    pub fn new(transport: T) -> Self {
+        SatBackend { transport }
+    }
+
+    /// トランスポートを指定してバックエンドを作成し、IDENTIFY DEVICEに応答することを確認する
+    pub fn probe(transport: T) -> Result<Self, String> {
+        let mut backend = SatBackend::new(transport);
+        backend.identify()?;
+        Ok(backend)
+    }
+
+    /// ATAコマンドを発行し、完了時のレジスタを取得する
+    fn execute(&mut self, command: &AtaCommand, transfer: Transfer<'_>, timeout: Duration) -> Result<AtaRegisters, String> {
+        let sense = self.transport.pass_through(&build_cdb(command), transfer, timeout)?;
+        parse_sense(&sense).ok_or_else(|| {
+            format!(
+                "ATA PASS-THROUGHの結果が返されませんでした（センスデータ: {}）。",
+                sense.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ")
+            )
+        })
+    }
+
+    /// Securityのパスワードを書き込むコマンドを発行する
+    ///
+    /// データの先頭ワードのbit 0はユーザーパスワード（0）、bit 1はEnhanced Secure Eraseを示します。
+    /// パスワードは`hdparm`と同じく、文字列のバイト列をそのまま32バイトの領域に格納します。
+    fn security_command(&mut self, command: u8, password: &str, control: u16, timeout: Duration, name: &str) -> Result<(), String> {
+        let mut data = [0u8; SECTOR_SIZE];
+        data[..2].copy_from_slice(&control.to_le_bytes());
+        let bytes = password.as_bytes();
+        let length = bytes.len().min(32);
+        data[2..2 + length].copy_from_slice(&bytes[..length]);
+        self.execute(&AtaCommand::pio(command, Protocol::PioDataOut), Transfer::ToDevice(&data), timeout)?
+            .check(name)
+            .map(|_| ())
+    }
+}
+
+impl<T: AtaTransport> AtaBackend for SatBackend<T> {
+    fn name(&self) -> &'static str {
+        "ATA PASS-THROUGH"
+    }
+
+    fn identify(&mut self) -> Result<AtaIdentify, String> {
+        let mut data = [0u8; SECTOR_SIZE];
+        self.execute(
+            &AtaCommand::pio(IDENTIFY_DEVICE, Protocol::PioDataIn),
+            Transfer::FromDevice(&mut data),
+            COMMAND_TIMEOUT,
+        )?
+        .check("IDENTIFY DEVICE")?;
+        let words: Vec<u16> = data.chunks_exact(2).map(|w| u16::from_le_bytes([w[0], w[1]])).collect();
+        ata_identify::from_words(&words).ok_or_else(|| "IDENTIFY DEVICEのデータがATAデバイスのものではありません。".to_string())
+    }
+
+    fn security_set_password(&mut self, password: &str) -> Result<(), String> {
+        self.security_command(SECURITY_SET_PASSWORD, password, 0, COMMAND_TIMEOUT, "SECURITY SET PASSWORD")
+    }
+
+    fn security_erase(&mut self, password: &str, enhanced: bool, timeout: Duration) -> Result<(), String> {
+        // SECURITY ERASE UNITの直前にSECURITY ERASE PREPAREが必要
+        self.execute(&AtaCommand::non_data(SECURITY_ERASE_PREPARE), Transfer::None, COMMAND_TIMEOUT)?
+            .check("SECURITY ERASE PREPARE")?;
+        let control = if enhanced { 0x0002 } else { 0x0000 };
+        self.security_command(SECURITY_ERASE_UNIT, password, control, timeout, "SECURITY ERASE UNIT")
+    }
+
+    fn security_unlock(&mut self, password: &str) -> Result<(), String> {
+        self.security_command(SECURITY_UNLOCK, password, 0, COMMAND_TIMEOUT, "SECURITY UNLOCK")
+    }
+
+    fn security_disable(&mut self, password: &str) -> Result<(), String> {
+        self.security_command(SECURITY_DISABLE_PASSWORD, password, 0, COMMAND_TIMEOUT, "SECURITY DISABLE PASSWORD")
+    }
+
+    fn sanitize(&mut self, action: SanitizeAction) -> Result<(), String> {
+        let command = match action {
+            SanitizeAction::BlockErase => AtaCommand::sanitize(BLOCK_ERASE_EXT, BLOCK_ERASE_KEY, 0),
+            SanitizeAction::CryptoErase => AtaCommand::sanitize(CRYPTO_SCRAMBLE_EXT, CRYPTO_SCRAMBLE_KEY, 0),
+            // COUNTのbit 3:0は上書きのパス数（1回）
+            SanitizeAction::Overwrite => AtaCommand::sanitize(
+                OVERWRITE_EXT,
+                OVERWRITE_KEY | u64::from(ata_sanitize::OVERWRITE_PATTERN),
+                0x0001,
+            ),
+        };
+        self.execute(&command, Transfer::None, COMMAND_TIMEOUT)?
+            .check("SANITIZE DEVICE")
+            .map(|_| ())
+    }
+
+    fn sanitize_status(&mut self) -> Result<SanitizeStatus, String> {
+        let registers = self.execute(
+            &AtaCommand::sanitize(SANITIZE_STATUS_EXT, 0, 0),
+            Transfer::None,
+            COMMAND_TIMEOUT,
+        )?;
+        if !registers.complete {
+            return Err("SANITIZE STATUS EXTのCOUNTの上位バイトを取得できませんでした。".to_string());
+        }
+
+        // 最後のSanitizeが失敗した場合、SANITIZE STATUS EXTはABRTで終了する
+        if registers.error & ERROR_ABRT == 0 {
+            registers.check("SANITIZE STATUS EXT")?;
+        }
+
+        // COUNTのbit 15は最後のSanitizeがエラーなく完了したこと、bit 14は実行中、bit 13はfrozen状態を示す
+        let (state, description) = if registers.failed() {
+            (SanitizeState::Failed, "SD3 Sanitize Operation Failed")
+        } else if registers.count & 0x2000 != 0 {
+            (SanitizeState::Frozen, "SD1 Sanitize Frozen")
+        } else if registers.count & 0x4000 != 0 {
+            (SanitizeState::InProgress, "SD2 Sanitize operation In Process")
+        } else {
+            (SanitizeState::Idle, "SD0 Sanitize Idle")
+        };
+        Ok(SanitizeStatus {
+            state,
+            description: description.to_string(),
+            progress: (state == SanitizeState::InProgress).then_some((registers.lba & 0xffff) as u16),
+            last_succeeded: registers.count & 0x8000 != 0,
+        })
+    }
+
+    fn standby(&mut self) -> Result<(), String> {
+        self.execute(&AtaCommand::non_data(STANDBY_IMMEDIATE), Transfer::None, COMMAND_TIMEOUT)?
+            .check("STANDBY IMMEDIATE")
+            .map(|_| ())
+    }
+}
+
+/// SG_IOのioctl
+const SG_IO: libc::c_ulong = 0x2285;
+/// sg_io_hdrのデータ転送方向
+const SG_DXFER_NONE: libc::c_int = -1;
+const SG_DXFER_TO_DEV: libc::c_int = -2;
+const SG_DXFER_FROM_DEV: libc::c_int = -3;
+/// ホストアダプタのタイムアウト（DID_TIME_OUT）
+const DID_TIME_OUT: u16 = 0x03;
+/// ドライバの状態: タイムアウト（DRIVER_TIMEOUT）
+const DRIVER_TIMEOUT: u16 = 0x06;
+/// ドライバの状態: センスデータあり（DRIVER_SENSE）
+const DRIVER_SENSE: u16 = 0x08;
+/// センスデータのバッファの長さ
+const SENSE_LEN: usize = 64;
+
+/// Linuxの`struct sg_io_hdr`
+#[repr(C)]
+struct SgIoHdr {
+    interface_id: libc::c_int,
+    dxfer_direction: libc::c_int,
+    cmd_len: libc::c_uchar,
+    mx_sb_len: libc::c_uchar,
+    iovec_count: libc::c_ushort,
+    dxfer_len: libc::c_uint,
+    dxferp: *mut libc::c_void,
+    cmdp: *const libc::c_uchar,
+    sbp: *mut libc::c_uchar,
+    timeout: libc::c_uint,
+    flags: libc::c_uint,
+    pack_id: libc::c_int,
+    usr_ptr: *mut libc::c_void,
+    status: libc::c_uchar,
+    masked_status: libc::c_uchar,
+    msg_status: libc::c_uchar,
+    sb_len_wr: libc::c_uchar,
+    host_status: libc::c_ushort,
+    driver_status: libc::c_ushort,
+    resid: libc::c_int,
+    duration: libc::c_uint,
+    info: libc::c_uint,
+}
+
+/// SG_IOのioctlでCDBを送信するトランスポート
+pub struct SgIoTransport {
+    file: File,
+}
+
+impl SgIoTransport {
+    /// デバイスを開く
+    ///
+    /// `hdparm`と同じく読み取り専用で開きます（SG_IOの発行にはCAP_SYS_RAWIOが必要です）。
+    pub fn open(device: &str) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(device)
+            .map_err(|e| format!("{}を開けませんでした: {}", device, e))?;
+        Ok(SgIoTransport { file })
+    }
+}
+
+impl AtaTransport for SgIoTransport {
+    fn pass_through(&mut self, cdb: &[u8; CDB_LEN], transfer: Transfer<'_>, timeout: Duration)
+        -> Result<Vec<u8>, String> {
+        let (direction, pointer, length) = match transfer {
+            Transfer::None => (SG_DXFER_NONE, std::ptr::null_mut(), 0),
+            Transfer::FromDevice(data) => (SG_DXFER_FROM_DEV, data.as_mut_ptr().cast(), data.len()),
+            // 書き込むバッファはカーネルが読み出すだけで変更しない
+            Transfer::ToDevice(data) => (SG_DXFER_TO_DEV, data.as_ptr().cast_mut().cast(), data.len()),
+        };
+        let mut sense = [0u8; SENSE_LEN];
+        let mut header = SgIoHdr {
+            interface_id: libc::c_int::from(b'S'),
+            dxfer_direction: direction,
+            cmd_len: CDB_LEN as libc::c_uchar,
+            mx_sb_len: SENSE_LEN as libc::c_uchar,
+            iovec_count: 0,
+            dxfer_len: length as libc::c_uint,
+            dxferp: pointer,
+            cmdp: cdb.as_ptr(),
+            sbp: sense.as_mut_ptr(),
+            timeout: timeout.as_millis().min(u128::from(u32::MAX)) as libc::c_uint,
+            flags: 0,
+            pack_id: 0,
+            usr_ptr: std::ptr::null_mut(),
+            status: 0,
+            masked_status: 0,
+            msg_status: 0,
+            sb_len_wr: 0,
+            host_status: 0,
+            driver_status: 0,
+            resid: 0,
+            duration: 0,
+            info: 0,
+        };
+
+        // SAFETY: headerのポインタはこの呼び出しの間有効なCDB、センスデータ、転送バッファを指している
+        let ret = unsafe { libc::ioctl(self.file.as_raw_fd(), SG_IO, &mut header) };
+        if ret != 0 {
+            return Err(format!("SG_IOの発行に失敗しました: {}", std::io::Error::last_os_error()));
+        }
+        if header.host_status == DID_TIME_OUT || header.driver_status & 0x0f == DRIVER_TIMEOUT {
+            return Err(device_backend::timeout_message(timeout));
+        }
+        if header.host_status != 0 || !matches!(header.driver_status & 0x0f, 0 | DRIVER_SENSE) {
+            return Err(format!(
+                "SG_IOでエラーが発生しました（host_status 0x{:02x}、driver_status 0x{:02x}）。",
+                header.host_status, header.driver_status
+            ));
+        }
+        Ok(sense[..usize::from(header.sb_len_wr).min(SENSE_LEN)].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// 送信したCDBとデータを記録し、用意した応答を返す偽のトランスポート
+    #[derive(Default)]
+    struct FakeTransport {
+        cdbs: Vec<[u8; CDB_LEN]>,
+        written: Vec<Vec<u8>>,
+        /// 応答（読み出すデータとセンスデータ）
+        responses: VecDeque<(Vec<u8>, Vec<u8>)>,
+    }
+
+    impl FakeTransport {
+        fn respond(mut self, data: Vec<u8>, sense: Vec<u8>) -> Self {
+            self.responses.push_back((data, sense));
+            self
+        }
+    }
+
+    impl AtaTransport for FakeTransport {
+        fn pass_through(&mut self, cdb: &[u8; CDB_LEN], transfer: Transfer<'_>, _timeout: Duration)
+            -> Result<Vec<u8>, String> {
+            self.cdbs.push(*cdb);
+            let (data, sense) = self.responses.pop_front().ok_or("応答がありません")?;
+            match transfer {
+                Transfer::FromDevice(buffer) => buffer[..data.len()].copy_from_slice(&data),
+                Transfer::ToDevice(buffer) => self.written.push(buffer.to_vec()),
+                Transfer::None => {}
+            }
+            Ok(sense)
+        }
+    }
+
+    /// ATA Status Return記述子を含む記述子形式のセンスデータ
+    fn status_sense(error: u8, status: u8, count: u16, lba: u64) -> Vec<u8> {
+        let l = lba.to_le_bytes();
+        let [count_high, count_low] = count.to_be_bytes();
+        vec![
+            0x72, 0x01, 0x00, 0x1d, 0, 0, 0, 14, 0x09, 0x0c, 0x01, error, count_high, count_low, l[3], l[0], l[4],
+            l[1], l[5], l[2], 0x40, status,
+        ]
+    }
+
+    fn ok_sense() -> Vec<u8> {
+        status_sense(0, 0x50, 0, 0)
+    }
+
+    #[test]
+    fn builds_pass_through_cdbs() {
+        let identify = build_cdb(&AtaCommand::pio(IDENTIFY_DEVICE, Protocol::PioDataIn));
+        assert_eq!(identify, [0x85, 0x08, 0x2e, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0xec, 0]);
+
+        let overwrite = build_cdb(&AtaCommand::sanitize(OVERWRITE_EXT, OVERWRITE_KEY | 0x1234_5678, 1));
+        assert_eq!(
+            overwrite,
+            [0x85, 0x07, 0x20, 0x00, 0x14, 0x00, 0x01, 0x12, 0x78, 0x57, 0x56, 0x4f, 0x34, 0x40, 0xb4, 0]
+        );
+    }
+
+    #[test]
+    fn parses_descriptor_and_fixed_sense() {
+        let registers = parse_sense(&status_sense(0x04, 0x51, 0x4000, 0x8000)).unwrap();
+        assert_eq!(registers.error, 0x04);
+        assert_eq!(registers.status, 0x51);
+        assert_eq!(registers.count, 0x4000);
+        assert_eq!(registers.lba, 0x8000);
+        assert!(registers.failed() && registers.complete);
+
+        let fixed = [0x70, 0, 0x01, 0x00, 0x50, 0x40, 0x01, 0, 0x40, 0x34, 0x12, 0x00];
+        let registers = parse_sense(&fixed).unwrap();
+        assert_eq!(registers.count, 1);
+        assert_eq!(registers.lba, 0x1234);
+        assert!(!registers.failed());
+        assert!(!registers.complete);
+
+        // USB変換アダプタがATA PASS-THROUGHに対応していない場合（ILLEGAL REQUEST、記述子なし）
+        assert_eq!(parse_sense(&[0x72, 0x05, 0x20, 0x00, 0, 0, 0, 0]), None);
+        assert_eq!(parse_sense(&[]), None);
+    }
+
+    #[test]
+    fn identifies_through_transport() {
+        let mut data = vec![0u8; SECTOR_SIZE];
+        for (i, pair) in b"WDC WD10EZEX-08WN4A0                    ".chunks(2).enumerate() {
+            data[54 + i * 2] = pair[1];
+            data[55 + i * 2] = pair[0];
+        }
+        data[128 * 2] = 0x01;
+        data[217 * 2..217 * 2 + 2].copy_from_slice(&7200u16.to_le_bytes());
+
+        let mut backend = SatBackend::new(FakeTransport::default().respond(data, ok_sense()));
+        let identify = backend.identify().unwrap();
+        assert_eq!(identify.model.as_deref(), Some("WDC WD10EZEX-08WN4A0"));
+        assert!(identify.security.supported);
+        assert_eq!(identify.rotation, Some(ata_identify::RotationRate::Rpm(7200)));
+        assert_eq!(backend.transport.cdbs[0][14], IDENTIFY_DEVICE);
+    }
+
+    #[test]
+    fn issues_erase_prepare_before_enhanced_erase_unit() {
+        let transport = FakeTransport::default()
+            .respond(vec![], ok_sense())
+            .respond(vec![], ok_sense());
+        let mut backend = SatBackend::new(transport);
+        backend.security_erase("p4ssw0rd", true, Duration::from_secs(600)).unwrap();
+
+        let transport = &backend.transport;
+        assert_eq!(transport.cdbs[0][14], SECURITY_ERASE_PREPARE);
+        assert_eq!(transport.cdbs[0][2], 0x20);
+        assert_eq!(transport.cdbs[1][14], SECURITY_ERASE_UNIT);
+        assert_eq!(transport.cdbs[1][2], 0x26);
+        let data = &transport.written[0];
+        assert_eq!(data.len(), SECTOR_SIZE);
+        assert_eq!(&data[..2], &[0x02, 0x00]);
+        assert_eq!(&data[2..10], b"p4ssw0rd");
+        assert!(data[10..34].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn reports_aborted_commands() {
+        let transport = FakeTransport::default().respond(vec![], status_sense(0x04, 0x51, 0, 0));
+        let mut backend = SatBackend::new(transport);
+        let error = backend.security_set_password("p4ssw0rd").unwrap_err();
+        assert!(error.contains("SECURITY SET PASSWORD"));
+        assert!(error.contains("中止"));
+
+        // センスデータが返されない場合はATA PASS-THROUGHに対応していない
+        let mut backend = SatBackend::new(FakeTransport::default().respond(vec![], vec![]));
+        assert!(backend.standby().is_err());
+    }
+
+    #[test]
+    fn reads_sanitize_status_registers() {
+        let transport = FakeTransport::default()
+            .respond(vec![], status_sense(0, 0x50, 0x4000, 0x5e3c))
+            .respond(vec![], status_sense(0, 0x50, 0x8000, 0))
+            .respond(vec![], status_sense(0x04, 0x51, 0, 0x01))
+            .respond(vec![], vec![0x70, 0, 0x01, 0, 0x50, 0x40, 0, 0, 0x40, 0, 0, 0]);
+        let mut backend = SatBackend::new(transport);
+
+        let status = backend.sanitize_status().unwrap();
+        assert_eq!(status.state, SanitizeState::InProgress);
+        assert_eq!(status.progress, Some(0x5e3c));
+        assert!(!status.last_succeeded);
+
+        let status = backend.sanitize_status().unwrap();
+        assert_eq!(status.state, SanitizeState::Idle);
+        assert!(status.last_succeeded);
+
+        let status = backend.sanitize_status().unwrap();
+        assert_eq!(status.state, SanitizeState::Failed);
+
+        // 固定形式でCOUNTの上位バイトが失われた場合は状態を判断しない
+        assert!(backend.sanitize_status().is_err());
+        assert!(backend.transport.cdbs.iter().all(|cdb| cdb[14] == SANITIZE_DEVICE && cdb[4] == 0));
+    }
+
+    /// CDBに並べられた48ビットのLBAを復元する
+    fn cdb_lba(cdb: &[u8; CDB_LEN]) -> u64 {
+        u64::from_le_bytes([cdb[8], cdb[10], cdb[12], cdb[7], cdb[9], cdb[11], 0, 0])
+    }
+
+    #[test]
+    fn places_sanitize_keys_in_lba() {
+        let cases = [
+            (SanitizeAction::CryptoErase, 0x11, 0x4372_7970, 0),
+            (SanitizeAction::BlockErase, 0x12, 0x426b_4572, 0),
+            (
+                SanitizeAction::Overwrite,
+                0x14,
+                0x4f57_0000_0000 | u64::from(ata_sanitize::OVERWRITE_PATTERN),
+                1,
+            ),
+        ];
+        for (action, features, lba, count) in cases {
+            let mut backend = SatBackend::new(FakeTransport::default().respond(vec![], ok_sense()));
+            backend.sanitize(action).unwrap();
+
+            let cdb = backend.transport.cdbs[0];
+            assert_eq!(cdb[14], SANITIZE_DEVICE, "{:?}", action);
+            assert_eq!(u16::from_be_bytes([cdb[3], cdb[4]]), features, "{:?}", action);
+            assert_eq!(u16::from_be_bytes([cdb[5], cdb[6]]), count, "{:?}", action);
+            assert_eq!(cdb_lba(&cdb), lba, "{:?}", action);
+        }
+
+        // ACS-3のキーはLBA 31:0に格納したASCII文字列
+        let cdb = build_cdb(&AtaCommand::sanitize(CRYPTO_SCRAMBLE_EXT, CRYPTO_SCRAMBLE_KEY, 0));
+        assert_eq!((cdb_lba(&cdb) as u32).to_be_bytes(), *b"Cryp");
+        let cdb = build_cdb(&AtaCommand::sanitize(BLOCK_ERASE_EXT, BLOCK_ERASE_KEY, 0));
+        assert_eq!((cdb_lba(&cdb) as u32).to_be_bytes(), *b"BkEr");
+        let cdb = build_cdb(&AtaCommand::sanitize(OVERWRITE_EXT, OVERWRITE_KEY, 1));
+        assert_eq!(((cdb_lba(&cdb) >> 32) as u16).to_be_bytes(), *b"OW");
+    }
+}
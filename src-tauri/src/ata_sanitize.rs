@@ -0,0 +1,329 @@
+/// # ATA Sanitizeモジュール
+///
+/// このモジュールは、ATA Sanitize機能セット（BLOCK ERASE、CRYPTO SCRAMBLE、OVERWRITE）による
+/// 消去を実行します。コマンドはATA PASS-THROUGH、使用できない場合は`hdparm`で発行します。
+/// Sanitizeはドライブ内部でバックグラウンドに実行されるため、コマンドの発行後は
+/// SANITIZE STATUS EXTで状態をポーリングし、進捗を進捗イベントとして送信します。
+///
+/// Sanitizeは開始後に中断できず、電源を入れ直してもドライブが処理を再開します。
+/// 失敗した場合、ドライブは再度Sanitizeが成功するまで失敗状態（SD3）のまま残ります。
+use crate::cancellation::DiskControl;
+use crate::device_backend::AtaBackend;
+use crate::erase_method::{EraseError, EraseMethod, SanitizeAction, SanitizeReport};
+use crate::logger::log_message;
+use crate::progress::ProgressReporter;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// OVERWRITEで書き込むパターン（32ビット）
+pub const OVERWRITE_PATTERN: u32 = 0x0000_0000;
+
+/// Sanitizeの状態を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 進捗メッセージを送信する間隔
+const REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 状態の取得に連続して失敗した場合に諦める回数
+const STATUS_ERROR_LIMIT: u32 = 30;
+
+/// SANITIZE STATUS EXTが報告する進捗の最大値（100%に相当）
+const PROGRESS_MAX: u64 = 0xffff;
+
+/// Sanitizeの状態（ACS-3のSD0〜SD4）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SanitizeState {
+    /// SD0: 実行していない
+    Idle,
+    /// SD1: Sanitizeがfrozen状態（電源を入れ直すまで実行できない）
+    Frozen,
+    /// SD2: 実行中
+    InProgress,
+    /// SD3: 失敗した
+    Failed,
+    /// SD4: 成功した
+    Succeeded,
+}
+
+/// SANITIZE STATUS EXTまたは`hdparm --sanitize-status`で取得したSanitizeの状態
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SanitizeStatus {
+    /// 状態
+    pub state: SanitizeState,
+    /// 状態の説明（hdparmの表示に合わせる、例: SD2 Sanitize operation In Process）
+    pub description: String,
+    /// 実行中の場合の進捗（0〜0xffff）
+    pub progress: Option<u16>,
+    /// 最後のSanitizeがエラーなく完了したか
+    pub last_succeeded: bool,
+}
+
+impl SanitizeStatus {
+    /// 進捗率（%）
+    fn percent(&self) -> Option<u64> {
+        self.progress.map(|p| u64::from(p) * 100 / PROGRESS_MAX)
+    }
+}
+
+/// ATA Sanitizeを実行する関数
+///
+/// # 引数
+///
+/// * `device` - 消去対象のデバイス名（例: /dev/sda）
+/// * `backend` - ATAコマンドを発行するバックエンド
+/// * `size` - ディスクのサイズ（バイト、進捗の表示に使用する）
+/// * `action` - Sanitizeの操作
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
+/// # 戻り値
+///
+/// * `Result<(), EraseError>` - 成功時は`Ok(())`、失敗時はドライブがSanitizeを受け付けたかどうかとエラーメッセージを含む`Err`
+pub fn sanitize(
+    device: &str,
+    mut backend: Box<dyn AtaBackend>,
+    size: u64,
+    action: SanitizeAction,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let method = EraseMethod::AtaSanitize { action };
+    reporter.set_method(method.name(), 1, size);
+    reporter.start_pass(1);
+
+    precheck(device, backend.as_mut(), action).inspect_err(|e| log_message("ATA Sanitize", "中止", e))
+        .map_err(EraseError::NotStarted)?;
+
+    // Sanitizeは開始すると中断できない
+    let _guard = control
+        .enter_non_interruptible(method.name())
+        .map_err(EraseError::NotStarted)?;
+    backend.sanitize(action).map_err(|e| {
+        let message = format!("{}の{}を開始できませんでした: {}", device, method.name(), e);
+        log_message("ATA Sanitize", "失敗", &message);
+        // ドライブがコマンドを拒否した場合は消去を開始していない
+        EraseError::NotStarted(message)
+    })?;
+    log_message(
+        "ATA Sanitize",
+        "開始",
+        &format!("{}の{}を開始しました（{}）。", device, method.name(), backend.name()),
+    );
+
+    let last = wait_for_completion(device, size, backend.as_mut(), reporter)
+        .map_err(EraseError::Started)?;
+    let report = SanitizeReport {
+        method,
+        completed: last.state == SanitizeState::Succeeded
+            || (last.state == SanitizeState::Idle && last.last_succeeded),
+        state: last.description.clone(),
+        failure_persists: last.state == SanitizeState::Failed,
+    };
+    reporter.record_sanitize(&report);
+
+    if !report.completed {
+        let message = if report.failure_persists {
+            format!(
+                "{}の{}が失敗しました（{}）。ドライブは電源を入れ直しても失敗状態のまま残るため、\
+                 再度Sanitizeを実行して成功させる必要があります。",
+                device,
+                method.name(),
+                last.description
+            )
+        } else {
+            format!(
+                "{}の{}が正常に完了したことを確認できませんでした（{}）。",
+                device,
+                method.name(),
+                last.description
+            )
+        };
+        log_message("ATA Sanitize", "失敗", &message);
+        return Err(EraseError::Started(message));
+    }
+
+    log_message(
+        "ATA Sanitize",
+        "成功",
+        &format!("{}の{}が正常に完了しました。", device, method.name()),
+    );
+    Ok(())
+}
+
+/// ドライブの対応状況と現在のSanitizeの状態から実行できるかを確認する関数
+fn precheck(device: &str, backend: &mut dyn AtaBackend, action: SanitizeAction) -> Result<(), String> {
+    let sanitize = backend.identify()?.sanitize;
+    let supported = match action {
+        SanitizeAction::BlockErase => sanitize.block_erase,
+        SanitizeAction::CryptoErase => sanitize.crypto_scramble,
+        SanitizeAction::Overwrite => sanitize.overwrite,
+    };
+    if !sanitize.supported || !supported {
+        return Err(format!(
+            "{}は{}に対応していません。",
+            device,
+            EraseMethod::AtaSanitize { action }.name()
+        ));
+    }
+
+    let status = backend
+        .sanitize_status()
+        .map_err(|e| format!("{}のSanitizeの状態を取得できませんでした: {}", device, e))?;
+    match status.state {
+        SanitizeState::Frozen => Err(format!(
+            "{}のSanitizeがfrozen状態です。ドライブの電源を入れ直してから再度実行してください。",
+            device
+        )),
+        SanitizeState::InProgress => Err(format!(
+            "{}では既にSanitizeが実行中です（{}%）。完了してから再度実行してください。",
+            device,
+            status.percent().unwrap_or(0)
+        )),
+        SanitizeState::Failed => {
+            // 失敗状態は新たなSanitizeの成功で解除される
+            log_message(
+                "ATA Sanitize",
+                "情報",
+                &format!("{}は前回のSanitizeが失敗した状態です。再度Sanitizeを実行します。", device),
+            );
+            Ok(())
+        }
+        SanitizeState::Idle | SanitizeState::Succeeded => Ok(()),
+    }
+}
+
+/// Sanitizeが終了するまで状態をポーリングし、進捗を通知する関数
+///
+/// # 戻り値
+///
+/// * `Result<SanitizeStatus, String>` - 終了後の状態、状態を取得できなくなった場合は`Err`
+fn wait_for_completion(
+    device: &str,
+    size: u64,
+    backend: &mut dyn AtaBackend,
+    reporter: &mut ProgressReporter,
+) -> Result<SanitizeStatus, String> {
+    let started = Instant::now();
+    let mut last_report: Option<Instant> = None;
+    let mut errors = 0;
+    loop {
+        let status = match backend.sanitize_status() {
+            Ok(status) => {
+                errors = 0;
+                status
+            }
+            Err(e) => {
+                errors += 1;
+                if errors >= STATUS_ERROR_LIMIT {
+                    return Err(format!(
+                        "{}のSanitizeの状態を取得できません: {}。ドライブはSanitizeを続けている可能性があります。",
+                        device, e
+                    ));
+                }
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+        };
+
+        if status.state != SanitizeState::InProgress {
+            return Ok(status);
+        }
+
+        let progress = u64::from(status.progress.unwrap_or(0));
+        reporter.update((size as u128 * progress as u128 / PROGRESS_MAX as u128) as u64);
+        if last_report.is_none_or(|t| t.elapsed() >= REPORT_INTERVAL) {
+            reporter.report_message(&format!(
+                "{}をSanitize中です（{}%、経過 {}分）。",
+                device,
+                status.percent().unwrap_or(0),
+                started.elapsed().as_secs() / 60
+            ));
+            last_report = Some(Instant::now());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// `hdparm --sanitize-status`の出力を解析する関数
+///
+/// # 引数
+///
+/// * `output` - `hdparm --sanitize-status`の標準出力
+///
+/// # 戻り値
+///
+/// * `Option<SanitizeStatus>` - 状態の行が見つからない場合は`None`
+pub fn parse_status(output: &str) -> Option<SanitizeStatus> {
+    let mut status: Option<SanitizeStatus> = None;
+    let mut progress = None;
+    let mut last_succeeded = false;
+
+    for line in output.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("State:") {
+            let description = value.trim().to_string();
+            let state = match description.strip_prefix("SD").and_then(|s| s.chars().next()) {
+                Some('0') => SanitizeState::Idle,
+                Some('1') => SanitizeState::Frozen,
+                Some('2') => SanitizeState::InProgress,
+                Some('3') => SanitizeState::Failed,
+                Some('4') => SanitizeState::Succeeded,
+                _ => continue,
+            };
+            status = Some(SanitizeStatus {
+                state,
+                description,
+                progress: None,
+                last_succeeded: false,
+            });
+        } else if let Some(value) = line.strip_prefix("Progress:") {
+            // 例: 0x5e3c (36%)
+            progress = value
+                .split_whitespace()
+                .next()
+                .and_then(|v| v.strip_prefix("0x"))
+                .and_then(|v| u16::from_str_radix(v, 16).ok());
+        } else if line.contains("Completed Without Error") {
+            last_succeeded = true;
+        }
+    }
+
+    status.map(|s| SanitizeStatus {
+        progress,
+        last_succeeded,
+        ..s
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_in_progress_status() {
+        let output = "\n/dev/sdb:\nIssuing SANITIZE_STATUS command\nSanitize status:\n    State:    SD2 Sanitize operation In Process\n    Progress: 0x5e3c (36%)\n";
+        let status = parse_status(output).unwrap();
+        assert_eq!(status.state, SanitizeState::InProgress);
+        assert_eq!(status.description, "SD2 Sanitize operation In Process");
+        assert_eq!(status.progress, Some(0x5e3c));
+        assert_eq!(status.percent(), Some(36));
+        assert!(!status.last_succeeded);
+    }
+
+    #[test]
+    fn parses_completed_status() {
+        let output = "\n/dev/sdb:\nIssuing SANITIZE_STATUS command\nSanitize status:\n    State:    SD0 Sanitize Idle\n    Last Sanitize Operation Completed Without Error\n";
+        let status = parse_status(output).unwrap();
+        assert_eq!(status.state, SanitizeState::Idle);
+        assert_eq!(status.progress, None);
+        assert!(status.last_succeeded);
+    }
+
+    #[test]
+    fn parses_failed_and_frozen_states() {
+        let failed = parse_status("Sanitize status:\n    State:    SD3 Sanitize Operation Failed\n").unwrap();
+        assert_eq!(failed.state, SanitizeState::Failed);
+        let frozen = parse_status("Sanitize status:\n    State:    SD1 Sanitize Frozen\n").unwrap();
+        assert_eq!(frozen.state, SanitizeState::Frozen);
+        assert!(parse_status("SG_IO: bad/missing sense data").is_none());
+    }
+}
@@ -0,0 +1,443 @@
+/// # ATA Secure Eraseモジュール
+///
+/// このモジュールは、ATA Security機能セットによるSecure Eraseを次の手順で実行します。
+///
+/// 1. 事前確認: IDENTIFY DEVICEでSecurityの状態（frozen、ロック、パスワード設定済み）を確認する
+/// 2. パスワード設定: 消去ごとに生成した一時パスワードを設定する
+/// 3. 消去: ドライブが報告する所要時間をもとにしたタイムアウトでSECURITY ERASE UNITを実行する
+/// 4. 事後確認: 消去後にSecurityが無効に戻ったことを確認する
+///
+/// コマンドはATA PASS-THROUGH、使用できない場合は`hdparm`で発行します（`device_backend`モジュール）。
+/// パスワード設定以降に失敗した場合は、ドライブがロックされたまま残らないよう必ずSECURITY DISABLE PASSWORDを試みます。
+/// 解除できなかった場合は、手動で解除できるよう一時パスワードをエラーメッセージとログに含めます。
+use crate::ata_identify::AtaSecurity;
+use crate::cancellation::DiskControl;
+use crate::csprng;
+use crate::device_backend::AtaBackend;
+use crate::erase_method::{EraseError, EraseMethod};
+use crate::logger::log_message;
+use crate::progress::ProgressReporter;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 一時パスワードの文字数
+const PASSWORD_LEN: usize = 16;
+
+/// ドライブが所要時間を報告しない場合のタイムアウト
+const DEFAULT_ERASE_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// ドライブが報告する所要時間に加える余裕
+const ERASE_TIMEOUT_MARGIN: Duration = Duration::from_secs(30 * 60);
+
+/// 消去の完了を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 進捗メッセージを送信する間隔
+const REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Secure Eraseの手順の段階
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    /// 一時パスワードを設定している
+    SettingPassword,
+    /// SECURITY ERASE UNITを実行している
+    Erasing,
+    /// 消去後の状態を確認している
+    Verifying,
+}
+
+impl Stage {
+    /// ログに使用する段階の名前
+    fn label(&self) -> &'static str {
+        match self {
+            Stage::SettingPassword => "パスワード設定",
+            Stage::Erasing => "消去",
+            Stage::Verifying => "事後確認",
+        }
+    }
+}
+
+/// ATA Secure Eraseを実行する関数
+///
+/// # 引数
+///
+/// * `device` - 消去対象のデバイス名（例: /dev/sda）
+/// * `backend` - ATAコマンドを発行するバックエンド
+/// * `enhanced` - Enhanced Secure Eraseを実行するか
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
+/// # 戻り値
+///
+/// * `Result<(), EraseError>` - 成功時は`Ok(())`、失敗時はパスワードを設定したかどうかとエラーメッセージを含む`Err`
+pub fn secure_erase(
+    device: &str,
+    mut backend: Box<dyn AtaBackend>,
+    enhanced: bool,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let method = EraseMethod::AtaSecureErase { enhanced };
+    reporter.set_method(method.name(), 1, 0);
+    reporter.start_pass(1);
+
+    let before = backend.identify().map_err(EraseError::NotStarted)?;
+    precheck(device, &before.security, enhanced)
+        .inspect_err(|e| log_message("ATA Secure Erase", "中止", e))
+        .map_err(EraseError::NotStarted)?;
+    let timeout = erase_timeout(&before.security, enhanced);
+
+    // パスワード設定以降はドライブがロック状態になるため中断できない
+    let _guard = control
+        .enter_non_interruptible(method.name())
+        .map_err(EraseError::NotStarted)?;
+    let password = csprng::temporary_password(PASSWORD_LEN).map_err(EraseError::NotStarted)?;
+
+    let mut stage = Stage::SettingPassword;
+    let result = (|| {
+        backend.security_set_password(&password)?;
+
+        stage = Stage::Erasing;
+        run_erase(device, backend.as_mut(), &password, enhanced, timeout, reporter)?;
+
+        // 消去が成功するとドライブはSecurityを無効に戻す
+        stage = Stage::Verifying;
+        let after = backend.identify()?;
+        if after.security.enabled || after.security.locked {
+            return Err("消去後もSecurityが有効なままです。".to_string());
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let message = format!("{}のATA Secure Erase（{}）に失敗しました: {}", device, stage.label(), e);
+        log_message("ATA Secure Erase", "失敗", &message);
+        // ドライブがロックされたまま残らないよう、どの段階で失敗してもパスワードの解除を試みる
+        return Err(match disable_security(device, backend.as_mut(), &password) {
+            // パスワードの設定で失敗し、解除も済んでいればドライブは消去前の状態のまま
+            Ok(()) if stage == Stage::SettingPassword => {
+                EraseError::NotStarted(format!("{}（パスワードは解除済みです）", message))
+            }
+            Ok(()) => EraseError::Started(format!("{}（パスワードは解除済みです）", message)),
+            Err(disable_error) => EraseError::Started(format!(
+                "{}。パスワードの解除にも失敗したため、ドライブがロックされている可能性があります: {}。\
+                 `hdparm --user-master u --security-disable {} {}`で解除してください。",
+                message, disable_error, password, device
+            )),
+        });
+    }
+
+    log_message(
+        "ATA Secure Erase",
+        "成功",
+        &format!("{}の{}が正常に完了しました。", device, method.name()),
+    );
+    Ok(())
+}
+
+/// Security機能の状態からSecure Eraseを実行できるかを確認する関数
+fn precheck(device: &str, security: &AtaSecurity, enhanced: bool) -> Result<(), String> {
+    if !security.supported {
+        return Err(format!("{}はATA Security機能セットに対応していません。", device));
+    }
+    if enhanced && !security.enhanced_erase_supported {
+        return Err(format!("{}はEnhanced Secure Eraseに対応していません。", device));
+    }
+    if security.frozen {
+        return Err(format!(
+            "{}のATA Securityがfrozen状態です。`systemctl suspend`でスリープしてから復帰するか、\
+             ドライブの電源ケーブルを抜き差し（ホットプラグ）してfrozen状態を解除してから再度実行してください。",
+            device
+        ));
+    }
+    if security.count_expired {
+        return Err(format!(
+            "{}はパスワードの試行回数の上限に達しています。電源を入れ直してから再度実行してください。",
+            device
+        ));
+    }
+    if security.locked {
+        return Err(format!(
+            "{}はATA Securityでロックされています。設定されたパスワードで解除してから再度実行してください。",
+            device
+        ));
+    }
+    if security.enabled {
+        return Err(format!(
+            "{}には既にATA Securityのパスワードが設定されています。パスワードを解除してから再度実行してください。",
+            device
+        ));
+    }
+    Ok(())
+}
+
+/// ドライブが報告する所要時間から消去のタイムアウトを決める関数
+fn erase_timeout(security: &AtaSecurity, enhanced: bool) -> Duration {
+    let estimate = if enhanced { security.enhanced_erase_time } else { security.erase_time };
+    match estimate {
+        // 報告できる上限を超える場合は実際の所要時間が分からないため、見積もりの4倍まで待つ
+        Some(estimate) if estimate.more_than => {
+            Duration::from_secs(u64::from(estimate.minutes) * 60 * 4) + ERASE_TIMEOUT_MARGIN
+        }
+        Some(estimate) => {
+            Duration::from_secs(u64::from(estimate.minutes.max(1)) * 60 * 2) + ERASE_TIMEOUT_MARGIN
+        }
+        None => DEFAULT_ERASE_TIMEOUT,
+    }
+}
+
+/// SECURITY ERASE UNITを別スレッドで実行し、完了するまで進捗メッセージを送信する関数
+///
+/// 消去コマンドは完了するか`timeout`が経過するまで戻らないため、待っている間も経過時間を通知します。
+fn run_erase(
+    device: &str,
+    backend: &mut dyn AtaBackend,
+    password: &str,
+    enhanced: bool,
+    timeout: Duration,
+    reporter: &mut ProgressReporter,
+) -> Result<(), String> {
+    thread::scope(|scope| {
+        let erase = scope.spawn(move || backend.security_erase(password, enhanced, timeout));
+
+        let started = Instant::now();
+        let mut last_report: Option<Instant> = None;
+        while !erase.is_finished() {
+            if last_report.is_none_or(|t| t.elapsed() >= REPORT_INTERVAL) {
+                reporter.report_message(&format!(
+                    "{}をSecure Erase中です（経過 {}分、タイムアウト {}分）。",
+                    device,
+                    started.elapsed().as_secs() / 60,
+                    timeout.as_secs() / 60
+                ));
+                last_report = Some(Instant::now());
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        erase
+            .join()
+            .unwrap_or_else(|_| Err("消去を実行するスレッドが異常終了しました。".to_string()))
+    })
+}
+
+/// Securityのパスワードを解除する関数
+fn disable_security(device: &str, backend: &mut dyn AtaBackend, password: &str) -> Result<(), String> {
+    let result = backend.security_disable(password).or_else(|_| {
+        // ロックされている場合はアンロックしてから解除する
+        backend.security_unlock(password)?;
+        backend.security_disable(password)
+    });
+
+    match result {
+        Ok(()) => {
+            log_message("ATA Secure Erase", "情報", &format!("{}のパスワードを解除しました。", device));
+            Ok(())
+        }
+        Err(e) => {
+            log_message(
+                "ATA Secure Erase",
+                "エラー",
+                &format!("{}のパスワードを解除できませんでした: {}", device, e),
+            );
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ata_identify::{AtaIdentify, EraseTimeEstimate};
+    use crate::cancellation::CancelRegistry;
+    use crate::logger;
+    use std::sync::{Arc, Mutex};
+
+    const DEVICE: &str = "/dev/sdz";
+
+    /// 発行したコマンドを記録し、指定したコマンドを失敗させる偽のドライブ
+    struct FakeDrive {
+        calls: Arc<Mutex<Vec<String>>>,
+        /// 設定された一時パスワード
+        password: Arc<Mutex<Option<String>>>,
+        /// 失敗させるコマンド
+        failing: Vec<&'static str>,
+        /// 最初の1回だけ失敗させるコマンド
+        failing_once: Vec<&'static str>,
+        /// IDENTIFY DEVICEで返すSecurityの状態（消去前、消去後）
+        security: Vec<AtaSecurity>,
+    }
+
+    impl FakeDrive {
+        fn new(failing: &[&'static str]) -> Self {
+            let ready = AtaSecurity {
+                supported: true,
+                enhanced_erase_supported: true,
+                erase_time: Some(EraseTimeEstimate { minutes: 2, more_than: false }),
+                ..AtaSecurity::default()
+            };
+            FakeDrive {
+                calls: Arc::default(),
+                password: Arc::default(),
+                failing: failing.to_vec(),
+                failing_once: Vec::new(),
+                security: vec![ready.clone(), ready],
+            }
+        }
+
+        fn command(&mut self, name: &'static str) -> Result<(), String> {
+            self.calls.lock().unwrap().push(name.to_string());
+            if let Some(index) = self.failing_once.iter().position(|n| *n == name) {
+                self.failing_once.remove(index);
+                Err(format!("{}が拒否されました", name))
+            } else if self.failing.contains(&name) {
+                Err(format!("{}が拒否されました", name))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl AtaBackend for FakeDrive {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn identify(&mut self) -> Result<AtaIdentify, String> {
+            self.command("identify")?;
+            let security = if self.security.len() > 1 { self.security.remove(0) } else { self.security[0].clone() };
+            Ok(AtaIdentify { security, ..AtaIdentify::default() })
+        }
+
+        fn security_set_password(&mut self, password: &str) -> Result<(), String> {
+            *self.password.lock().unwrap() = Some(password.to_string());
+            self.command("set_password")
+        }
+
+        fn security_erase(&mut self, _password: &str, _enhanced: bool, _timeout: Duration) -> Result<(), String> {
+            self.command("erase")
+        }
+
+        fn security_unlock(&mut self, _password: &str) -> Result<(), String> {
+            self.command("unlock")
+        }
+
+        fn security_disable(&mut self, _password: &str) -> Result<(), String> {
+            self.command("disable")
+        }
+
+        fn sanitize(&mut self, _action: crate::erase_method::SanitizeAction) -> Result<(), String> {
+            self.command("sanitize")
+        }
+
+        fn sanitize_status(&mut self) -> Result<crate::ata_sanitize::SanitizeStatus, String> {
+            Err("未対応".to_string())
+        }
+
+        fn standby(&mut self) -> Result<(), String> {
+            self.command("standby")
+        }
+    }
+
+    /// 偽のドライブでSecure Eraseを実行し、結果と発行したコマンド、設定された一時パスワードを返す
+    fn run(drive: FakeDrive) -> (Result<(), EraseError>, Vec<String>, Option<String>) {
+        let calls = drive.calls.clone();
+        let password = drive.password.clone();
+        let control = CancelRegistry::default()
+            .register(&[DEVICE.to_string()])
+            .disk(DEVICE)
+            .unwrap();
+        let mut reporter = ProgressReporter::new(None, "test", DEVICE);
+        let result = secure_erase(DEVICE, Box::new(drive), false, &control, &mut reporter);
+        let calls = calls.lock().unwrap().clone();
+        let password = password.lock().unwrap().clone();
+        (result, calls, password)
+    }
+
+    /// 一時パスワードがログに記録されていないことを確認する
+    fn assert_not_logged(password: &str) {
+        let log = std::fs::read_to_string(logger::log_path()).unwrap_or_default();
+        assert!(!log.contains(password));
+    }
+
+    #[test]
+    fn completes_without_disabling_the_password() {
+        let (result, calls, _) = run(FakeDrive::new(&[]));
+        assert!(result.is_ok());
+        assert_eq!(calls, ["identify", "set_password", "erase", "identify"]);
+    }
+
+    #[test]
+    fn precheck_failure_does_not_set_a_password() {
+        let mut drive = FakeDrive::new(&[]);
+        drive.security[0].frozen = true;
+        let (result, calls, password) = run(drive);
+        assert!(matches!(result, Err(EraseError::NotStarted(_))));
+        assert_eq!(calls, ["identify"]);
+        assert_eq!(password, None);
+    }
+
+    #[test]
+    fn failed_password_setting_is_not_started_after_disabling() {
+        let (result, calls, password) = run(FakeDrive::new(&["set_password"]));
+        let password = password.unwrap();
+        match result {
+            Err(EraseError::NotStarted(message)) => assert!(!message.contains(&password)),
+            other => panic!("予期しない結果: {:?}", other),
+        }
+        assert_eq!(calls, ["identify", "set_password", "disable"]);
+        assert_not_logged(&password);
+    }
+
+    #[test]
+    fn failed_erase_is_started_after_disabling() {
+        let (result, calls, password) = run(FakeDrive::new(&["erase"]));
+        let password = password.unwrap();
+        match result {
+            Err(EraseError::Started(message)) => assert!(!message.contains(&password)),
+            other => panic!("予期しない結果: {:?}", other),
+        }
+        assert_eq!(calls, ["identify", "set_password", "erase", "disable"]);
+        assert_not_logged(&password);
+    }
+
+    #[test]
+    fn failed_verification_is_started_after_disabling() {
+        let mut drive = FakeDrive::new(&[]);
+        drive.security[1].enabled = true;
+        let (result, calls, password) = run(drive);
+        let password = password.unwrap();
+        match result {
+            Err(EraseError::Started(message)) => assert!(!message.contains(&password)),
+            other => panic!("予期しない結果: {:?}", other),
+        }
+        assert_eq!(calls, ["identify", "set_password", "erase", "identify", "disable"]);
+        assert_not_logged(&password);
+    }
+
+    #[test]
+    fn unlocks_before_disabling_a_locked_drive() {
+        let mut drive = FakeDrive::new(&["erase"]);
+        drive.failing_once.push("disable");
+        let (result, calls, password) = run(drive);
+        let password = password.unwrap();
+        match result {
+            Err(EraseError::Started(message)) => assert!(!message.contains(&password)),
+            other => panic!("予期しない結果: {:?}", other),
+        }
+        assert_eq!(calls, ["identify", "set_password", "erase", "disable", "unlock", "disable"]);
+        assert_not_logged(&password);
+    }
+
+    #[test]
+    fn reports_the_password_only_when_disabling_fails() {
+        let (result, calls, password) = run(FakeDrive::new(&["erase", "disable", "unlock"]));
+        let password = password.unwrap();
+        match result {
+            Err(EraseError::Started(message)) => {
+                assert!(message.contains(&format!("--security-disable {} {}", password, DEVICE)))
+            }
+            other => panic!("予期しない結果: {:?}", other),
+        }
+        assert_eq!(calls, ["identify", "set_password", "erase", "disable", "unlock"]);
+    }
+}
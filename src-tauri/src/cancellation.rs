@@ -0,0 +1,254 @@
+/// # キャンセル管理モジュール
+///
+/// このモジュールは、実行中の消去ジョブおよびディスクごとの消去処理を
+/// 中断するためのキャンセルトークンを提供します。
+/// ATA Secure Eraseのように途中で中断できない処理の実行中は、
+/// キャンセル要求を理由とともに拒否します。
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// キャンセル要求を伝えるトークン
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// キャンセルを要求する
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// キャンセルが要求されているかどうかを返す
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// ディスク1台分の消去処理を制御する構造体
+pub struct DiskControl {
+    device: String,
+    token: CancelToken,
+    job_token: CancelToken,
+    /// 中断できない処理の名前（実行中の場合のみ`Some`）
+    non_interruptible: Mutex<Option<String>>,
+}
+
+impl DiskControl {
+    /// 対象のデバイス名を返す
+    pub fn device(&self) -> &str {
+        &self.device
+    }
+
+    /// ディスクまたはジョブ全体にキャンセルが要求されているかどうかを返す
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled() || self.job_token.is_cancelled()
+    }
+
+    /// キャンセルされている場合にエラーを返す
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err(format!("{}の消去はキャンセルされました。", self.device));
+        }
+        Ok(())
+    }
+
+    /// 中断できない処理の開始を宣言する
+    ///
+    /// 返されたガードが破棄されるまでの間、このディスクへのキャンセル要求は拒否されます。
+    /// キャンセルの確認と宣言は同じロックの中で行うため、その間に受け付けたキャンセル要求を見落としません。
+    ///
+    /// # 引数
+    ///
+    /// * `operation` - 実行する処理の名前（例: "ATA Secure Erase"）
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<NonInterruptibleGuard, String>` - 処理を開始できる場合はガード、キャンセルされている場合は`Err`
+    pub fn enter_non_interruptible(&self, operation: &str) -> Result<NonInterruptibleGuard<'_>, String> {
+        let mut non_interruptible = self.non_interruptible.lock().unwrap();
+        self.check()?;
+        *non_interruptible = Some(operation.to_string());
+        Ok(NonInterruptibleGuard { control: self })
+    }
+
+    /// このディスクの消去処理にキャンセルを要求する
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<(), String>` - 受け付けた場合は`Ok(())`、中断できない処理の実行中は理由を含む`Err`
+    pub fn request_cancel(&self) -> Result<(), String> {
+        // 中断できない処理の開始と競合しないよう、ロックを保持したままキャンセルする
+        let non_interruptible = self.non_interruptible.lock().unwrap();
+        if let Some(operation) = non_interruptible.as_ref() {
+            return Err(format!(
+                "{}では{}を実行中のため中断できません。ドライブ内部で処理が進行しており、\
+                 途中で電源やコマンドを遮断するとドライブが使用不能になる恐れがあります。\
+                 完了までお待ちください。",
+                self.device, operation
+            ));
+        }
+        self.token.cancel();
+        Ok(())
+    }
+}
+
+/// 中断できない処理の実行中であることを示すガード
+pub struct NonInterruptibleGuard<'a> {
+    control: &'a DiskControl,
+}
+
+impl Drop for NonInterruptibleGuard<'_> {
+    fn drop(&mut self) {
+        *self.control.non_interruptible.lock().unwrap() = None;
+    }
+}
+
+/// 1回の消去ジョブを制御する構造体
+pub struct JobControl {
+    id: String,
+    token: CancelToken,
+    disks: HashMap<String, Arc<DiskControl>>,
+}
+
+impl JobControl {
+    /// ジョブIDを返す
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// 指定したディスクの制御オブジェクトを返す
+    pub fn disk(&self, device: &str) -> Option<Arc<DiskControl>> {
+        self.disks.get(device).cloned()
+    }
+
+    /// ジョブ全体にキャンセルを要求する
+    ///
+    /// 中断できない処理を実行中のディスクは、その処理の完了後に停止します。
+    ///
+    /// # 戻り値
+    ///
+    /// * `Vec<String>` - 即座に中断できなかったディスクについての説明
+    pub fn cancel(&self) -> Vec<String> {
+        self.token.cancel();
+        self.disks
+            .values()
+            .filter_map(|disk| disk.request_cancel().err())
+            .collect()
+    }
+}
+
+/// 実行中のジョブを管理するレジストリ
+#[derive(Default)]
+pub struct CancelRegistry {
+    jobs: Mutex<HashMap<String, Arc<JobControl>>>,
+    next_id: AtomicU64,
+}
+
+impl CancelRegistry {
+    /// 新しいジョブを登録する
+    ///
+    /// # 引数
+    ///
+    /// * `disks` - ジョブで消去するディスクのリスト
+    pub fn register(&self, disks: &[String]) -> Arc<JobControl> {
+        let id = format!("job-{}", self.next_id.fetch_add(1, Ordering::SeqCst) + 1);
+        let token = CancelToken::default();
+        let disks = disks
+            .iter()
+            .map(|device| {
+                let control = DiskControl {
+                    device: device.clone(),
+                    token: CancelToken::default(),
+                    job_token: token.clone(),
+                    non_interruptible: Mutex::new(None),
+                };
+                (device.clone(), Arc::new(control))
+            })
+            .collect();
+
+        let job = Arc::new(JobControl { id: id.clone(), token, disks });
+        self.jobs.lock().unwrap().insert(id, job.clone());
+        job
+    }
+
+    /// 終了したジョブを登録解除する
+    pub fn unregister(&self, job_id: &str) {
+        self.jobs.lock().unwrap().remove(job_id);
+    }
+
+    /// 実行中のすべてのジョブにキャンセルを要求する
+    ///
+    /// # 戻り値
+    ///
+    /// * `Vec<String>` - 即座に中断できなかったディスクについての説明
+    pub fn cancel_all(&self) -> Vec<String> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .flat_map(|job| job.cancel())
+            .collect()
+    }
+
+    /// 指定したジョブにキャンセルを要求する
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<Vec<String>, String>` - 即座に中断できなかったディスクについての説明、
+    ///   ジョブが実行中でない場合は`Err`
+    pub fn cancel_job(&self, job_id: &str) -> Result<Vec<String>, String> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .get(job_id)
+            .ok_or_else(|| format!("ジョブ{}は実行中ではありません。", job_id))?;
+        Ok(job.cancel())
+    }
+
+    /// 指定したディスクの消去処理にキャンセルを要求する
+    ///
+    /// # 引数
+    ///
+    /// * `device` - キャンセルするディスクのデバイス名
+    pub fn cancel_disk(&self, device: &str) -> Result<(), String> {
+        let disk = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .find_map(|job| job.disk(device))
+            .ok_or_else(|| format!("{}は消去中ではありません。", device))?;
+        disk.request_cancel()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refuses_to_enter_after_cancel() {
+        let registry = CancelRegistry::default();
+        let job = registry.register(&["/dev/sda".to_string()]);
+        let disk = job.disk("/dev/sda").unwrap();
+
+        registry.cancel_disk("/dev/sda").unwrap();
+        assert!(disk.enter_non_interruptible("ATA Secure Erase").is_err());
+    }
+
+    #[test]
+    fn rejects_cancel_while_non_interruptible() {
+        let registry = CancelRegistry::default();
+        let job = registry.register(&["/dev/sda".to_string()]);
+        let disk = job.disk("/dev/sda").unwrap();
+
+        let guard = disk.enter_non_interruptible("ATA Secure Erase").unwrap();
+        assert!(registry.cancel_disk("/dev/sda").is_err());
+        assert!(!disk.is_cancelled());
+
+        drop(guard);
+        registry.cancel_disk("/dev/sda").unwrap();
+        assert!(disk.is_cancelled());
+    }
+}
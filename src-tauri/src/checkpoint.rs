@@ -0,0 +1,532 @@
+/// # チェックポイントモジュール
+///
+/// このモジュールは、上書き消去の途中経過を状態ファイルに保存し、
+/// アプリケーションのクラッシュ、再起動、USBディスクの切断などで中断した消去を
+/// 中断した位置から再開できるようにする機能を提供します。
+///
+/// チェックポイントにはディスクの識別情報（シリアル番号、WWN、サイズ）、消去方式、
+/// 実行中のパスとオフセット、ランダムデータのパスで使用している乱数のシードを記録します。
+/// 再開前には識別情報を照合し、同じ物理ディスクであることを確認します。
+/// 乱数のシードはそのパスが完了した時点でチェックポイントから削除されます。
+/// 書き込みや読み込みのできなかった不良セクタも記録し、再開後の結果に引き継ぎます。
+use crate::csprng::PassSeed;
+use crate::erase_standards::OverwriteScheme;
+use crate::logger::log_message;
+use crate::verification::{LbaRange, VerificationLevel};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// チェックポイントを保存する状態ファイル
+pub const CHECKPOINT_FILE: &str = "erase_checkpoints.json";
+
+/// 状態ファイルのパス
+#[cfg(not(test))]
+fn checkpoint_path() -> PathBuf {
+    PathBuf::from(CHECKPOINT_FILE)
+}
+
+/// 状態ファイルのパス（テストでは作業ディレクトリの状態ファイルを変更しない）
+#[cfg(test)]
+fn checkpoint_path() -> PathBuf {
+    std::env::temp_dir().join(format!("disk_eraser_test_checkpoints_{}.json", std::process::id()))
+}
+
+/// パスの途中でチェックポイントを保存する間隔
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 状態ファイルの読み書きを直列化するロック（複数ディスクを並列に消去するため）
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 物理ディスクを識別する情報
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DiskIdentity {
+    /// シリアル番号
+    pub serial: Option<String>,
+    /// WWN（World Wide Name）
+    pub wwn: Option<String>,
+    /// 総バイト数
+    pub size: u64,
+}
+
+impl DiskIdentity {
+    /// 対象のデバイスまたはファイルの識別情報を取得する
+    ///
+    /// # 引数
+    ///
+    /// * `path` - 対象のパス（例: /dev/sda、ディスクイメージファイル）
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| format!("{}の情報を取得できませんでした: {}", path.display(), e))?;
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if metadata.file_type().is_block_device() {
+                return read_block_device_identity(path);
+            }
+        }
+
+        Ok(DiskIdentity {
+            serial: None,
+            wwn: None,
+            size: metadata.len(),
+        })
+    }
+
+    /// 保存された識別情報と現在の識別情報が同じ物理ディスクを示すかを確認する
+    ///
+    /// シリアル番号とWWNのどちらも記録されていないブロックデバイスは、
+    /// 同じディスクであることを確認できないため再開できません。
+    ///
+    /// # 引数
+    ///
+    /// * `current` - 現在接続されているディスクの識別情報
+    /// * `is_image` - 対象が通常のファイル（ディスクイメージ）かどうか
+    pub fn confirm(&self, current: &DiskIdentity, is_image: bool) -> Result<(), String> {
+        if self.size != current.size {
+            return Err(format!(
+                "ディスクのサイズが一致しません（記録: {}バイト、現在: {}バイト）。",
+                self.size, current.size
+            ));
+        }
+        if self.serial.is_none() && self.wwn.is_none() && !is_image {
+            return Err(
+                "シリアル番号とWWNが記録されていないため、同じ物理ディスクであることを確認できません。"
+                    .to_string(),
+            );
+        }
+        if self.serial.is_some() && self.serial != current.serial {
+            return Err(format!(
+                "シリアル番号が一致しません（記録: {}、現在: {}）。",
+                self.serial.as_deref().unwrap_or("-"),
+                current.serial.as_deref().unwrap_or("-")
+            ));
+        }
+        if self.wwn.is_some() && self.wwn != current.wwn {
+            return Err(format!(
+                "WWNが一致しません（記録: {}、現在: {}）。",
+                self.wwn.as_deref().unwrap_or("-"),
+                current.wwn.as_deref().unwrap_or("-")
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// チェックポイントを保存した時点で実行していた処理
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointPhase {
+    /// パスの書き込み中
+    Write,
+    /// パスの読み戻し検証中（再開時は検証をやり直す）
+    Verify,
+}
+
+/// 上書き消去の途中経過
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    /// 対象のデバイス名
+    pub device: String,
+    /// 対象のディスクの識別情報
+    pub identity: DiskIdentity,
+    /// 上書き消去方式のID
+    pub scheme_id: String,
+    /// 上書き消去方式の名前
+    pub scheme_name: String,
+    /// 各パスのパターンの説明（方式の定義が変更されていないことの確認に使用）
+    pub passes: Vec<String>,
+    /// 読み戻し検証の範囲
+    pub verification: VerificationLevel,
+    /// 実行中のパス（0始まり）
+    pub pass: usize,
+    /// 実行中の処理
+    pub phase: CheckpointPhase,
+    /// 書き込みが完了し、デバイスまで反映されたオフセット
+    pub offset: u64,
+    /// ランダムデータのパスで使用している乱数のシード（16進数）
+    pub seed: Option<String>,
+    /// これまでに見つかった不良セクタのLBA範囲
+    #[serde(default)]
+    pub bad_sectors: Vec<LbaRange>,
+    /// 保存時刻（UNIX時間、秒）
+    pub updated_at: u64,
+}
+
+impl Checkpoint {
+    /// チェックポイントが指定した方式と同じ定義で作成されたかを確認する
+    pub fn matches_scheme(&self, scheme: &OverwriteScheme) -> Result<(), String> {
+        let passes: Vec<String> = scheme.passes.iter().map(|p| p.pattern.description()).collect();
+        if self.scheme_id != scheme.id || self.passes != passes {
+            return Err(format!(
+                "上書き消去方式{}の定義がチェックポイントの作成時から変更されています。",
+                self.scheme_id
+            ));
+        }
+        if self.pass >= passes.len() {
+            return Err(format!(
+                "チェックポイントのパス番号{}が方式のパス数{}を超えています。",
+                self.pass + 1,
+                passes.len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// 記録された乱数のシードを復元する
+    pub fn seed(&self) -> Result<Option<PassSeed>, String> {
+        self.seed.as_deref().map(PassSeed::from_hex).transpose()
+    }
+}
+
+/// 上書き中にチェックポイントを保存する構造体
+pub struct CheckpointWriter {
+    checkpoint: Checkpoint,
+    last_saved: Instant,
+    warned: bool,
+}
+
+impl CheckpointWriter {
+    /// 新しいチェックポイントの保存先を作成する
+    ///
+    /// # 引数
+    ///
+    /// * `device` - 対象のデバイス名
+    /// * `identity` - 対象のディスクの識別情報
+    /// * `scheme` - 上書き消去方式
+    /// * `verification` - 読み戻し検証の範囲
+    pub fn new(
+        device: &str,
+        identity: DiskIdentity,
+        scheme: &OverwriteScheme,
+        verification: VerificationLevel,
+    ) -> Self {
+        CheckpointWriter {
+            checkpoint: Checkpoint {
+                device: device.to_string(),
+                identity,
+                scheme_id: scheme.id.clone(),
+                scheme_name: scheme.name.clone(),
+                passes: scheme.passes.iter().map(|p| p.pattern.description()).collect(),
+                verification,
+                pass: 0,
+                phase: CheckpointPhase::Write,
+                offset: 0,
+                seed: None,
+                bad_sectors: Vec::new(),
+                updated_at: 0,
+            },
+            last_saved: Instant::now(),
+            warned: false,
+        }
+    }
+
+    /// パスの書き込みまたは検証の開始を記録する
+    ///
+    /// # 引数
+    ///
+    /// * `pass` - パス（0始まり）
+    /// * `phase` - 開始する処理
+    /// * `offset` - 書き込みを開始するオフセット
+    /// * `seed` - ランダムデータのパスで使用する乱数のシード
+    pub fn begin(&mut self, pass: usize, phase: CheckpointPhase, offset: u64, seed: Option<&PassSeed>) {
+        self.checkpoint.pass = pass;
+        self.checkpoint.phase = phase;
+        self.checkpoint.offset = offset;
+        self.checkpoint.seed = seed.map(PassSeed::to_hex);
+        self.store();
+    }
+
+    /// これまでに見つかった不良セクタを記録する（次回の保存時に状態ファイルへ反映される）
+    pub fn record_bad_sectors(&mut self, ranges: Vec<LbaRange>) {
+        self.checkpoint.bad_sectors = ranges;
+    }
+
+    /// 前回の保存から一定時間が経過したかどうか
+    pub fn is_due(&self) -> bool {
+        self.last_saved.elapsed() >= CHECKPOINT_INTERVAL
+    }
+
+    /// デバイスまで反映されたオフセットを記録する
+    pub fn advance(&mut self, offset: u64) {
+        self.checkpoint.offset = offset;
+        self.store();
+    }
+
+    /// チェックポイントを状態ファイルに保存する
+    ///
+    /// 保存に失敗しても消去自体は継続し、最初の失敗のみをログに記録します。
+    fn store(&mut self) {
+        self.checkpoint.updated_at = unix_time();
+        self.last_saved = Instant::now();
+        if let Err(e) = save(&self.checkpoint) {
+            if !self.warned {
+                log_message(
+                    &format!("{}のチェックポイント保存", self.checkpoint.device),
+                    "警告",
+                    &format!("チェックポイントを保存できませんでした。中断した場合は最初からやり直しになります: {}", e),
+                );
+                self.warned = true;
+            }
+        }
+    }
+}
+
+/// フロントエンドへ提示する再開可能な消去
+#[derive(Serialize, Clone, Debug)]
+pub struct ResumableErase {
+    /// 対象のデバイス名
+    pub device: String,
+    /// 上書き消去方式の名前
+    pub scheme_name: String,
+    /// 中断したパス番号（1始まり）
+    pub pass: usize,
+    /// 総パス数
+    pub total_passes: usize,
+    /// 中断した処理
+    pub phase: CheckpointPhase,
+    /// 中断したオフセット
+    pub offset: u64,
+    /// ディスクの総バイト数
+    pub size: u64,
+    /// チェックポイントの保存時刻（UNIX時間、秒）
+    pub updated_at: u64,
+    /// 再開できるかどうか
+    pub resumable: bool,
+    /// 再開できない理由
+    pub reason: Option<String>,
+}
+
+/// 状態ファイルに記録されたチェックポイントから再開可能な消去の一覧を作成する関数
+///
+/// 各チェックポイントについて、現在接続されているディスクが同じ物理ディスクかを確認します。
+pub fn resumable_erasures() -> Result<Vec<ResumableErase>, String> {
+    Ok(load_all()?
+        .into_iter()
+        .map(|checkpoint| {
+            let check = validate_resume(&checkpoint);
+            ResumableErase {
+                device: checkpoint.device.clone(),
+                scheme_name: checkpoint.scheme_name.clone(),
+                pass: checkpoint.pass + 1,
+                total_passes: checkpoint.passes.len(),
+                phase: checkpoint.phase,
+                offset: checkpoint.offset,
+                size: checkpoint.identity.size,
+                updated_at: checkpoint.updated_at,
+                resumable: check.is_ok(),
+                reason: check.err(),
+            }
+        })
+        .collect())
+}
+
+/// チェックポイントの対象が現在接続されている同じ物理ディスクかを確認する関数
+pub fn validate_resume(checkpoint: &Checkpoint) -> Result<(), String> {
+    let path = Path::new(&checkpoint.device);
+    let current = DiskIdentity::read(path)
+        .map_err(|e| format!("ディスクが接続されていません: {}", e))?;
+    let is_image = std::fs::metadata(path).map(|m| m.is_file()).unwrap_or(false);
+    checkpoint.identity.confirm(&current, is_image)
+}
+
+/// 指定したデバイスのチェックポイントを取得する関数
+pub fn find(device: &str) -> Result<Option<Checkpoint>, String> {
+    Ok(load_all()?.into_iter().find(|c| c.device == device))
+}
+
+/// 状態ファイルに記録されたすべてのチェックポイントを読み込む関数
+pub fn load_all() -> Result<Vec<Checkpoint>, String> {
+    let _lock = FILE_LOCK.lock().unwrap();
+    read_file()
+}
+
+/// チェックポイントを状態ファイルに保存する関数（同じデバイスの記録は置き換える）
+pub fn save(checkpoint: &Checkpoint) -> Result<(), String> {
+    let _lock = FILE_LOCK.lock().unwrap();
+    let mut checkpoints = read_file()?;
+    checkpoints.retain(|c| c.device != checkpoint.device);
+    checkpoints.push(checkpoint.clone());
+    write_file(&checkpoints)
+}
+
+/// 指定したデバイスのチェックポイントを削除する関数
+pub fn remove(device: &str) -> Result<(), String> {
+    let _lock = FILE_LOCK.lock().unwrap();
+    let mut checkpoints = read_file()?;
+    let before = checkpoints.len();
+    checkpoints.retain(|c| c.device != device);
+    if checkpoints.len() == before {
+        return Ok(());
+    }
+    write_file(&checkpoints)
+}
+
+/// 状態ファイルを読み込む（ロックを保持した状態で呼び出すこと）
+fn read_file() -> Result<Vec<Checkpoint>, String> {
+    let content = match std::fs::read_to_string(checkpoint_path()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("状態ファイル{}を読み込めませんでした: {}", CHECKPOINT_FILE, e)),
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| format!("状態ファイル{}の解析に失敗しました: {}", CHECKPOINT_FILE, e))
+}
+
+/// 状態ファイルを一時ファイル経由で置き換える（ロックを保持した状態で呼び出すこと）
+fn write_file(checkpoints: &[Checkpoint]) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(checkpoints)
+        .map_err(|e| format!("チェックポイントのシリアライズに失敗しました: {}", e))?;
+    let path = checkpoint_path();
+    let temp = path.with_extension("json.tmp");
+
+    let write = || -> std::io::Result<()> {
+        use std::io::Write;
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temp, &path)
+    };
+    write().map_err(|e| format!("状態ファイル{}を書き込めませんでした: {}", CHECKPOINT_FILE, e))
+}
+
+/// ブロックデバイスのシリアル番号、WWN、サイズをlsblkで取得する関数
+#[cfg(target_os = "linux")]
+fn read_block_device_identity(path: &Path) -> Result<DiskIdentity, String> {
+    use std::process::Command;
+
+    let output = Command::new("lsblk")
+        .args(["-d", "-n", "-b", "-J", "-o", "SIZE,SERIAL,WWN"])
+        .arg(path)
+        .output()
+        .map_err(|e| format!("lsblkコマンドの実行に失敗しました: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{}の識別情報を取得できませんでした: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("lsblkの出力の解析に失敗しました: {}", e))?;
+    let device = &json["blockdevices"][0];
+    let text = |key: &str| {
+        device[key]
+            .as_str()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    // lsblkのバージョンによってSIZEは数値または文字列で出力される
+    let size = device["size"]
+        .as_u64()
+        .or_else(|| device["size"].as_str().and_then(|s| s.trim().parse().ok()))
+        .ok_or_else(|| format!("{}のサイズを取得できませんでした。", path.display()))?;
+
+    Ok(DiskIdentity {
+        serial: text("serial"),
+        wwn: text("wwn"),
+        size,
+    })
+}
+
+/// 現在のUNIX時間（秒）を取得する関数
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::erase_standards::PassSpec;
+    use crate::overwrite::FillPattern;
+
+    fn scheme() -> OverwriteScheme {
+        OverwriteScheme {
+            id: "test-checkpoint".to_string(),
+            name: "テスト".to_string(),
+            description: String::new(),
+            passes: vec![
+                PassSpec { pattern: FillPattern::Byte(0x00), verify: false },
+                PassSpec { pattern: FillPattern::Random, verify: true },
+            ],
+        }
+    }
+
+    fn identity() -> DiskIdentity {
+        DiskIdentity {
+            serial: Some("S1234".to_string()),
+            wwn: Some("0x5000c500a1b2c3d4".to_string()),
+            size: 1 << 30,
+        }
+    }
+
+    #[test]
+    fn saves_and_loads_checkpoint() {
+        let device = format!("/dev/test-roundtrip-{}", std::process::id());
+        let seed = PassSeed::generate().unwrap();
+        let mut writer = CheckpointWriter::new(&device, identity(), &scheme(), VerificationLevel::Sample { percent: 10 });
+        writer.begin(1, CheckpointPhase::Write, 4096, Some(&seed));
+        writer.record_bad_sectors(vec![LbaRange { start: 3, end: 4 }]);
+        writer.advance(8192);
+
+        let saved = load_all().unwrap().into_iter().find(|c| c.device == device).unwrap();
+        assert_eq!(saved.identity, identity());
+        assert_eq!(saved.scheme_id, "test-checkpoint");
+        assert_eq!(saved.passes, vec!["0x00".to_string(), FillPattern::Random.description()]);
+        assert_eq!(saved.verification, VerificationLevel::Sample { percent: 10 });
+        assert_eq!((saved.pass, saved.phase, saved.offset), (1, CheckpointPhase::Write, 8192));
+        assert_eq!(saved.seed().unwrap().unwrap().to_hex(), seed.to_hex());
+        assert_eq!(saved.bad_sectors, vec![LbaRange { start: 3, end: 4 }]);
+        saved.matches_scheme(&scheme()).unwrap();
+
+        remove(&device).unwrap();
+        assert!(find(&device).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_changed_scheme() {
+        let mut writer = CheckpointWriter::new("/dev/test-scheme", identity(), &scheme(), VerificationLevel::Full);
+        writer.checkpoint.pass = 1;
+        let checkpoint = writer.checkpoint.clone();
+        checkpoint.matches_scheme(&scheme()).unwrap();
+
+        let mut renamed = scheme();
+        renamed.id = "other".to_string();
+        assert!(checkpoint.matches_scheme(&renamed).is_err());
+
+        let mut changed = scheme();
+        changed.passes[0].pattern = FillPattern::Byte(0xFF);
+        assert!(checkpoint.matches_scheme(&changed).is_err());
+
+        let mut shortened = scheme();
+        shortened.passes.pop();
+        assert!(checkpoint.matches_scheme(&shortened).is_err());
+    }
+
+    #[test]
+    fn confirms_same_physical_disk_only() {
+        let saved = identity();
+        saved.confirm(&identity(), false).unwrap();
+
+        let other_serial = DiskIdentity { serial: Some("S9999".to_string()), ..identity() };
+        assert!(saved.confirm(&other_serial, false).unwrap_err().contains("シリアル番号"));
+
+        let other_wwn = DiskIdentity { wwn: Some("0x5000c500ffffffff".to_string()), ..identity() };
+        assert!(saved.confirm(&other_wwn, false).unwrap_err().contains("WWN"));
+
+        let other_size = DiskIdentity { size: 1 << 20, ..identity() };
+        assert!(saved.confirm(&other_size, false).unwrap_err().contains("サイズ"));
+
+        // 識別情報のないブロックデバイスは同じディスクか確認できないが、ディスクイメージは再開できる
+        let anonymous = DiskIdentity { serial: None, wwn: None, size: 1 << 30 };
+        assert!(anonymous.confirm(&anonymous, false).is_err());
+        anonymous.confirm(&anonymous, true).unwrap();
+    }
+}
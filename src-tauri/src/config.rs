@@ -0,0 +1,308 @@
+/// # 設定ファイルモジュール
+///
+/// このモジュールは、アプリケーションの設定ファイル（TOMLまたはJSON）を読み込みます。
+/// 設定ファイルには、契約などで指定された独自の上書き消去方式や、
+/// システムで使用中のディスクの保護を解除する設定を記述できます。
+///
+/// 設定ファイルは環境変数`DISK_ERASER_CONFIG`で指定されたパス、
+/// 未指定の場合はカレントディレクトリの`disk_eraser.toml`または`disk_eraser.json`です。
+///
+/// ```toml
+/// [[schemes]]
+/// id = "customer-a"
+/// name = "顧客A指定方式"
+/// description = "0x00、その補数、LBAスタンプの順に書き込み、最終パスを検証する"
+///
+/// [[schemes.passes]]
+/// type = "byte"
+/// value = 0x00
+///
+/// [[schemes.passes]]
+/// type = "complement"
+///
+/// [[schemes.passes]]
+/// type = "lba"
+/// verify = true
+/// ```
+use crate::erase_policy::ErasePolicy;
+use crate::simulation::SimulationConfig;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// 設定ファイルのパスを指定する環境変数
+pub const CONFIG_ENV: &str = "DISK_ERASER_CONFIG";
+
+/// 既定の設定ファイル名（拡張子なし）
+const DEFAULT_CONFIG_STEM: &str = "disk_eraser";
+
+/// 設定ファイル全体
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfig {
+    /// ユーザー定義の上書き消去方式
+    #[serde(default)]
+    pub schemes: Vec<SchemeDefinition>,
+    /// 使用中のディスクの保護に関する設定
+    #[serde(default)]
+    pub safety: SafetyConfig,
+    /// 仮想ディスクによるシミュレーションの設定
+    #[serde(default)]
+    pub simulation: SimulationConfig,
+    /// 消去方式を指定しない場合に試す消去方式の優先順位
+    #[serde(default)]
+    pub policy: ErasePolicy,
+}
+
+/// 使用中のディスクの保護に関する設定
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SafetyConfig {
+    /// 保護対象であっても消去を許可するデバイス名（例: /dev/sdb）
+    #[serde(default)]
+    pub allow_protected_disks: Vec<String>,
+}
+
+/// ユーザー定義の上書き消去方式
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SchemeDefinition {
+    /// 方式のID
+    pub id: String,
+    /// 表示名
+    pub name: String,
+    /// 方式の説明
+    #[serde(default)]
+    pub description: String,
+    /// パスの列
+    pub passes: Vec<PassDefinition>,
+}
+
+/// ユーザー定義のパス
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "RawPassDefinition")]
+pub struct PassDefinition {
+    /// 書き込むパターン
+    pub pattern: PatternDefinition,
+    /// 書き込み後に読み戻して検証するか
+    pub verify: bool,
+}
+
+/// ユーザー定義のパターン
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternDefinition {
+    /// 固定の1バイト値
+    Byte { value: u8 },
+    /// 複数バイトの繰り返しパターン
+    Pattern { bytes: Vec<u8> },
+    /// ランダムデータ
+    Random,
+    /// 直前のパスのビット反転
+    Complement,
+    /// 各論理セクタにそのLBAを書き込むパターン
+    Lba,
+}
+
+/// 設定ファイルに記述されたパス
+///
+/// `#[serde(flatten)]`では`deny_unknown_fields`が効かず、`verfy`のような綴り誤りが無視されるため、
+/// すべてのキーをこの構造体で受け取って未知のキーを拒否し、パターンの種類ごとに必要なキーを確認します。
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPassDefinition {
+    #[serde(rename = "type")]
+    kind: PatternKind,
+    value: Option<u8>,
+    bytes: Option<Vec<u8>>,
+    #[serde(default)]
+    verify: bool,
+}
+
+/// パターンの種類（`type`キーの値）
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum PatternKind {
+    Byte,
+    Pattern,
+    Random,
+    Complement,
+    Lba,
+}
+
+impl PatternKind {
+    /// 設定ファイルでの名前
+    fn name(self) -> &'static str {
+        match self {
+            PatternKind::Byte => "byte",
+            PatternKind::Pattern => "pattern",
+            PatternKind::Random => "random",
+            PatternKind::Complement => "complement",
+            PatternKind::Lba => "lba",
+        }
+    }
+}
+
+impl TryFrom<RawPassDefinition> for PassDefinition {
+    type Error = String;
+
+    fn try_from(raw: RawPassDefinition) -> Result<Self, String> {
+        let pattern = match (raw.kind, raw.value, raw.bytes) {
+            (PatternKind::Byte, Some(value), None) => PatternDefinition::Byte { value },
+            (PatternKind::Pattern, None, Some(bytes)) => PatternDefinition::Pattern { bytes },
+            (PatternKind::Random, None, None) => PatternDefinition::Random,
+            (PatternKind::Complement, None, None) => PatternDefinition::Complement,
+            (PatternKind::Lba, None, None) => PatternDefinition::Lba,
+            (PatternKind::Byte, None, _) => {
+                return Err("type = \"byte\"のパスにはvalueを指定してください。".to_string())
+            }
+            (PatternKind::Pattern, _, None) => {
+                return Err("type = \"pattern\"のパスにはbytesを指定してください。".to_string())
+            }
+            (kind, value, _) => {
+                let key = if value.is_some() && kind != PatternKind::Byte { "value" } else { "bytes" };
+                return Err(format!("type = \"{}\"のパスに{}は指定できません。", kind.name(), key));
+            }
+        };
+        Ok(PassDefinition {
+            pattern,
+            verify: raw.verify,
+        })
+    }
+}
+
+/// 設定ファイルのパスを決定する関数
+///
+/// # 戻り値
+///
+/// * `Option<PathBuf>` - 設定ファイルが存在する場合はそのパス
+pub fn config_path() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var(CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+
+    ["toml", "json"]
+        .iter()
+        .map(|ext| PathBuf::from(format!("{}.{}", DEFAULT_CONFIG_STEM, ext)))
+        .find(|path| path.exists())
+}
+
+/// 設定ファイルを読み込む関数
+///
+/// 設定ファイルが存在しない場合は既定の設定を返します。
+///
+/// # 戻り値
+///
+/// * `Result<AppConfig, String>` - 読み込みまたは解析に失敗した場合はエラーメッセージを含む`Err`
+pub fn load() -> Result<AppConfig, String> {
+    match config_path() {
+        Some(path) => load_from(&path),
+        None => Ok(AppConfig::default()),
+    }
+}
+
+/// 指定したパスの設定ファイルを読み込む関数
+///
+/// 拡張子が`.json`の場合はJSON、それ以外はTOMLとして解析します。
+pub fn load_from(path: &Path) -> Result<AppConfig, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        format!("設定ファイル{}を読み込めませんでした: {}", path.display(), e)
+    })?;
+
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    if is_json {
+        serde_json::from_str(&content)
+            .map_err(|e| format!("設定ファイル{}の解析に失敗しました: {}", path.display(), e))
+    } else {
+        toml::from_str(&content)
+            .map_err(|e| format!("設定ファイル{}の解析に失敗しました: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_toml(content: &str) -> Result<AppConfig, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn parses_the_documented_scheme() {
+        let config = parse_toml(
+            r#"
+            [[schemes]]
+            id = "customer-a"
+            name = "顧客A指定方式"
+
+            [[schemes.passes]]
+            type = "byte"
+            value = 0x00
+
+            [[schemes.passes]]
+            type = "complement"
+
+            [[schemes.passes]]
+            type = "pattern"
+            bytes = [0x92, 0x49, 0x24]
+
+            [[schemes.passes]]
+            type = "lba"
+            verify = true
+            "#,
+        )
+        .unwrap();
+
+        let passes = &config.schemes[0].passes;
+        assert_eq!(
+            passes.iter().map(|p| p.pattern.clone()).collect::<Vec<_>>(),
+            vec![
+                PatternDefinition::Byte { value: 0x00 },
+                PatternDefinition::Complement,
+                PatternDefinition::Pattern { bytes: vec![0x92, 0x49, 0x24] },
+                PatternDefinition::Lba,
+            ]
+        );
+        assert_eq!(passes.iter().map(|p| p.verify).collect::<Vec<_>>(), vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn rejects_misspelled_pass_keys() {
+        let error = parse_toml(
+            r#"
+            [[schemes]]
+            id = "typo"
+            name = "綴り誤り"
+
+            [[schemes.passes]]
+            type = "random"
+            verfy = true
+            "#,
+        )
+        .unwrap_err();
+        assert!(error.contains("verfy"), "{}", error);
+
+        let json = r#"{"schemes": [{"id": "typo", "name": "綴り誤り",
+            "passes": [{"type": "byte", "value": 0, "verfy": true}]}]}"#;
+        let error = serde_json::from_str::<AppConfig>(json).unwrap_err().to_string();
+        assert!(error.contains("verfy"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_keys_that_do_not_belong_to_the_pattern() {
+        let pass = |body: &str| {
+            parse_toml(&format!(
+                "[[schemes]]\nid = \"x\"\nname = \"x\"\n[[schemes.passes]]\n{}\n",
+                body
+            ))
+        };
+
+        assert!(pass("type = \"byte\"").unwrap_err().contains("value"));
+        assert!(pass("type = \"pattern\"").unwrap_err().contains("bytes"));
+        assert!(pass("type = \"random\"\nvalue = 1").unwrap_err().contains("value"));
+        assert!(pass("type = \"byte\"\nvalue = 1\nbytes = [1]").unwrap_err().contains("bytes"));
+        assert!(pass("type = \"pattern\"\nvalue = 1\nbytes = [1]").unwrap_err().contains("value"));
+        assert!(pass("type = \"zero\"").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+/// # 乱数生成モジュール
+///
+/// このモジュールは、ランダムデータのパスで書き込むデータをプロセス内で生成する機能を提供します。
+/// パスごとにOSの乱数源から256ビットのシードを取得し、ChaCha20ストリーム暗号で乱数列を生成します。
+/// 同じシードから任意のオフセットの乱数列を再生成できるため、
+/// 書き込んだランダムデータを読み戻して1バイト単位で検証できます。
+/// シードは検証が終わった時点で破棄され、メモリ上の値もゼロで上書きされます。
+/// 消去を再開できるよう、パスの実行中に限りシードはチェックポイントにも記録されます。
+use rand_chacha::ChaCha20Rng;
+use rand_core::{OsRng, RngCore, SeedableRng};
+
+/// シードのバイト数
+const SEED_LEN: usize = 32;
+
+/// 1パス分の乱数列のシード
+///
+/// 破棄時にメモリ上のシードをゼロで上書きします。
+pub struct PassSeed([u8; SEED_LEN]);
+
+impl PassSeed {
+    /// OSの乱数源から新しいシードを生成する
+    pub fn generate() -> Result<Self, String> {
+        let mut seed = [0u8; SEED_LEN];
+        OsRng
+            .try_fill_bytes(&mut seed)
+            .map_err(|e| format!("乱数のシードを取得できませんでした: {}", e))?;
+        Ok(PassSeed(seed))
+    }
+
+    /// チェックポイントに記録するためにシードを16進数の文字列に変換する
+    pub fn to_hex(&self) -> String {
+        self.0.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// チェックポイントに記録された16進数の文字列からシードを復元する
+    pub fn from_hex(hex: &str) -> Result<Self, String> {
+        let invalid = || "乱数のシードの形式が不正です。".to_string();
+        if hex.len() != SEED_LEN * 2 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let mut seed = [0u8; SEED_LEN];
+        for (i, byte) in seed.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+        Ok(PassSeed(seed))
+    }
+}
+
+impl Drop for PassSeed {
+    fn drop(&mut self) {
+        for byte in self.0.iter_mut() {
+            // SAFETY: 自身が所有する配列の要素への有効な参照に書き込む
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+/// シードから乱数列を生成する構造体
+pub struct RandomStream {
+    rng: ChaCha20Rng,
+}
+
+impl RandomStream {
+    /// シードから乱数列の生成器を作成する
+    pub fn new(seed: &PassSeed) -> Self {
+        RandomStream {
+            rng: ChaCha20Rng::from_seed(seed.0),
+        }
+    }
+
+    /// 乱数列の先頭から`offset`バイトの位置のデータで`buffer`を埋める
+    ///
+    /// # 引数
+    ///
+    /// * `buffer` - 埋めるバッファ
+    /// * `offset` - 乱数列の先頭からのオフセット（4の倍数であること）
+    pub fn fill_at(&mut self, buffer: &mut [u8], offset: u64) {
+        debug_assert!(offset.is_multiple_of(4));
+        self.rng.set_word_pos(u128::from(offset / 4));
+        self.rng.fill_bytes(buffer);
+    }
+}
+
+/// 一時パスワードに使用する文字
+const PASSWORD_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// ATA Securityの一時パスワードを生成する関数
+///
+/// 消去ごとにOSの乱数源から生成します。コマンドの引数として安全に渡せるよう英小文字と数字のみを使用します。
+///
+/// # 引数
+///
+/// * `len` - パスワードの文字数（ATAのパスワードは32バイトまで）
+pub fn temporary_password(len: usize) -> Result<String, String> {
+    let mut password = String::with_capacity(len);
+    let mut buffer = [0u8; 64];
+    while password.len() < len {
+        OsRng
+            .try_fill_bytes(&mut buffer)
+            .map_err(|e| format!("一時パスワードを生成できませんでした: {}", e))?;
+        // 偏りが出ないよう、文字数の倍数に収まらない値は捨てる
+        let limit = 256 - 256 % PASSWORD_CHARS.len();
+        password.extend(
+            buffer
+                .iter()
+                .filter(|&&b| usize::from(b) < limit)
+                .map(|&b| char::from(PASSWORD_CHARS[usize::from(b) % PASSWORD_CHARS.len()]))
+                .take(len - password.len()),
+        );
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed(byte: u8) -> PassSeed {
+        PassSeed([byte; SEED_LEN])
+    }
+
+    #[test]
+    fn regenerates_stream_at_any_aligned_offset() {
+        let mut reference = vec![0u8; 64 * 1024];
+        RandomStream::new(&seed(0x5a)).fill_at(&mut reference, 0);
+
+        let mut stream = RandomStream::new(&seed(0x5a));
+        for offset in [4u64, 60, 64, 68, 1020, 4096, 12_292, 40_000] {
+            for len in [1usize, 3, 64, 100, 4096, 20_000] {
+                let len = len.min(reference.len() - offset as usize);
+                let mut buffer = vec![0u8; len];
+                stream.fill_at(&mut buffer, offset);
+                assert_eq!(
+                    buffer,
+                    &reference[offset as usize..offset as usize + len],
+                    "オフセット{}、長さ{}",
+                    offset,
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn different_seeds_produce_different_streams() {
+        let mut first = [0u8; 4096];
+        let mut second = [0u8; 4096];
+        RandomStream::new(&seed(1)).fill_at(&mut first, 0);
+        RandomStream::new(&seed(2)).fill_at(&mut second, 0);
+        assert_ne!(first, second);
+
+        RandomStream::new(&PassSeed::generate().unwrap()).fill_at(&mut first, 0);
+        RandomStream::new(&PassSeed::generate().unwrap()).fill_at(&mut second, 0);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn restores_seed_from_hex() {
+        let original = PassSeed::generate().unwrap();
+        let restored = PassSeed::from_hex(&original.to_hex()).unwrap();
+        assert_eq!(original.0, restored.0);
+        assert!(PassSeed::from_hex("zz").is_err());
+    }
+}
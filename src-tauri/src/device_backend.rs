@@ -0,0 +1,661 @@
+/// # デバイスコマンドのバックエンドモジュール
+///
+/// このモジュールは、ATAコマンドとNVMe管理コマンドをディスクに発行する方法をトレイトとして抽象化します。
+///
+/// - 直接発行: ATA PASS-THROUGH（SAT）をSG_IOで、NVMe管理コマンドをNVMeのioctlで発行する
+///   （`ata_passthrough`、`nvme_passthrough`モジュール）
+/// - 外部コマンド: `hdparm`と`nvme`（nvme-cli）を実行して出力を解析する
+///
+/// デバイスを開く際にまず直接発行を試み、権限が足りない場合やUSB変換アダプタがATA PASS-THROUGHに
+/// 対応していない場合は外部コマンドにフォールバックします。
+/// 同じ消去を二重に開始しないよう、開いた後にコマンドが失敗しても別の方法で再発行することはありません。
+use crate::ata_identify::{self, AtaIdentify};
+use crate::ata_passthrough::SatBackend;
+use crate::ata_sanitize::{self, SanitizeStatus};
+use crate::disk_selection;
+use crate::erase_method::{NvmeSanitizeOptions, SanitizeAction};
+use crate::logger::log_message;
+use crate::nvme_identify::{self, NvmeController, NvmeNamespace};
+use crate::nvme_passthrough::AdminBackend;
+use crate::nvme_sanitize::{self, SanitizeLog};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 外部コマンドの終了を確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 外部コマンドを実行する権限
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Privilege {
+    /// アプリケーションと同じ権限で実行する（消去処理）
+    Inherit,
+    /// `sudo`経由で実行する（ディスクの検出と消去後の処理）
+    Sudo,
+}
+
+/// ATAコマンドを発行するバックエンド
+pub trait AtaBackend: Send {
+    /// ログに使用するバックエンドの名前
+    fn name(&self) -> &'static str;
+
+    /// IDENTIFY DEVICEの内容を取得する
+    fn identify(&mut self) -> Result<AtaIdentify, String>;
+
+    /// SECURITY SET PASSWORDでユーザーパスワードを設定する
+    fn security_set_password(&mut self, password: &str) -> Result<(), String>;
+
+    /// SECURITY ERASE PREPAREとSECURITY ERASE UNITを発行し、消去の完了を待つ
+    ///
+    /// `timeout`以内に完了しない場合は`Err`を返します。
+    fn security_erase(&mut self, password: &str, enhanced: bool, timeout: Duration) -> Result<(), String>;
+
+    /// SECURITY UNLOCKでロックを解除する
+    fn security_unlock(&mut self, password: &str) -> Result<(), String>;
+
+    /// SECURITY DISABLE PASSWORDでユーザーパスワードを解除する
+    fn security_disable(&mut self, password: &str) -> Result<(), String>;
+
+    /// SANITIZE DEVICEを発行する（完了を待たない）
+    fn sanitize(&mut self, action: SanitizeAction) -> Result<(), String>;
+
+    /// SANITIZE STATUS EXTで現在のSanitizeの状態を取得する
+    fn sanitize_status(&mut self) -> Result<SanitizeStatus, String>;
+
+    /// STANDBY IMMEDIATEでスピンダウンする
+    fn standby(&mut self) -> Result<(), String>;
+}
+
+/// NVMe Format NVMの設定
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FormatNvm {
+    /// ネームスペースID
+    pub nsid: u32,
+    /// LBAフォーマットの番号
+    pub lba_format: u8,
+    /// Secure Erase Settings（0=消去なし、1=ユーザーデータ消去、2=暗号化消去）
+    pub ses: u8,
+    /// メタデータを論理ブロックの末尾に付加する拡張LBA形式にするか
+    pub metadata_extended: bool,
+    /// エンドツーエンドデータ保護の種類
+    pub protection_type: u8,
+    /// 保護情報をメタデータの先頭に置くか
+    pub protection_first: bool,
+}
+
+/// NVMe管理コマンドを発行するバックエンド
+pub trait NvmeBackend: Send {
+    /// ログに使用するバックエンドの名前
+    fn name(&self) -> &'static str;
+
+    /// Identify Controllerの内容を取得する
+    fn identify_controller(&mut self) -> Result<NvmeController, String>;
+
+    /// Identify Namespaceの内容を取得する
+    fn identify_namespace(&mut self, nsid: u32) -> Result<NvmeNamespace, String>;
+
+    /// コントローラのアクティブなネームスペースIDの一覧を取得する
+    fn namespace_list(&mut self) -> Result<Vec<u32>, String>;
+
+    /// Format NVMを発行し、完了を待つ
+    fn format(&mut self, format: &FormatNvm) -> Result<(), String>;
+
+    /// Sanitizeを発行する（完了を待たない）
+    fn sanitize(&mut self, action: SanitizeAction, options: &NvmeSanitizeOptions) -> Result<(), String>;
+
+    /// Sanitize Statusログを取得する
+    fn sanitize_log(&mut self) -> Result<SanitizeLog, String>;
+}
+
+/// 外部コマンドの実行結果
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// 正常に終了したか
+    pub success: bool,
+    /// 標準出力
+    pub stdout: String,
+    /// 標準エラー出力
+    pub stderr: String,
+}
+
+/// 外部コマンドを実行するランナー
+pub trait CommandRunner: Send {
+    /// 外部コマンドを実行し、終了を待つ
+    ///
+    /// # 引数
+    ///
+    /// * `program` - 実行するコマンド名
+    /// * `args` - コマンドの引数
+    /// * `privilege` - 実行権限
+    /// * `timeout` - 終了を待つ時間の上限（`None`の場合は無制限）
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<CommandOutput, String>` - 実行結果、コマンドを実行できなかった場合や時間内に終了しなかった場合は`Err`
+    fn run(
+        &mut self,
+        program: &str,
+        args: &[String],
+        privilege: Privilege,
+        timeout: Option<Duration>,
+    ) -> Result<CommandOutput, String>;
+}
+
+/// 外部コマンドをプロセスとして実行するランナー
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(
+        &mut self,
+        program: &str,
+        args: &[String],
+        privilege: Privilege,
+        timeout: Option<Duration>,
+    ) -> Result<CommandOutput, String> {
+        let mut command = command(program, privilege);
+        command.args(args);
+        let output = match timeout {
+            None => command.output(),
+            Some(timeout) => {
+                let mut child = command
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| format!("{}の実行に失敗しました: {}", program, e))?;
+
+                let started = Instant::now();
+                while child
+                    .try_wait()
+                    .map_err(|e| format!("{}の終了を確認できませんでした: {}", program, e))?
+                    .is_none()
+                {
+                    if started.elapsed() >= timeout {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(timeout_message(timeout));
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                child.wait_with_output()
+            }
+        }
+        .map_err(|e| format!("{}の実行に失敗しました: {}", program, e))?;
+
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// ATAコマンドを発行するバックエンドを開く関数
+///
+/// SG_IOでIDENTIFY DEVICEを発行できる場合は直接発行し、できない場合は`hdparm`を使用します。
+///
+/// # 引数
+///
+/// * `device` - デバイス名（例: /dev/sda）
+/// * `privilege` - `hdparm`にフォールバックした場合の実行権限
+///
+/// # 戻り値
+///
+/// * `Box<dyn AtaBackend>` - 使用するバックエンド
+pub fn open_ata(device: &str, privilege: Privilege) -> Box<dyn AtaBackend> {
+    select_ata(
+        device,
+        SatBackend::open(device).map(|backend| Box::new(backend) as Box<dyn AtaBackend>),
+        privilege,
+        Box::new(SystemRunner),
+    )
+}
+
+/// 直接発行を試みた結果からATAコマンドのバックエンドを選択する関数
+///
+/// # 引数
+///
+/// * `device` - デバイス名（例: /dev/sda）
+/// * `direct` - ATA PASS-THROUGHのバックエンドを開いた結果
+/// * `privilege` - `hdparm`にフォールバックした場合の実行権限
+/// * `runner` - `hdparm`にフォールバックした場合に使用するランナー
+///
+/// # 戻り値
+///
+/// * `Box<dyn AtaBackend>` - 使用するバックエンド
+pub fn select_ata(
+    device: &str,
+    direct: Result<Box<dyn AtaBackend>, String>,
+    privilege: Privilege,
+    runner: Box<dyn CommandRunner>,
+) -> Box<dyn AtaBackend> {
+    match direct {
+        Ok(backend) => backend,
+        Err(e) => {
+            log_message(
+                "デバイスアクセス",
+                "情報",
+                &format!("{}: ATA PASS-THROUGHを使用できないため、hdparmを使用します（{}）。", device, e),
+            );
+            Box::new(HdparmBackend::new(device, privilege, runner))
+        }
+    }
+}
+
+/// NVMe管理コマンドを発行するバックエンドを開く関数
+///
+/// NVMeのioctlでIdentify Controllerを発行できる場合は直接発行し、できない場合は`nvme`を使用します。
+///
+/// # 引数
+///
+/// * `device` - ネームスペースのデバイス名（例: /dev/nvme0n1）
+/// * `privilege` - `nvme`にフォールバックした場合の実行権限
+///
+/// # 戻り値
+///
+/// * `Box<dyn NvmeBackend>` - 使用するバックエンド
+pub fn open_nvme(device: &str, privilege: Privilege) -> Box<dyn NvmeBackend> {
+    select_nvme(
+        device,
+        AdminBackend::open(device).map(|backend| Box::new(backend) as Box<dyn NvmeBackend>),
+        privilege,
+        Box::new(SystemRunner),
+    )
+}
+
+/// 直接発行を試みた結果からNVMe管理コマンドのバックエンドを選択する関数
+///
+/// # 引数
+///
+/// * `device` - ネームスペースのデバイス名（例: /dev/nvme0n1）
+/// * `direct` - NVMeのioctlのバックエンドを開いた結果
+/// * `privilege` - `nvme`にフォールバックした場合の実行権限
+/// * `runner` - `nvme`にフォールバックした場合に使用するランナー
+///
+/// # 戻り値
+///
+/// * `Box<dyn NvmeBackend>` - 使用するバックエンド
+pub fn select_nvme(
+    device: &str,
+    direct: Result<Box<dyn NvmeBackend>, String>,
+    privilege: Privilege,
+    runner: Box<dyn CommandRunner>,
+) -> Box<dyn NvmeBackend> {
+    match direct {
+        Ok(backend) => backend,
+        Err(e) => {
+            log_message(
+                "デバイスアクセス",
+                "情報",
+                &format!("{}: NVMeのioctlを使用できないため、nvmeコマンドを使用します（{}）。", device, e),
+            );
+            Box::new(NvmeCliBackend::new(device, privilege, runner))
+        }
+    }
+}
+
+/// 消去が時間内に完了しなかった場合のエラーメッセージ
+pub fn timeout_message(timeout: Duration) -> String {
+    format!(
+        "{}分以内に完了しませんでした。ドライブは消去を続けている可能性があります。",
+        timeout.as_secs() / 60
+    )
+}
+
+/// `hdparm`でATAコマンドを発行するバックエンド
+pub struct HdparmBackend {
+    device: String,
+    privilege: Privilege,
+    runner: Box<dyn CommandRunner>,
+}
+
+impl HdparmBackend {
+    /// 対象のデバイスと実行権限、ランナーを指定してバックエンドを作成する
+    pub fn new(device: &str, privilege: Privilege, runner: Box<dyn CommandRunner>) -> Self {
+        HdparmBackend {
+            device: device.to_string(),
+            privilege,
+            runner,
+        }
+    }
+
+    /// hdparmを実行する
+    fn run(&mut self, args: &[&str], timeout: Option<Duration>) -> Result<CommandOutput, String> {
+        let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        args.push(self.device.clone());
+        check_status(self.runner.run("hdparm", &args, self.privilege, timeout)?)
+    }
+}
+
+impl AtaBackend for HdparmBackend {
+    fn name(&self) -> &'static str {
+        "hdparm"
+    }
+
+    fn identify(&mut self) -> Result<AtaIdentify, String> {
+        let output = self.run(&["-I"], None)?;
+        ata_identify::parse(&output.stdout)
+            .ok_or_else(|| format!("{}からATA IDENTIFYの情報を取得できませんでした。", self.device))
+    }
+
+    fn security_set_password(&mut self, password: &str) -> Result<(), String> {
+        self.run(&["--user-master", "u", "--security-set-pass", password], None).map(|_| ())
+    }
+
+    fn security_erase(&mut self, password: &str, enhanced: bool, timeout: Duration) -> Result<(), String> {
+        let erase_arg = if enhanced { "--security-erase-enhanced" } else { "--security-erase" };
+        self.run(&["--user-master", "u", erase_arg, password], Some(timeout)).map(|_| ())
+    }
+
+    fn security_unlock(&mut self, password: &str) -> Result<(), String> {
+        self.run(&["--user-master", "u", "--security-unlock", password], None).map(|_| ())
+    }
+
+    fn security_disable(&mut self, password: &str) -> Result<(), String> {
+        self.run(&["--user-master", "u", "--security-disable", password], None).map(|_| ())
+    }
+
+    fn sanitize(&mut self, action: SanitizeAction) -> Result<(), String> {
+        let pattern = format!("hex:{:08x}", ata_sanitize::OVERWRITE_PATTERN);
+        let mut args = vec!["--yes-i-know-what-i-am-doing"];
+        match action {
+            SanitizeAction::BlockErase => args.push("--sanitize-block-erase"),
+            SanitizeAction::CryptoErase => args.push("--sanitize-crypto-scramble"),
+            SanitizeAction::Overwrite => args.extend(["--sanitize-overwrite", &pattern]),
+        }
+        self.run(&args, None).map(|_| ())
+    }
+
+    fn sanitize_status(&mut self) -> Result<SanitizeStatus, String> {
+        let output = self.run(&["--sanitize-status"], None)?;
+        ata_sanitize::parse_status(&output.stdout)
+            .ok_or_else(|| format!("{}のSanitizeの状態を解析できませんでした。", self.device))
+    }
+
+    fn standby(&mut self) -> Result<(), String> {
+        self.run(&["-y"], None).map(|_| ())
+    }
+}
+
+/// `nvme`（nvme-cli）でNVMe管理コマンドを発行するバックエンド
+pub struct NvmeCliBackend {
+    device: String,
+    privilege: Privilege,
+    runner: Box<dyn CommandRunner>,
+}
+
+impl NvmeCliBackend {
+    /// 対象のデバイスと実行権限、ランナーを指定してバックエンドを作成する
+    pub fn new(device: &str, privilege: Privilege, runner: Box<dyn CommandRunner>) -> Self {
+        NvmeCliBackend {
+            device: device.to_string(),
+            privilege,
+            runner,
+        }
+    }
+
+    /// nvmeコマンドを実行する
+    fn run<S: AsRef<str>>(&mut self, args: &[S]) -> Result<CommandOutput, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.as_ref().to_string()).collect();
+        check_status(self.runner.run("nvme", &args, self.privilege, None)?)
+    }
+}
+
+impl NvmeBackend for NvmeCliBackend {
+    fn name(&self) -> &'static str {
+        "nvme-cli"
+    }
+
+    fn identify_controller(&mut self) -> Result<NvmeController, String> {
+        let device = self.device.clone();
+        let output = self.run(&["id-ctrl", "-o", "json", device.as_str()])?;
+        nvme_identify::parse_controller(&output.stdout)
+    }
+
+    fn identify_namespace(&mut self, nsid: u32) -> Result<NvmeNamespace, String> {
+        let output = self.run(&[
+            "id-ns".to_string(),
+            "-o".to_string(),
+            "json".to_string(),
+            format!("--namespace-id={}", nsid),
+            self.device.clone(),
+        ])?;
+        nvme_identify::parse_namespace(nsid, &output.stdout)
+    }
+
+    fn namespace_list(&mut self) -> Result<Vec<u32>, String> {
+        let controller = disk_selection::nvme_controller_path(&self.device).unwrap_or_else(|| self.device.clone());
+        let output = self.run(&["list-ns", "-o", "json", controller.as_str()])?;
+        nvme_identify::parse_namespace_list(&output.stdout)
+    }
+
+    fn format(&mut self, format: &FormatNvm) -> Result<(), String> {
+        self.run(&[
+            "format".to_string(),
+            self.device.clone(),
+            format!("--namespace-id={}", format.nsid),
+            format!("--ses={}", format.ses),
+            format!("--lbaf={}", format.lba_format),
+            format!("--ms={}", u8::from(format.metadata_extended)),
+            format!("--pi={}", format.protection_type),
+            format!("--pil={}", u8::from(format.protection_first)),
+            "--force".to_string(),
+        ])
+        .map(|_| ())
+    }
+
+    fn sanitize(&mut self, action: SanitizeAction, options: &NvmeSanitizeOptions) -> Result<(), String> {
+        let mut args = vec![
+            "sanitize".to_string(),
+            self.device.clone(),
+            format!("--sanact={}", nvme_sanitize::sanitize_action_code(action)),
+        ];
+        if action == SanitizeAction::Overwrite {
+            args.push(format!("--owpass={}", options.overwrite_passes));
+            args.push(format!("--ovrpat={}", options.overwrite_pattern));
+            if options.invert_pattern {
+                args.push("--oipbp".to_string());
+            }
+        }
+        if options.no_deallocate {
+            args.push("--no-dealloc".to_string());
+        }
+        self.run(&args).map(|_| ())
+    }
+
+    fn sanitize_log(&mut self) -> Result<SanitizeLog, String> {
+        let device = self.device.clone();
+        let output = self.run(&["sanitize-log", "-o", "json", device.as_str()])?;
+        nvme_sanitize::parse_sanitize_log(&output.stdout)
+    }
+}
+
+/// 権限に応じて外部コマンドを準備する
+fn command(program: &str, privilege: Privilege) -> Command {
+    match privilege {
+        Privilege::Inherit => Command::new(program),
+        Privilege::Sudo => {
+            let mut command = Command::new("sudo");
+            command.arg(program);
+            command
+        }
+    }
+}
+
+/// コマンドの終了状態を確認する関数
+fn check_status(output: CommandOutput) -> Result<CommandOutput, String> {
+    if output.success {
+        Ok(output)
+    } else {
+        Err(output.stderr.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ata_passthrough::{AtaTransport, Transfer, CDB_LEN};
+    use crate::nvme_passthrough::{AdminCommand, NvmeTransport};
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    /// 実行したコマンド（コマンド名、引数、実行権限、タイムアウト）
+    type Call = (String, Vec<String>, Privilege, Option<Duration>);
+
+    /// 実行したコマンドを記録し、用意した結果を返す偽のランナー
+    #[derive(Default)]
+    struct FakeRunner {
+        calls: Arc<Mutex<Vec<Call>>>,
+        outputs: VecDeque<CommandOutput>,
+    }
+
+    impl FakeRunner {
+        fn respond(mut self, success: bool, stdout: &str, stderr: &str) -> Self {
+            self.outputs.push_back(CommandOutput {
+                success,
+                stdout: stdout.to_string(),
+                stderr: stderr.to_string(),
+            });
+            self
+        }
+    }
+
+    impl CommandRunner for FakeRunner {
+        fn run(
+            &mut self,
+            program: &str,
+            args: &[String],
+            privilege: Privilege,
+            timeout: Option<Duration>,
+        ) -> Result<CommandOutput, String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((program.to_string(), args.to_vec(), privilege, timeout));
+            self.outputs.pop_front().ok_or_else(|| "結果がありません".to_string())
+        }
+    }
+
+    /// どのコマンドにも応答しないトランスポート
+    struct Unresponsive;
+
+    impl AtaTransport for Unresponsive {
+        fn pass_through(&mut self, _cdb: &[u8; CDB_LEN], _transfer: Transfer<'_>, _timeout: Duration)
+            -> Result<Vec<u8>, String> {
+            Err("SG_IOに対応していません".to_string())
+        }
+    }
+
+    impl NvmeTransport for Unresponsive {
+        fn admin(&mut self, _command: &AdminCommand, _data: Option<&mut [u8]>, _timeout: Duration)
+            -> Result<u32, String> {
+            Err("ioctlに対応していません".to_string())
+        }
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn falls_back_to_hdparm_when_pass_through_does_not_respond() {
+        let runner = FakeRunner::default().respond(true, "", "");
+        let calls = runner.calls.clone();
+        let direct = SatBackend::probe(Unresponsive).map(|b| Box::new(b) as Box<dyn AtaBackend>);
+        let mut backend = select_ata("/dev/sda", direct, Privilege::Sudo, Box::new(runner));
+
+        assert_eq!(backend.name(), "hdparm");
+        backend.security_set_password("pw").unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![(
+                "hdparm".to_string(),
+                args(&["--user-master", "u", "--security-set-pass", "pw", "/dev/sda"]),
+                Privilege::Sudo,
+                None
+            )]
+        );
+    }
+
+    #[test]
+    fn keeps_pass_through_when_it_opens() {
+        let runner = FakeRunner::default();
+        let calls = runner.calls.clone();
+        let direct: Result<Box<dyn AtaBackend>, String> = Ok(Box::new(SatBackend::new(Unresponsive)));
+        let backend = select_ata("/dev/sda", direct, Privilege::Inherit, Box::new(runner));
+
+        assert_eq!(backend.name(), "ATA PASS-THROUGH");
+        assert!(calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn hdparm_erase_waits_with_timeout_and_reports_stderr() {
+        let runner = FakeRunner::default().respond(false, "", "  SECURITY_ERASE: Input/output error\n");
+        let calls = runner.calls.clone();
+        let mut backend = HdparmBackend::new("/dev/sdb", Privilege::Inherit, Box::new(runner));
+        let timeout = Duration::from_secs(600);
+
+        assert_eq!(
+            backend.security_erase("pw", true, timeout),
+            Err("SECURITY_ERASE: Input/output error".to_string())
+        );
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![(
+                "hdparm".to_string(),
+                args(&["--user-master", "u", "--security-erase-enhanced", "pw", "/dev/sdb"]),
+                Privilege::Inherit,
+                Some(timeout)
+            )]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_nvme_cli_when_ioctl_does_not_respond() {
+        let runner = FakeRunner::default().respond(true, "", "");
+        let calls = runner.calls.clone();
+        let direct = AdminBackend::probe(Unresponsive).map(|b| Box::new(b) as Box<dyn NvmeBackend>);
+        let mut backend = select_nvme("/dev/nvme0n1", direct, Privilege::Inherit, Box::new(runner));
+
+        assert_eq!(backend.name(), "nvme-cli");
+        backend
+            .format(&FormatNvm {
+                nsid: 1,
+                lba_format: 2,
+                ses: 2,
+                metadata_extended: false,
+                protection_type: 1,
+                protection_first: true,
+            })
+            .unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![(
+                "nvme".to_string(),
+                args(&[
+                    "format",
+                    "/dev/nvme0n1",
+                    "--namespace-id=1",
+                    "--ses=2",
+                    "--lbaf=2",
+                    "--ms=0",
+                    "--pi=1",
+                    "--pil=1",
+                    "--force"
+                ]),
+                Privilege::Inherit,
+                None
+            )]
+        );
+    }
+
+    #[test]
+    fn keeps_ioctl_when_it_opens() {
+        let runner = FakeRunner::default();
+        let calls = runner.calls.clone();
+        let direct: Result<Box<dyn NvmeBackend>, String> = Ok(Box::new(AdminBackend::new(Unresponsive)));
+        let backend = select_nvme("/dev/nvme0n1", direct, Privilege::Sudo, Box::new(runner));
+
+        assert_eq!(backend.name(), "NVMe ioctl");
+        assert!(calls.lock().unwrap().is_empty());
+    }
+}
@@ -2,247 +2,609 @@
 /// 
 /// このモジュールは、システム上の利用可能なディスクを検出し、
 /// ユーザーがディスクを選択できるようにする機能を提供します。
+/// ディスクは`/sys/block`から列挙し、容量、セクタサイズ、回転/リムーバブル/読み取り専用の各フラグ、
+/// ベンダー、モデル、シリアル番号、WWN、ファームウェアのリビジョンをsysfsから取得します。
+/// 光学ドライブ、ループデバイス、zramなど消去対象にならない種類のデバイスは除外されます。
 
-use crate::DiskInfo;
+#[cfg(target_os = "linux")]
+use crate::ata_identify::RotationRate;
+#[cfg(target_os = "linux")]
+use crate::device_backend::{self, Privilege};
 use crate::logger::log_message;
-use std::process::Command;
+use crate::safety;
+use crate::simulation::{self, SimulationConfig};
+#[cfg(target_os = "linux")]
+use crate::SiblingNamespace;
+use crate::{DeviceKind, DiskInfo, EraseCapabilities, MediaKind, Transport};
+use std::fs;
+use std::path::Path;
+
+/// ブロックデバイスの一覧を取得するsysfsのディレクトリ
+const SYS_BLOCK: &str = "/sys/block";
+
+/// sysfsの`size`属性の単位（論理セクタサイズにかかわらず常に512バイト）
+const SYSFS_SECTOR_SIZE: u64 = 512;
 
 /// 利用可能なディスク情報を取得する関数
-pub fn get_available_disks() -> Vec<DiskInfo> {
+///
+/// シミュレーションが有効な場合は実際のディスクを検出せず、仮想ディスクのみを返します。
+///
+/// # 戻り値
+///
+/// * `Result<Vec<DiskInfo>, String>` - 検出したディスクの一覧、検出に失敗した場合は`Err`
+pub fn get_available_disks() -> Result<Vec<DiskInfo>, String> {
+    available_disks(simulation::active_config()?)
+}
+
+/// シミュレーションの設定に応じて仮想ディスクまたは実際のディスクを取得する関数
+///
+/// # 引数
+///
+/// * `simulation` - 有効なシミュレーションの設定（無効な場合は`None`）
+fn available_disks(simulation: Option<SimulationConfig>) -> Result<Vec<DiskInfo>, String> {
+    if let Some(config) = simulation {
+        let disks = simulation::virtual_disks(&config);
+        log_message(
+            "ディスク検出",
+            "完了",
+            &format!(
+                "シミュレーションモードのため、{}台の仮想ディスクを使用します。",
+                disks.len()
+            ),
+        );
+        return Ok(disks);
+    }
+
     log_message(
         "ディスク検出",
         "開始",
         "システム上の利用可能なディスクを検出しています...",
     );
 
-    // lsblk コマンドでディスクのデバイス名を取得
-    let output = match Command::new("lsblk")
-        .arg("-d")
-        .arg("-o")
-        .arg("NAME,TRAN")
-        .output() {
-            Ok(output) => output,
-            Err(e) => {
-                log_message(
-                    "ディスク検出エラー",
-                    "エラー",
-                    &format!("lsblkコマンドの実行に失敗しました: {}", e),
-                );
-                // エラーの場合はダミーデータを返す（開発用）
-                return get_dummy_disks();
-            }
-        };
+    let mut disks = detect_disks(Path::new(SYS_BLOCK))?;
+    for disk in disks.iter_mut() {
+        probe_capabilities(disk);
+    }
+
+    safety::mark_protected(&mut disks);
+
+    log_message(
+        "ディスク検出",
+        "完了",
+        &format!("{}台のディスクが検出されました。", disks.len()),
+    );
+
+    Ok(disks)
+}
+
+/// sysfsのブロックデバイスの一覧から消去対象にできるディスクを検出する関数
+///
+/// 消去対象外の種類のデバイスとメディアが挿入されていないデバイスは除外します。
+/// 属性を読み取れないデバイスがあっても検出は中断しません。
+///
+/// # 引数
+///
+/// * `sys_block` - ブロックデバイスの一覧を取得するディレクトリ（通常は/sys/block）
+///
+/// # 戻り値
+///
+/// * `Result<Vec<DiskInfo>, String>` - 検出したディスクの一覧、ディレクトリを読み取れない場合は`Err`
+fn detect_disks(sys_block: &Path) -> Result<Vec<DiskInfo>, String> {
+    let entries = match fs::read_dir(sys_block) {
+        Ok(entries) => entries,
+        Err(e) => {
+            let message = format!("{}を読み取れませんでした: {}", sys_block.display(), e);
+            log_message("ディスク検出エラー", "エラー", &message);
+            return Err(message);
+        }
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
 
-    let output_str = String::from_utf8_lossy(&output.stdout);
     let mut disks = vec![];
+    for name in names {
+        let Some(disk) = read_sysfs_disk(sys_block, &name) else {
+            continue;
+        };
 
-    // 取得したデバイス名に対してhdparmを実行して詳細情報を取得
-    for line in output_str.lines().skip(1) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.is_empty() {
+        if !disk.kind.is_erasable() {
+            log_message(
+                "ディスク検出",
+                "情報",
+                &format!("{}は消去対象外の種類（{:?}）のため除外します。", disk.device_name, disk.kind),
+            );
+            continue;
+        }
+        if disk.size == 0 {
+            log_message(
+                "ディスク検出",
+                "情報",
+                &format!("{}はメディアが挿入されていないため除外します。", disk.device_name),
+            );
             continue;
         }
 
-        let disk_name = parts[0].trim();
-        let transport = if parts.len() >= 2 { parts[1].trim() } else { "unknown" };
+        disks.push(disk);
+    }
 
-        // NVMeディスクの場合
-        if disk_name.starts_with("nvme") {
-            if let Some(disk_info) = get_nvme_disk_info(disk_name) {
-                disks.push(disk_info);
-                continue;
-            }
-        }
+    Ok(disks)
+}
 
-        // SATA/USBディスクの場合
-        if let Some(disk_info) = get_sata_disk_info(disk_name, transport) {
-            disks.push(disk_info);
+/// sysfsからブロックデバイスの情報を読み取る関数
+///
+/// device-mapper、MD RAID、RAMディスクなどの物理デバイスを持たない仮想デバイスと、
+/// NVMeマルチパスの非表示のパスデバイスは対象外として`None`を返します。
+///
+/// # 引数
+///
+/// * `sys_block` - ブロックデバイスの一覧を取得するディレクトリ（通常は/sys/block）
+/// * `name` - `sys_block`のエントリ名（例: sda、nvme0n1）
+fn read_sysfs_disk(sys_block: &Path, name: &str) -> Option<DiskInfo> {
+    let block = sys_block.join(name);
+    let device = block.join("device");
+
+    if read_attr(&block, "hidden").as_deref() == Some("1") {
+        return None;
+    }
+
+    let kind = if name.starts_with("loop") {
+        DeviceKind::Loop
+    } else if name.starts_with("zram") {
+        DeviceKind::Zram
+    } else if name.starts_with("sr") || read_attr(&device, "type").as_deref() == Some("5") {
+        // SCSIデバイスタイプ5はCD/DVD-ROM
+        DeviceKind::Cdrom
+    } else if name.starts_with("mmcblk") {
+        // eMMCのブートパーティションとRPMBは独立したデバイスとして見えるが消去対象ではない
+        if name.contains("boot") || name.contains("rpmb") {
+            return None;
         }
+        DeviceKind::Mmc
+    } else if device.exists() {
+        DeviceKind::Disk
+    } else {
+        return None;
+    };
+
+    let transport = detect_transport(name, &block);
+    let rotational = read_attr(&block.join("queue"), "rotational").as_deref() == Some("1");
+
+    let model = read_attr(&device, "model")
+        .or_else(|| read_attr(&device, "name"))
+        .unwrap_or_else(|| format!("Disk {}", name));
+    let vendor = read_attr(&device, "vendor").or_else(|| read_attr(&device, "manfid"));
+    let serial = read_attr(&device, "serial")
+        .or_else(|| read_attr(&block, "serial"))
+        .or_else(|| read_vpd_serial(&device));
+    let firmware = read_attr(&device, "firmware_rev")
+        .or_else(|| read_attr(&device, "rev"))
+        .or_else(|| read_attr(&device, "fwrev"));
+    let wwn = read_attr(&block, "wwid")
+        .or_else(|| read_attr(&device, "wwid"))
+        .and_then(|wwid| parse_wwn(&wwid));
+
+    Some(DiskInfo {
+        device_name: format!("/dev/{}", name),
+        model,
+        device_type: if rotational { MediaKind::Hdd } else { MediaKind::Ssd },
+        transport,
+        kind,
+        size: read_number(&block, "size").unwrap_or(0) * SYSFS_SECTOR_SIZE,
+        logical_sector_size: read_number(&block.join("queue"), "logical_block_size")
+            .unwrap_or(SYSFS_SECTOR_SIZE) as u32,
+        physical_sector_size: read_number(&block.join("queue"), "physical_block_size")
+            .unwrap_or(SYSFS_SECTOR_SIZE) as u32,
+        rotational,
+        removable: read_attr(&block, "removable").as_deref() == Some("1"),
+        read_only: read_attr(&block, "ro").as_deref() == Some("1"),
+        vendor,
+        serial,
+        wwn,
+        firmware,
+        capabilities: EraseCapabilities {
+            discard_supported: read_number(&block.join("queue"), "discard_max_bytes").unwrap_or(0) > 0,
+            ..Default::default()
+        },
+        sibling_namespaces: Vec::new(),
+        protected: false,
+        protection_reasons: Vec::new(),
+        simulated: false,
+    })
+}
+
+/// sysfs上のデバイスの位置から接続方式を判定する関数
+fn detect_transport(name: &str, block: &Path) -> Transport {
+    if name.starts_with("nvme") {
+        return Transport::Nvme;
+    }
+    if name.starts_with("mmcblk") {
+        return Transport::Mmc;
     }
 
-    if disks.is_empty() {
-        log_message(
-            "ディスク検出",
-            "警告",
-            "利用可能なディスクが見つかりませんでした。ダミーデータを使用します。",
-        );
-        // ディスクが見つからない場合はダミーデータを返す（開発用）
-        return get_dummy_disks();
+    // /sys/block/<name>はPCIやUSBのバスを辿る/sys/devices以下のパスへのシンボリックリンク
+    let path = fs::canonicalize(block)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if path.contains("/usb") {
+        Transport::Usb
+    } else if path.contains("/ata") {
+        Transport::Sata
+    } else if path.contains("/end_device-") {
+        Transport::Sas
+    } else if path.contains("/virtio") {
+        Transport::Virtio
+    } else {
+        Transport::Unknown
     }
+}
 
-    log_message(
-        "ディスク検出",
-        "完了",
-        &format!("{}台のディスクが検出されました。", disks.len()),
-    );
+/// SCSIデバイスのシリアル番号をVPDページ0x80（Unit Serial Number）から取得する関数
+///
+/// VPDページは4バイトのヘッダに続いてASCIIのシリアル番号が格納されています。
+fn read_vpd_serial(device: &Path) -> Option<String> {
+    let page = fs::read(device.join("vpd_pg80")).ok()?;
+    let length = usize::from(*page.get(3)?);
+    let serial = String::from_utf8_lossy(page.get(4..4 + length)?).trim().to_string();
+    (!serial.is_empty()).then_some(serial)
+}
 
-    disks
+/// sysfsの`wwid`属性からWWNを取得する関数
+///
+/// NAA形式（`naa.5000c500...`）は`0x`で始まる16進数に、EUI-64形式はそのまま返します。
+/// T10ベンダーID形式などWWNではない識別子の場合は`None`を返します。
+fn parse_wwn(wwid: &str) -> Option<String> {
+    if let Some(naa) = wwid.strip_prefix("naa.") {
+        Some(format!("0x{}", naa.to_lowercase()))
+    } else if wwid.starts_with("eui.") {
+        Some(wwid.to_string())
+    } else {
+        None
+    }
 }
 
-/// NVMeディスクの情報を取得する関数
-fn get_nvme_disk_info(disk_name: &str) -> Option<DiskInfo> {
-    log_message(
-        "NVMeディスク検出",
-        "情報",
-        &format!("NVMeディスク {} の情報を取得しています...", disk_name),
-    );
+/// sysfsの属性を読み取る関数（空の場合は`None`）
+fn read_attr(dir: &Path, name: &str) -> Option<String> {
+    let value = fs::read_to_string(dir.join(name)).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
 
-    // nvme id-ctrl コマンドを実行
-    let output = Command::new("sudo")
-        .arg("nvme")
-        .arg("id-ctrl")
-        .arg(format!("/dev/{}", disk_name))
-        .arg("-H")
-        .output();
+/// sysfsの数値の属性を読み取る関数
+fn read_number(dir: &Path, name: &str) -> Option<u64> {
+    read_attr(dir, name)?.parse().ok()
+}
 
-    let output_str = match output {
-        Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
-        Err(e) => {
+/// ディスクが対応している消去機能を調べる関数
+///
+/// NVMeディスクはIdentify Controller、ATAコマンドを発行できる可能性のあるディスクはIDENTIFY DEVICEで調べます。
+/// コマンドはioctlで直接発行し、権限が足りない場合などは`sudo`経由の`nvme`、`hdparm`で発行します。
+/// MMCやvirtioなどATA/NVMeのコマンドを受け付けないディスクにはコマンドを発行しません。
+#[cfg(target_os = "linux")]
+fn probe_capabilities(disk: &mut DiskInfo) {
+    match disk.transport {
+        Transport::Nvme => {
             log_message(
-                "NVMeディスク検出エラー",
-                "警告",
-                &format!("nvmeコマンドの実行に失敗しました: {}", e),
+                "NVMeディスク検出",
+                "情報",
+                &format!("NVMeディスク {} の情報を取得しています...", disk.device_name),
             );
-            return Some(DiskInfo {
-                device_name: format!("/dev/{}", disk_name),
-                model: format!("NVMe Drive {}", disk_name),
-                device_type: "SSD".to_string(), // NVMeはSSDとして扱う
-                transport: "NVMe".to_string(),
-            });
+            probe_nvme(disk);
         }
-    };
+        Transport::Sata | Transport::Usb | Transport::Sas | Transport::Unknown => {
+            log_message(
+                "SATAディスク検出",
+                "情報",
+                &format!("SATAディスク {} の情報を取得しています...", disk.device_name),
+            );
+            let identify = match device_backend::open_ata(&disk.device_name, Privilege::Sudo).identify() {
+                Ok(identify) => identify,
+                Err(e) => {
+                    log_message("SATAディスク検出", "情報", &format!("{}: {}", disk.device_name, e));
+                    return;
+                }
+            };
+            disk.capabilities = EraseCapabilities {
+                discard_supported: disk.capabilities.discard_supported,
+                ..identify.erase_capabilities()
+            };
 
-    let mut model = format!("NVMe Drive {}", disk_name);
+            // SCSI経由のモデル名は16文字に切り詰められているため、ドライブ自身の報告を優先する
+            if let Some(model) = identify.model {
+                disk.model = model;
+            }
+            disk.serial = disk.serial.take().or(identify.serial);
+            disk.firmware = identify.firmware.or(disk.firmware.take());
+            disk.wwn = disk.wwn.take().or(identify.wwn);
 
-    // モデル名を抽出
-    for line in output_str.lines() {
-        if line.contains("mn") || line.contains("Model Number") {
-            if let Some(model_part) = line.split(":").nth(1) {
-                model = model_part.trim().to_string();
-                break;
+            // USB変換アダプタ経由では回転フラグが正しく報告されないため、ドライブ自身の報告を優先する
+            match identify.rotation {
+                Some(RotationRate::SolidState) => {
+                    disk.device_type = MediaKind::Ssd;
+                    disk.rotational = false;
+                }
+                Some(RotationRate::Rpm(_)) => {
+                    disk.device_type = MediaKind::Hdd;
+                    disk.rotational = true;
+                }
+                None => {}
             }
         }
+        Transport::Mmc | Transport::Virtio => {}
     }
-
-    Some(DiskInfo {
-        device_name: format!("/dev/{}", disk_name),
-        model,
-        device_type: "SSD".to_string(), // NVMeはSSDとして扱う
-        transport: "NVMe".to_string(),
-    })
 }
 
-/// SATA/USBディスクの情報を取得する関数
-fn get_sata_disk_info(disk_name: &str, transport: &str) -> Option<DiskInfo> {
-    log_message(
-        "SATAディスク検出",
-        "情報",
-        &format!("SATAディスク {} の情報を取得しています...", disk_name),
-    );
+/// Linux以外では実際のディスクを検出しないため、消去機能も調べない
+#[cfg(not(target_os = "linux"))]
+fn probe_capabilities(_disk: &mut DiskInfo) {}
 
-    // hdparm -I コマンドを実行
-    let output = Command::new("sudo")
-        .arg("hdparm")
-        .arg("-I")
-        .arg(format!("/dev/{}", disk_name))
-        .output();
-
-    let output_str = match output {
-        Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+/// NVMeのコントローラとネームスペースの情報を取得し、ディスク情報に反映する関数
+///
+/// Identify Controller、アクティブなネームスペースの一覧、Identify Namespaceから消去機能を判定します。
+#[cfg(target_os = "linux")]
+fn probe_nvme(disk: &mut DiskInfo) {
+    let mut backend = device_backend::open_nvme(&disk.device_name, Privilege::Sudo);
+    let controller = match backend.identify_controller() {
+        Ok(controller) => controller,
         Err(e) => {
+            log_message("NVMeディスク検出", "情報", &format!("{}: {}", disk.device_name, e));
+            return;
+        }
+    };
+
+    // Formatが他のネームスペースに影響するかを判断するため、コントローラのネームスペース一覧を取得する
+    let namespaces = backend.namespace_list().unwrap_or_default();
+    disk.capabilities = EraseCapabilities {
+        discard_supported: disk.capabilities.discard_supported,
+        ..controller.erase_capabilities(namespaces.len())
+    };
+    let own_nsid = nvme_namespace_id(&disk.device_name);
+    if let Some(controller_path) = nvme_controller_path(&disk.device_name) {
+        disk.sibling_namespaces = namespaces
+            .iter()
+            .filter(|nsid| Some(**nsid) != own_nsid)
+            .map(|nsid| SiblingNamespace {
+                device_name: format!("{}n{}", controller_path, nsid),
+                protected: false,
+            })
+            .collect();
+    }
+
+    if !controller.model.is_empty() {
+        disk.model = controller.model;
+    }
+    if disk.serial.is_none() && !controller.serial.is_empty() {
+        disk.serial = Some(controller.serial);
+    }
+    if !controller.firmware.is_empty() {
+        disk.firmware = Some(controller.firmware);
+    }
+
+    let namespace = own_nsid.and_then(|nsid| backend.identify_namespace(nsid).ok());
+    if let Some(namespace) = namespace {
+        if let (Some(format), Some(size)) = (namespace.format(), namespace.size_bytes()) {
+            disk.logical_sector_size = format.data_size;
             log_message(
-                "SATAディスク検出エラー",
-                "警告",
-                &format!("hdparmコマンドの実行に失敗しました: {}", e),
+                "NVMeディスク検出",
+                "情報",
+                &format!(
+                    "{}: ネームスペース{}（{}/{}）、LBAフォーマット{}（{}バイト）、{}バイト",
+                    disk.device_name,
+                    namespace.nsid,
+                    namespaces.len(),
+                    controller.namespace_count,
+                    namespace.current_format,
+                    format.data_size,
+                    size
+                ),
             );
-            return Some(DiskInfo {
-                device_name: format!("/dev/{}", disk_name),
-                model: format!("Disk {}", disk_name),
-                device_type: "Unknown".to_string(),
-                transport: transport.to_uppercase(),
-            });
         }
-    };
+    }
+}
 
-    let mut model = format!("Disk {}", disk_name);
-    let mut device_type = "Unknown".to_string();
+/// NVMeのネームスペースのデバイス名からコントローラのデバイスのパスを取得する関数
+///
+/// 例: `/dev/nvme0n1` → `/dev/nvme0`
+pub fn nvme_controller_path(device: &str) -> Option<String> {
+    let (controller, _) = device.rsplit_once('n')?;
+    controller.contains("nvme").then(|| controller.to_string())
+}
 
-    // hdparmの出力からモデル名と回転速度を取得
-    for line in output_str.lines() {
-        if line.contains("Model Number") {
-            // モデル名の抽出
-            if let Some(model_number) = line.split(":").nth(1) {
-                model = model_number.trim().to_string();
-            }
+/// NVMeのネームスペースのデバイス名からネームスペースIDを取得する関数
+///
+/// 例: `/dev/nvme0n1` → `1`
+pub fn nvme_namespace_id(device: &str) -> Option<u32> {
+    device.rsplit_once('n')?.1.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    /// 一時ディレクトリに作成したsysfs
+    struct FakeSysfs {
+        dir: TempDir,
+    }
+
+    impl FakeSysfs {
+        fn new() -> Self {
+            let sysfs = FakeSysfs {
+                dir: TempDir::new().unwrap(),
+            };
+            fs::create_dir_all(sysfs.sys_block()).unwrap();
+            sysfs
         }
 
-        if line.contains("Rotation Rate") {
-            // 回転速度からディスクタイプを判別
-            if let Some(rotation_rate) = line.split(":").nth(1) {
-                let rotation_rate = rotation_rate.trim();
-                if rotation_rate == "Solid State Device" {
-                    device_type = "SSD".to_string();
-                } else if let Ok(rate) = rotation_rate.parse::<u32>() {
-                    if rate > 0 {
-                        device_type = "HDD".to_string();
-                    }
-                }
+        fn sys_block(&self) -> PathBuf {
+            self.dir.path().join("block")
+        }
+
+        /// `devices/<parent>/block/<name>`にブロックデバイスを作成し、`block/<name>`から参照する
+        fn block(&self, parent: &str, name: &str, attrs: &[(&str, &str)]) -> PathBuf {
+            let path = self.dir.path().join("devices").join(parent).join("block").join(name);
+            fs::create_dir_all(&path).unwrap();
+            symlink(&path, self.sys_block().join(name)).unwrap();
+            for (attr, value) in attrs {
+                let file = path.join(attr);
+                fs::create_dir_all(file.parent().unwrap()).unwrap();
+                fs::write(file, format!("{}\n", value)).unwrap();
             }
+            path
         }
 
-        // SSDの特徴的な文字列を検索
-        if device_type == "Unknown" && (
-            line.contains("Solid State") || 
-            line.contains("SSD") || 
-            line.contains("Flash")
-        ) {
-            device_type = "SSD".to_string();
+        /// `device`ディレクトリを持つ物理デバイスを作成する
+        fn disk(&self, parent: &str, name: &str, attrs: &[(&str, &str)]) -> PathBuf {
+            let path = self.block(parent, name, attrs);
+            fs::create_dir_all(path.join("device")).unwrap();
+            path
         }
     }
 
-    // ディスクタイプが不明な場合、モデル名からの推測を試みる
-    if device_type == "Unknown" {
-        let model_lower = model.to_lowercase();
-        if model_lower.contains("ssd") || 
-           model_lower.contains("solid") || 
-           model_lower.contains("flash") || 
-           model_lower.contains("nvme") {
-            device_type = "SSD".to_string();
-        } else if model_lower.contains("hdd") || 
-                model_lower.contains("hard drive") || 
-                model_lower.contains("harddisk") {
-            device_type = "HDD".to_string();
+    fn names(disks: &[DiskInfo]) -> Vec<&str> {
+        disks.iter().map(|disk| disk.device_name.as_str()).collect()
+    }
+
+    #[test]
+    fn classifies_device_kinds_and_skips_non_erasable_devices() {
+        let sysfs = FakeSysfs::new();
+        let size = [("size", "2048")];
+        sysfs.disk("pci0000:00/ata1/host0", "sda", &size);
+        sysfs.disk("pci0000:00/ata2/host1", "sr0", &size);
+        sysfs.disk("pci0000:00/ata3/host2", "sdc", &[("size", "2048"), ("device/type", "5")]);
+        sysfs.block("virtual", "loop0", &size);
+        sysfs.block("virtual", "zram0", &size);
+        sysfs.block("virtual", "dm-0", &size);
+        sysfs.disk("mmc_host/mmc0/mmc0:0001", "mmcblk0", &size);
+        sysfs.disk("mmc_host/mmc0/mmc0:0001", "mmcblk0boot0", &size);
+        sysfs.disk("mmc_host/mmc0/mmc0:0001", "mmcblk0rpmb", &size);
+        sysfs.disk("pci0000:00/nvme/nvme0", "nvme0n1", &size);
+        sysfs.disk("pci0000:00/nvme/nvme0", "nvme0c0n1", &[("size", "2048"), ("hidden", "1")]);
+
+        let sys_block = sysfs.sys_block();
+        let kind = |name: &str| read_sysfs_disk(&sys_block, name).map(|disk| disk.kind);
+        assert_eq!(kind("sda"), Some(DeviceKind::Disk));
+        assert_eq!(kind("sr0"), Some(DeviceKind::Cdrom));
+        assert_eq!(kind("sdc"), Some(DeviceKind::Cdrom));
+        assert_eq!(kind("loop0"), Some(DeviceKind::Loop));
+        assert_eq!(kind("zram0"), Some(DeviceKind::Zram));
+        assert_eq!(kind("mmcblk0"), Some(DeviceKind::Mmc));
+        assert_eq!(kind("mmcblk0boot0"), None);
+        assert_eq!(kind("mmcblk0rpmb"), None);
+        assert_eq!(kind("dm-0"), None);
+        assert_eq!(kind("nvme0c0n1"), None);
+
+        let disks = detect_disks(&sys_block).unwrap();
+        assert_eq!(names(&disks), vec!["/dev/mmcblk0", "/dev/nvme0n1", "/dev/sda"]);
+        assert_eq!(disks[0].transport, Transport::Mmc);
+        assert_eq!(disks[1].transport, Transport::Nvme);
+        assert_eq!(disks[2].transport, Transport::Sata);
+    }
+
+    #[test]
+    fn parses_sector_sizes_and_flags() {
+        let sysfs = FakeSysfs::new();
+        sysfs.disk(
+            "pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0",
+            "sda",
+            &[
+                ("size", "3907029168"),
+                ("removable", "0"),
+                ("ro", "0"),
+                ("queue/rotational", "1"),
+                ("queue/logical_block_size", "512"),
+                ("queue/physical_block_size", "4096"),
+                ("queue/discard_max_bytes", "0"),
+                ("device/model", "WDC WD20EZAZ-00L"),
+                ("device/vendor", "ATA"),
+                ("device/rev", "0A80"),
+                ("device/wwid", "naa.5000C500A1B2C3D4"),
+            ],
+        );
+        sysfs.disk(
+            "pci0000:00/0000:00:14.0/usb2/2-1/2-1:1.0/host6/target6:0:0/6:0:0:0",
+            "sdb",
+            &[
+                ("size", "250069680"),
+                ("removable", "1"),
+                ("ro", "1"),
+                ("queue/rotational", "0"),
+                ("queue/logical_block_size", "4096"),
+                ("queue/physical_block_size", "4096"),
+                ("queue/discard_max_bytes", "2147450880"),
+                ("device/model", "Portable SSD"),
+                ("device/wwid", "t10.ATA     Portable SSD"),
+            ],
+        );
+
+        let disks = detect_disks(&sysfs.sys_block()).unwrap();
+        assert_eq!(names(&disks), vec!["/dev/sda", "/dev/sdb"]);
+
+        let hdd = &disks[0];
+        assert_eq!(hdd.size, 3907029168 * 512);
+        assert_eq!((hdd.logical_sector_size, hdd.physical_sector_size), (512, 4096));
+        assert_eq!((hdd.device_type, hdd.rotational), (MediaKind::Hdd, true));
+        assert!(!hdd.removable && !hdd.read_only && !hdd.capabilities.discard_supported);
+        assert_eq!(hdd.transport, Transport::Sata);
+        assert_eq!(hdd.model, "WDC WD20EZAZ-00L");
+        assert_eq!(hdd.vendor.as_deref(), Some("ATA"));
+        assert_eq!(hdd.firmware.as_deref(), Some("0A80"));
+        assert_eq!(hdd.wwn.as_deref(), Some("0x5000c500a1b2c3d4"));
+
+        let usb = &disks[1];
+        assert_eq!(usb.size, 250069680 * 512);
+        assert_eq!((usb.logical_sector_size, usb.physical_sector_size), (4096, 4096));
+        assert_eq!((usb.device_type, usb.rotational), (MediaKind::Ssd, false));
+        assert!(usb.removable && usb.read_only && usb.capabilities.discard_supported);
+        assert_eq!(usb.transport, Transport::Usb);
+        assert_eq!(usb.wwn, None);
+    }
+
+    #[test]
+    fn missing_attributes_do_not_abort_enumeration() {
+        let sysfs = FakeSysfs::new();
+        sysfs.disk("platform/host0", "sda", &[("size", "1024")]);
+        // 容量を読み取れないデバイスはメディアが挿入されていないものとして除外する
+        sysfs.disk("platform/host1", "sdb", &[("size", "unknown")]);
+        sysfs.disk("platform/host2", "sdc", &[]);
+        sysfs.disk(
+            "platform/host3",
+            "sdd",
+            &[("size", "2048"), ("queue/logical_block_size", ""), ("queue/physical_block_size", "abc")],
+        );
+
+        let disks = detect_disks(&sysfs.sys_block()).unwrap();
+        assert_eq!(names(&disks), vec!["/dev/sda", "/dev/sdd"]);
+        for disk in &disks {
+            assert_eq!((disk.logical_sector_size, disk.physical_sector_size), (512, 512));
+            assert_eq!(disk.device_type, MediaKind::Ssd);
+            assert!(!disk.rotational && !disk.removable && !disk.read_only);
+            assert_eq!(disk.transport, Transport::Unknown);
+            assert_eq!((disk.vendor.as_ref(), disk.serial.as_ref(), disk.wwn.as_ref()), (None, None, None));
         }
+        assert_eq!(disks[0].model, "Disk sda");
+        assert_eq!(disks[1].size, 2048 * 512);
+
+        assert!(detect_disks(&sysfs.dir.path().join("missing")).is_err());
     }
 
-    Some(DiskInfo {
-        device_name: format!("/dev/{}", disk_name),
-        model,
-        device_type,
-        transport: transport.to_uppercase(),
-    })
-}
+    #[test]
+    fn simulation_never_mixes_virtual_and_real_disks() {
+        let disks = available_disks(Some(SimulationConfig {
+            enabled: true,
+            ..Default::default()
+        }))
+        .unwrap();
 
-/// 開発用のダミーディスクデータを生成する関数
-fn get_dummy_disks() -> Vec<DiskInfo> {
-    vec![
-        DiskInfo {
-            device_name: "/dev/sda".to_string(),
-            model: "Samsung SSD 970 EVO Plus 1TB".to_string(),
-            device_type: "SSD".to_string(),
-            transport: "SATA".to_string(),
-        },
-        DiskInfo {
-            device_name: "/dev/sdb".to_string(),
-            model: "WD Blue 2TB".to_string(),
-            device_type: "HDD".to_string(),
-            transport: "SATA".to_string(),
-        },
-        DiskInfo {
-            device_name: "/dev/nvme0n1".to_string(),
-            model: "Samsung PM9A1 NVMe 512GB".to_string(),
-            device_type: "SSD".to_string(),
-            transport: "NVME".to_string(),
-        },
-    ]
-} 
\ No newline at end of file
+        assert_eq!(disks.len(), 3);
+        for disk in &disks {
+            assert!(disk.device_name.starts_with(simulation::DEVICE_PREFIX), "{}", disk.device_name);
+            assert!(disk.simulated);
+        }
+    }
+}
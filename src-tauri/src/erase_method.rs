@@ -0,0 +1,174 @@
+/// # 消去方式モジュール
+///
+/// このモジュールは、ディスクに対して実行する消去方式を表す型を提供します。
+/// 消去方式はジョブの状態に記録され、`method`をタグとしたJSONとしてフロントエンドに送信されます。
+///
+/// ```json
+/// { "method": "ata_secure_erase", "enhanced": true }
+/// { "method": "nvme_sanitize", "action": "crypto_erase" }
+/// ```
+use serde::{Deserialize, Serialize};
+
+/// ディスクに対して実行する消去方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum EraseMethod {
+    /// ブロックデバイス全体への上書き（HDDは選択した上書き消去方式、SSDはゼロ書き込み）
+    Overwrite,
+    /// ATA Secure Erase（`enhanced`がtrueの場合はEnhanced Secure Erase）
+    AtaSecureErase { enhanced: bool },
+    /// ATA Sanitize
+    AtaSanitize { action: SanitizeAction },
+    /// NVMe Format（`ses`はSecure Erase Settings: 1=ユーザーデータ消去、2=暗号化消去）
+    NvmeFormat { ses: u8 },
+    /// NVMe Sanitize
+    NvmeSanitize { action: SanitizeAction },
+    /// ブロックデバイス全体のDiscard（TRIM）
+    Discard,
+    /// TCG OpalのRevert（暗号鍵の再生成による初期化）
+    OpalRevert,
+}
+
+/// Sanitizeの操作
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SanitizeAction {
+    /// ブロック消去（すべての物理ブロックを消去する）
+    BlockErase,
+    /// 暗号化消去（ATAではCRYPTO SCRAMBLE）
+    CryptoErase,
+    /// 上書き
+    Overwrite,
+}
+
+/// NVMe Sanitizeのオプション
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NvmeSanitizeOptions {
+    /// 上書きのパス数（1〜16、上書きの場合のみ使用）
+    pub overwrite_passes: u8,
+    /// 上書きに使用する32ビットのパターン
+    pub overwrite_pattern: u32,
+    /// パスごとにパターンを反転するか
+    pub invert_pattern: bool,
+    /// Sanitize後にユーザーデータの割り当て解除を行わないか（No-Deallocate After Sanitize）
+    pub no_deallocate: bool,
+}
+
+impl Default for NvmeSanitizeOptions {
+    fn default() -> Self {
+        NvmeSanitizeOptions {
+            overwrite_passes: 1,
+            overwrite_pattern: 0,
+            invert_pattern: false,
+            no_deallocate: false,
+        }
+    }
+}
+
+impl NvmeSanitizeOptions {
+    /// オプションの指定が妥当かどうかを確認する
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=16).contains(&self.overwrite_passes) {
+            return Err(format!(
+                "NVMe Sanitizeの上書きのパス数は1〜16で指定してください: {}",
+                self.overwrite_passes
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Sanitizeの完了状態（ジョブの結果に記録する）
+#[derive(Serialize, Clone, Debug)]
+pub struct SanitizeReport {
+    /// 実行したSanitizeの消去方式
+    pub method: EraseMethod,
+    /// Sanitizeが正常に完了したか
+    pub completed: bool,
+    /// ドライブが報告した最終状態
+    pub state: String,
+    /// 失敗状態が電源を入れ直しても解除されず、再度Sanitizeを成功させる必要があるか
+    pub failure_persists: bool,
+}
+
+/// 消去方式の実行に失敗した理由
+///
+/// 消去のコマンドをドライブが受け付けた後の失敗では、ドライブが消去を続けていたり、
+/// ロックされたままや失敗状態のまま残っていたりする可能性があるため、次の消去方式にフォールバックしません。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EraseError {
+    /// 消去のコマンドを発行する前に失敗した、またはドライブがコマンドを拒否した
+    NotStarted(String),
+    /// 消去のコマンドをドライブが受け付けた後、または上書きを開始した後に失敗した
+    Started(String),
+    /// 消去のコマンドは完了したが、読み戻し検証がキャンセルされたため消去結果を確認していない
+    Unverified(String),
+}
+
+impl EraseError {
+    /// エラーメッセージ
+    pub fn message(&self) -> &str {
+        match self {
+            EraseError::NotStarted(message)
+            | EraseError::Started(message)
+            | EraseError::Unverified(message) => message,
+        }
+    }
+
+    /// エラーメッセージを取り出す
+    pub fn into_message(self) -> String {
+        match self {
+            EraseError::NotStarted(message)
+            | EraseError::Started(message)
+            | EraseError::Unverified(message) => message,
+        }
+    }
+
+    /// 次の消去方式にフォールバックできるかどうか
+    pub fn allows_fallback(&self) -> bool {
+        matches!(self, EraseError::NotStarted(_))
+    }
+}
+
+impl EraseMethod {
+    /// ログや進捗表示に使用する消去方式の名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            EraseMethod::Overwrite => "上書き消去",
+            EraseMethod::AtaSecureErase { enhanced: true } => "ATA Enhanced Secure Erase",
+            EraseMethod::AtaSecureErase { enhanced: false } => "ATA Secure Erase",
+            EraseMethod::AtaSanitize { action: SanitizeAction::BlockErase } => "ATA Sanitize（ブロック消去）",
+            EraseMethod::AtaSanitize { action: SanitizeAction::CryptoErase } => "ATA Sanitize（暗号化消去）",
+            EraseMethod::AtaSanitize { action: SanitizeAction::Overwrite } => "ATA Sanitize（上書き）",
+            EraseMethod::NvmeFormat { ses: 2 } => "NVMe Format（暗号化消去）",
+            EraseMethod::NvmeFormat { .. } => "NVMe Format（ユーザーデータ消去）",
+            EraseMethod::NvmeSanitize { action: SanitizeAction::BlockErase } => "NVMe Sanitize（ブロック消去）",
+            EraseMethod::NvmeSanitize { action: SanitizeAction::CryptoErase } => "NVMe Sanitize（暗号化消去）",
+            EraseMethod::NvmeSanitize { action: SanitizeAction::Overwrite } => "NVMe Sanitize（上書き）",
+            EraseMethod::Discard => "Discard（TRIM）",
+            EraseMethod::OpalRevert => "TCG Opal Revert",
+        }
+    }
+
+    /// 消去方式の候補の一覧（能力の報告に使用する）
+    pub fn all() -> Vec<EraseMethod> {
+        let actions = [
+            SanitizeAction::CryptoErase,
+            SanitizeAction::BlockErase,
+            SanitizeAction::Overwrite,
+        ];
+        let mut methods = vec![
+            EraseMethod::Overwrite,
+            EraseMethod::AtaSecureErase { enhanced: false },
+            EraseMethod::AtaSecureErase { enhanced: true },
+        ];
+        methods.extend(actions.map(|action| EraseMethod::AtaSanitize { action }));
+        methods.push(EraseMethod::NvmeFormat { ses: 1 });
+        methods.push(EraseMethod::NvmeFormat { ses: 2 });
+        methods.extend(actions.map(|action| EraseMethod::NvmeSanitize { action }));
+        methods.push(EraseMethod::Discard);
+        methods.push(EraseMethod::OpalRevert);
+        methods
+    }
+}
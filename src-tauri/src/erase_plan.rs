@@ -0,0 +1,438 @@
+/// # 消去計画モジュール
+///
+/// このモジュールは、フロントエンドから受け取ったディスクごとの消去計画
+/// （消去方式、読み戻し検証の範囲、上書き消去方式、NVMe Sanitizeのオプション、消去後の処理）を検証し、実行する計画に解決します。
+/// 計画はディスクの対応機能と照合され、1台でも実行できない計画があればジョブ全体を開始しません。
+use crate::config;
+#[cfg(target_os = "linux")]
+use crate::device_backend::{self, Privilege};
+use crate::disk_selection;
+use crate::erase_method::{EraseMethod, NvmeSanitizeOptions};
+use crate::erase_policy::{ErasePolicy, MethodAttempt};
+use crate::erase_standards::{self, OverwriteScheme};
+use crate::logger::log_message;
+use crate::method_support;
+use crate::verification::VerificationLevel;
+use crate::{DiskInfo, MediaKind, Transport};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+#[cfg(target_os = "linux")]
+use std::process::Command;
+
+/// SSDなどを上書き消去する場合と、消去ポリシーで消去方式を選択する場合に、上書き消去方式が指定されていなければ使用する方式
+pub const SSD_OVERWRITE_SCHEME_ID: &str = "nist-800-88-clear";
+
+/// フロントエンドから受け取るディスクごとの消去計画
+#[derive(Deserialize, Debug, Clone)]
+pub struct DiskPlan {
+    /// 消去対象のデバイス名
+    pub device: String,
+    /// 消去方式（省略時は消去ポリシーに従って実行できる方式を順に試す）
+    #[serde(default)]
+    pub method: Option<EraseMethod>,
+    /// 上書き後の読み戻し検証の範囲（省略時は全領域）
+    #[serde(default)]
+    pub verification: VerificationLevel,
+    /// 上書き消去方式のID（省略時は消去方式を指定したHDDはDoD 5220.22-M、それ以外はNIST SP 800-88 Clear）
+    #[serde(default)]
+    pub scheme: Option<String>,
+    /// NVMe Sanitizeのオプション（省略時は1パスの0x00000000で上書きし、Sanitize後に割り当てを解除する）
+    #[serde(default)]
+    pub nvme_sanitize: NvmeSanitizeOptions,
+    /// 消去後の処理
+    #[serde(default)]
+    pub post_erase: PostEraseAction,
+}
+
+/// 消去後の処理
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PostEraseAction {
+    /// 何もしない
+    #[default]
+    None,
+    /// ドライブをスタンバイ状態にする（`hdparm -y`）
+    SpinDown,
+    /// ドライブの電源を切断して取り外せる状態にする（`udisksctl power-off`）
+    PowerOff,
+}
+
+impl PostEraseAction {
+    /// ログに使用する処理の名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            PostEraseAction::None => "なし",
+            PostEraseAction::SpinDown => "スピンダウン",
+            PostEraseAction::PowerOff => "電源切断",
+        }
+    }
+}
+
+/// 検証済みの消去計画
+#[derive(Debug, Clone)]
+pub struct ErasePlan {
+    /// 試す順に並べた消去方式（失敗した場合は次の方式にフォールバックする）
+    pub methods: Vec<EraseMethod>,
+    /// 実行できないためスキップした消去方式
+    pub skipped: Vec<MethodAttempt>,
+    /// 上書き消去に使用する方式
+    pub scheme: OverwriteScheme,
+    /// 上書き後の読み戻し検証の範囲
+    pub verification: VerificationLevel,
+    /// NVMe Sanitizeのオプション
+    pub nvme_sanitize: NvmeSanitizeOptions,
+    /// 消去後の処理
+    pub post_erase: PostEraseAction,
+}
+
+impl ErasePlan {
+    /// 中断した上書き消去を再開するための計画を作成する
+    pub fn resume(scheme: OverwriteScheme, verification: VerificationLevel) -> Self {
+        ErasePlan {
+            methods: vec![EraseMethod::Overwrite],
+            skipped: Vec::new(),
+            scheme,
+            verification,
+            nvme_sanitize: NvmeSanitizeOptions::default(),
+            post_erase: PostEraseAction::None,
+        }
+    }
+}
+
+/// ディスクごとの消去計画を検証し、実行する計画に解決する関数
+///
+/// # 引数
+///
+/// * `plans` - フロントエンドから受け取ったディスクごとの消去計画
+///
+/// # 戻り値
+///
+/// * `Result<Vec<(String, ErasePlan)>, String>` - デバイス名と解決した計画、1台でも実行できない場合はすべての理由を含む`Err`
+pub fn resolve(plans: &[DiskPlan]) -> Result<Vec<(String, ErasePlan)>, String> {
+    if plans.is_empty() {
+        return Err("ディスクが選択されていません。".to_string());
+    }
+
+    let disks = disk_selection::get_available_disks()?;
+    let policy = config::load()?.policy;
+    resolve_against(plans, &disks, &policy)
+}
+
+/// 検出したディスクと消去ポリシーに対して消去計画を検証する関数
+fn resolve_against(
+    plans: &[DiskPlan],
+    disks: &[DiskInfo],
+    policy: &ErasePolicy,
+) -> Result<Vec<(String, ErasePlan)>, String> {
+    let mut seen = HashSet::new();
+    let mut resolved = Vec::new();
+    let mut errors = Vec::new();
+
+    for plan in plans {
+        if !seen.insert(plan.device.as_str()) {
+            errors.push(format!("{}: 同じディスクが複数回指定されています。", plan.device));
+            continue;
+        }
+        let result = disks
+            .iter()
+            .find(|disk| disk.device_name == plan.device)
+            .ok_or_else(|| "ディスクが見つかりません。".to_string())
+            .and_then(|disk| resolve_disk(disk, plan, policy));
+        match result {
+            Ok(erase_plan) => resolved.push((plan.device.clone(), erase_plan)),
+            Err(e) => errors.push(format!("{}: {}", plan.device, e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        log_message("消去計画の検証", "エラー", &errors.join(" / "));
+        return Err(format!(
+            "実行できない消去計画があるため、消去を開始しませんでした。\n{}",
+            errors.join("\n")
+        ));
+    }
+
+    Ok(resolved)
+}
+
+/// 1台のディスクの消去計画を検証する関数
+fn resolve_disk(disk: &DiskInfo, plan: &DiskPlan, policy: &ErasePolicy) -> Result<ErasePlan, String> {
+    plan.verification.validate()?;
+    plan.nvme_sanitize.validate()?;
+
+    // 消去方式を指定した場合はその方式のみを実行し、フォールバックしない
+    let (methods, skipped) = match plan.method {
+        Some(method) => {
+            let reasons = method_support::unavailable_reasons(disk, method);
+            if !reasons.is_empty() {
+                return Err(format!("{}を実行できません: {}", method.name(), reasons.join(" ")));
+            }
+            for warning in method_support::warnings(disk, method) {
+                log_message(&format!("{}の消去計画", disk.device_name), "警告", &warning);
+            }
+            (vec![method], Vec::new())
+        }
+        None => {
+            let chain = policy.chain(disk)?;
+            (chain.methods, chain.skipped)
+        }
+    };
+
+    // 消去ポリシーの最後の上書きは1パスの上書きと検証で実行する
+    let scheme_id = plan.scheme.as_deref().unwrap_or(match (plan.method, disk.device_type) {
+        (Some(_), MediaKind::Hdd) => erase_standards::DEFAULT_SCHEME_ID,
+        _ => SSD_OVERWRITE_SCHEME_ID,
+    });
+    let scheme = erase_standards::find_scheme(scheme_id)?;
+
+    validate_post_erase(disk, plan.post_erase)?;
+
+    Ok(ErasePlan {
+        methods,
+        skipped,
+        scheme,
+        verification: plan.verification,
+        nvme_sanitize: plan.nvme_sanitize,
+        post_erase: plan.post_erase,
+    })
+}
+
+/// 消去後の処理をディスクに対して実行できるかを確認する関数
+fn validate_post_erase(disk: &DiskInfo, action: PostEraseAction) -> Result<(), String> {
+    match action {
+        PostEraseAction::None => Ok(()),
+        PostEraseAction::SpinDown => match disk.transport {
+            Transport::Sata | Transport::Usb => Ok(()),
+            _ => Err(format!(
+                "接続方式{}のディスクはスピンダウンできません。",
+                disk.transport.label()
+            )),
+        },
+        PostEraseAction::PowerOff => {
+            if disk.removable || disk.transport == Transport::Usb {
+                Ok(())
+            } else {
+                Err("内蔵ディスクの電源は切断できません。".to_string())
+            }
+        }
+    }
+}
+
+/// 消去後の処理を実行する関数
+///
+/// # 引数
+///
+/// * `device` - 対象のデバイス名（例: /dev/sda）
+/// * `action` - 消去後の処理
+///
+/// # 戻り値
+///
+/// * `Result<(), String>` - 成功時は`Ok(())`、失敗時はエラーメッセージを含む`Err`
+pub fn run_post_erase(device: &str, action: PostEraseAction) -> Result<(), String> {
+    if action == PostEraseAction::None {
+        return Ok(());
+    }
+
+    // Linux以外では実際のディスクを操作できない
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(format!("この環境では{}の{}を実行できません。", device, action.name()))
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let result = match action {
+            PostEraseAction::None => Ok(()),
+            // STANDBY IMMEDIATEを発行する
+            PostEraseAction::SpinDown => device_backend::open_ata(device, Privilege::Sudo).standby(),
+            PostEraseAction::PowerOff => Command::new("sudo")
+                .args(["udisksctl", "power-off", "-b", device])
+                .output()
+                .map_err(|e| format!("udisksctlコマンドの実行に失敗しました: {}", e))
+                .and_then(|output| {
+                    if output.status.success() {
+                        Ok(())
+                    } else {
+                        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+                    }
+                }),
+        };
+        result.map_err(|e| format!("{}に失敗しました: {}", action.name(), e))?;
+        log_message(
+            &format!("{}の消去後の処理", device),
+            "成功",
+            &format!("{}を実行しました。", action.name()),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::erase_method::SanitizeAction;
+    use crate::SiblingNamespace;
+
+    fn plan(device: &str, method: Option<EraseMethod>) -> DiskPlan {
+        DiskPlan {
+            device: device.to_string(),
+            method,
+            verification: VerificationLevel::Full,
+            scheme: None,
+            nvme_sanitize: NvmeSanitizeOptions::default(),
+            post_erase: PostEraseAction::None,
+        }
+    }
+
+    fn hdd(device: &str) -> DiskInfo {
+        DiskInfo::for_test(device, MediaKind::Hdd, Transport::Sata)
+    }
+
+    fn nvme(device: &str) -> DiskInfo {
+        let mut disk = DiskInfo::for_test(device, MediaKind::Ssd, Transport::Nvme);
+        disk.capabilities.nvme_format_supported = true;
+        disk.capabilities.nvme_crypto_erase_supported = true;
+        disk.capabilities.nvme_sanitize_crypto_supported = true;
+        disk
+    }
+
+    /// 指定した方式の計画を検証し、実行できない場合の理由を返す
+    fn rejection(disk: &DiskInfo, method: EraseMethod) -> String {
+        resolve_disk(disk, &plan(&disk.device_name, Some(method)), &ErasePolicy::default()).unwrap_err()
+    }
+
+    #[test]
+    fn one_unavailable_disk_rejects_the_whole_batch() {
+        let mut read_only = hdd("/dev/sdb");
+        read_only.read_only = true;
+        let disks = vec![hdd("/dev/sda"), read_only];
+        let plans = [
+            plan("/dev/sda", Some(EraseMethod::Overwrite)),
+            plan("/dev/sdb", Some(EraseMethod::Overwrite)),
+            plan("/dev/sdx", None),
+            plan("/dev/sda", None),
+        ];
+
+        let error = resolve_against(&plans, &disks, &ErasePolicy::default()).unwrap_err();
+        let lines: Vec<&str> = error.lines().skip(1).collect();
+        assert_eq!(lines.len(), 3, "{}", error);
+        assert!(lines[0].starts_with("/dev/sdb: ") && lines[0].contains("読み取り専用"));
+        assert!(lines[1].starts_with("/dev/sdx: ") && lines[1].contains("見つかりません"));
+        assert!(lines[2].starts_with("/dev/sda: ") && lines[2].contains("複数回"));
+
+        let resolved = resolve_against(&plans[..1], &disks, &ErasePolicy::default()).unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].0, "/dev/sda");
+    }
+
+    #[test]
+    fn rejects_protected_disks() {
+        let mut disk = hdd("/dev/sda");
+        disk.protected = true;
+        disk.protection_reasons = vec!["/にマウントされています".to_string()];
+        let error = rejection(&disk, EraseMethod::Overwrite);
+        assert!(error.contains("システムで使用中") && error.contains("/にマウントされています"), "{}", error);
+
+        // 消去方式を指定しない場合もポリシーのすべての方式が実行できないため拒否される
+        assert!(resolve_disk(&disk, &plan("/dev/sda", None), &ErasePolicy::default()).is_err());
+    }
+
+    #[test]
+    fn reports_each_unavailable_reason() {
+        let mut frozen = DiskInfo::for_test("/dev/sda", MediaKind::Ssd, Transport::Sata);
+        frozen.capabilities.ata_security_supported = true;
+        frozen.capabilities.ata_security_frozen = true;
+        let mut locked = frozen.clone();
+        locked.capabilities.ata_security_frozen = false;
+        locked.capabilities.ata_security_locked = true;
+        let mut usb = frozen.clone();
+        usb.transport = Transport::Usb;
+        usb.capabilities.ata_security_frozen = false;
+        let mut shared = nvme("/dev/nvme0n1");
+        shared.capabilities.nvme_format_affects_other_namespaces = true;
+        shared.sibling_namespaces = vec![SiblingNamespace {
+            device_name: "/dev/nvme0n2".to_string(),
+            protected: true,
+        }];
+
+        let cases = [
+            (&frozen, EraseMethod::AtaSecureErase { enhanced: false }, "frozen"),
+            (&frozen, EraseMethod::AtaSecureErase { enhanced: true }, "Enhanced Secure Erase"),
+            (&locked, EraseMethod::AtaSecureErase { enhanced: false }, "ロック"),
+            (&usb, EraseMethod::AtaSecureErase { enhanced: false }, "USB変換アダプタ"),
+            (&frozen, EraseMethod::NvmeFormat { ses: 1 }, "NVMeコマンドを使用できません"),
+            (&shared, EraseMethod::AtaSanitize { action: SanitizeAction::BlockErase }, "ATAコマンドを使用できません"),
+            (&shared, EraseMethod::NvmeFormat { ses: 2 }, "/dev/nvme0n2"),
+            (&shared, EraseMethod::NvmeSanitize { action: SanitizeAction::BlockErase }, "ブロック消去"),
+            (&shared, EraseMethod::Discard, "まだ対応していません"),
+        ];
+        for (disk, method, reason) in cases {
+            let error = rejection(disk, method);
+            assert!(error.contains(method.name()) && error.contains(reason), "{:?}: {}", method, error);
+        }
+    }
+
+    #[test]
+    fn explicit_method_is_not_replaced_by_the_policy() {
+        // 対応していない方式を明示した場合は、ポリシーの他の方式にフォールバックせずに拒否する
+        let error = rejection(&nvme("/dev/nvme0n1"), EraseMethod::NvmeSanitize { action: SanitizeAction::Overwrite });
+        assert!(error.contains("対応していません"), "{}", error);
+
+        let method = EraseMethod::NvmeFormat { ses: 2 };
+        let resolved =
+            resolve_disk(&nvme("/dev/nvme0n1"), &plan("/dev/nvme0n1", Some(method)), &ErasePolicy::default()).unwrap();
+        assert_eq!(resolved.methods, vec![method]);
+        assert!(resolved.skipped.is_empty());
+    }
+
+    #[test]
+    fn selects_the_default_scheme() {
+        let policy = ErasePolicy::default();
+        let scheme = |disk: &DiskInfo, plan: &DiskPlan| resolve_disk(disk, plan, &policy).unwrap().scheme.id;
+
+        // 消去方式を指定したHDDはDoD 5220.22-M、それ以外は1パスの検証付き上書き
+        assert_eq!(
+            scheme(&hdd("/dev/sda"), &plan("/dev/sda", Some(EraseMethod::Overwrite))),
+            erase_standards::DEFAULT_SCHEME_ID
+        );
+        assert_eq!(scheme(&hdd("/dev/sda"), &plan("/dev/sda", None)), SSD_OVERWRITE_SCHEME_ID);
+        let ssd = DiskInfo::for_test("/dev/sdb", MediaKind::Ssd, Transport::Sata);
+        assert_eq!(scheme(&ssd, &plan("/dev/sdb", Some(EraseMethod::Overwrite))), SSD_OVERWRITE_SCHEME_ID);
+        assert_eq!(scheme(&ssd, &plan("/dev/sdb", None)), SSD_OVERWRITE_SCHEME_ID);
+
+        let mut gutmann = plan("/dev/sda", Some(EraseMethod::Overwrite));
+        gutmann.scheme = Some("gutmann".to_string());
+        assert_eq!(scheme(&hdd("/dev/sda"), &gutmann), "gutmann");
+    }
+
+    #[test]
+    fn uses_the_policy_chain_when_no_method_is_given() {
+        let resolved =
+            resolve_disk(&nvme("/dev/nvme0n1"), &plan("/dev/nvme0n1", None), &ErasePolicy::default()).unwrap();
+        assert_eq!(
+            resolved.methods,
+            vec![
+                EraseMethod::NvmeSanitize { action: SanitizeAction::CryptoErase },
+                EraseMethod::NvmeFormat { ses: 2 },
+                EraseMethod::NvmeFormat { ses: 1 },
+                EraseMethod::Overwrite,
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_options_and_post_erase_actions() {
+        let disk = nvme("/dev/nvme0n1");
+        let mut sample = plan("/dev/nvme0n1", Some(EraseMethod::Overwrite));
+        sample.verification = VerificationLevel::Sample { percent: 0 };
+        assert!(resolve_disk(&disk, &sample, &ErasePolicy::default()).is_err());
+
+        let mut spin_down = plan("/dev/nvme0n1", Some(EraseMethod::Overwrite));
+        spin_down.post_erase = PostEraseAction::SpinDown;
+        assert!(resolve_disk(&disk, &spin_down, &ErasePolicy::default()).is_err());
+
+        let mut unknown = plan("/dev/nvme0n1", Some(EraseMethod::Overwrite));
+        unknown.scheme = Some("no-such-scheme".to_string());
+        assert!(resolve_disk(&disk, &unknown, &ErasePolicy::default()).is_err());
+    }
+}
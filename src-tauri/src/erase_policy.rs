@@ -0,0 +1,285 @@
+/// # 消去ポリシーモジュール
+///
+/// このモジュールは、消去方式を指定しない場合に試す消去方式の優先順位（フォールバックチェーン）を提供します。
+/// ディスクで実行できない方式はスキップし、実行した方式が消去のコマンドを発行する前に失敗した場合は次の方式を試します。
+/// ドライブがコマンドを受け付けた後に失敗した場合は、ドライブの状態が不明なため次の方式を試しません。
+/// 各方式の試行結果とフォールバックの理由はジョブの状態に記録されます。
+///
+/// 優先順位は設定ファイルの`[policy]`セクションで変更できます。
+///
+/// ```toml
+/// [policy]
+/// ssd = [
+///     { method = "nvme_sanitize", action = "crypto_erase" },
+///     { method = "nvme_format", ses = 2 },
+///     { method = "nvme_sanitize", action = "block_erase" },
+///     { method = "overwrite" },
+/// ]
+/// hdd = [{ method = "ata_sanitize", action = "overwrite" }, { method = "overwrite" }]
+/// ```
+use crate::erase_method::{EraseMethod, SanitizeAction};
+use crate::method_support;
+use crate::{DiskInfo, MediaKind};
+use serde::{Deserialize, Serialize};
+
+/// ディスクの種類ごとの消去方式の優先順位
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields, default)]
+pub struct ErasePolicy {
+    /// SSDで試す消去方式（先頭から順に試す）
+    pub ssd: Vec<EraseMethod>,
+    /// HDDで試す消去方式（先頭から順に試す）
+    pub hdd: Vec<EraseMethod>,
+}
+
+impl Default for ErasePolicy {
+    fn default() -> Self {
+        ErasePolicy {
+            ssd: vec![
+                EraseMethod::NvmeSanitize { action: SanitizeAction::CryptoErase },
+                EraseMethod::NvmeFormat { ses: 2 },
+                EraseMethod::NvmeSanitize { action: SanitizeAction::BlockErase },
+                EraseMethod::AtaSanitize { action: SanitizeAction::CryptoErase },
+                EraseMethod::AtaSanitize { action: SanitizeAction::BlockErase },
+                EraseMethod::AtaSecureErase { enhanced: true },
+                EraseMethod::AtaSecureErase { enhanced: false },
+                EraseMethod::NvmeFormat { ses: 1 },
+                EraseMethod::Overwrite,
+            ],
+            hdd: vec![
+                EraseMethod::AtaSanitize { action: SanitizeAction::Overwrite },
+                EraseMethod::Overwrite,
+            ],
+        }
+    }
+}
+
+/// 消去方式の試行結果
+#[derive(Serialize, Clone, Debug)]
+pub struct MethodAttempt {
+    /// 消去方式
+    pub method: EraseMethod,
+    /// 結果
+    pub outcome: AttemptOutcome,
+    /// スキップまたは失敗した理由
+    pub reason: Option<String>,
+}
+
+/// 消去方式の試行の結果
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptOutcome {
+    /// ディスクで実行できないため試行しなかった
+    Skipped,
+    /// 実行したが失敗した
+    Failed,
+    /// キャンセルにより中断した
+    Cancelled,
+    /// 成功した
+    Succeeded,
+}
+
+/// ポリシーに従ってディスクで試す消去方式の一覧
+#[derive(Debug, Clone)]
+pub struct PolicyChain {
+    /// 試す順に並べた、ディスクで実行できる消去方式
+    pub methods: Vec<EraseMethod>,
+    /// 実行できないためスキップした消去方式
+    pub skipped: Vec<MethodAttempt>,
+}
+
+impl ErasePolicy {
+    /// ディスクの種類に対応する優先順位
+    fn methods_for(&self, device_type: MediaKind) -> Option<&[EraseMethod]> {
+        match device_type {
+            MediaKind::Hdd => Some(&self.hdd),
+            MediaKind::Ssd => Some(&self.ssd),
+            MediaKind::Unknown => None,
+        }
+    }
+
+    /// ディスクで試す消去方式の一覧を作成する
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<PolicyChain, String>` - 試す消去方式とスキップした方式、実行できる方式が1つもない場合は`Err`
+    pub fn chain(&self, disk: &DiskInfo) -> Result<PolicyChain, String> {
+        let candidates = self
+            .methods_for(disk.device_type)
+            .ok_or_else(|| "ディスクの種類が不明なため、消去方式を自動選択できません。".to_string())?;
+
+        let mut chain = PolicyChain {
+            methods: Vec::new(),
+            skipped: Vec::new(),
+        };
+        for &method in candidates {
+            if chain.methods.contains(&method) {
+                continue;
+            }
+            let reasons = method_support::unavailable_reasons(disk, method);
+            // 他のネームスペースにも影響する方式は、明示的に選択した場合のみ実行する
+            let warnings = method_support::warnings(disk, method);
+            if reasons.is_empty() && warnings.is_empty() {
+                chain.methods.push(method);
+            } else {
+                let reason = if reasons.is_empty() {
+                    format!("{}（消去方式を明示的に選択した場合のみ実行します）", warnings.join(" "))
+                } else {
+                    reasons.join(" ")
+                };
+                chain.skipped.push(MethodAttempt {
+                    method,
+                    outcome: AttemptOutcome::Skipped,
+                    reason: Some(reason),
+                });
+            }
+        }
+
+        if chain.methods.is_empty() {
+            return Err(format!(
+                "消去ポリシーに実行できる消去方式がありません: {}",
+                chain
+                    .skipped
+                    .iter()
+                    .map(|a| format!("{}（{}）", a.method.name(), a.reason.as_deref().unwrap_or("")))
+                    .collect::<Vec<_>>()
+                    .join("、")
+            ));
+        }
+
+        Ok(chain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SiblingNamespace, Transport};
+
+    fn nvme_ssd() -> DiskInfo {
+        let mut disk = DiskInfo::for_test("/dev/nvme0n1", MediaKind::Ssd, Transport::Nvme);
+        let caps = &mut disk.capabilities;
+        caps.nvme_format_supported = true;
+        caps.nvme_crypto_erase_supported = true;
+        caps.nvme_sanitize_crypto_supported = true;
+        caps.nvme_sanitize_block_supported = true;
+        disk
+    }
+
+    fn sata_ssd() -> DiskInfo {
+        let mut disk = DiskInfo::for_test("/dev/sda", MediaKind::Ssd, Transport::Sata);
+        let caps = &mut disk.capabilities;
+        caps.ata_security_supported = true;
+        caps.ata_enhanced_erase_supported = true;
+        caps.ata_sanitize_crypto_supported = true;
+        caps.ata_sanitize_block_supported = true;
+        disk
+    }
+
+    fn skipped(chain: &PolicyChain) -> Vec<EraseMethod> {
+        chain.skipped.iter().map(|attempt| attempt.method).collect()
+    }
+
+    #[test]
+    fn nvme_ssd_chain_prefers_sanitize_and_format() {
+        let chain = ErasePolicy::default().chain(&nvme_ssd()).unwrap();
+        assert_eq!(
+            chain.methods,
+            vec![
+                EraseMethod::NvmeSanitize { action: SanitizeAction::CryptoErase },
+                EraseMethod::NvmeFormat { ses: 2 },
+                EraseMethod::NvmeSanitize { action: SanitizeAction::BlockErase },
+                EraseMethod::NvmeFormat { ses: 1 },
+                EraseMethod::Overwrite,
+            ]
+        );
+        assert_eq!(
+            skipped(&chain),
+            vec![
+                EraseMethod::AtaSanitize { action: SanitizeAction::CryptoErase },
+                EraseMethod::AtaSanitize { action: SanitizeAction::BlockErase },
+                EraseMethod::AtaSecureErase { enhanced: true },
+                EraseMethod::AtaSecureErase { enhanced: false },
+            ]
+        );
+        assert!(chain
+            .skipped
+            .iter()
+            .all(|a| a.outcome == AttemptOutcome::Skipped && a.reason.is_some()));
+    }
+
+    #[test]
+    fn sata_ssd_chain_prefers_sanitize_and_secure_erase() {
+        let chain = ErasePolicy::default().chain(&sata_ssd()).unwrap();
+        assert_eq!(
+            chain.methods,
+            vec![
+                EraseMethod::AtaSanitize { action: SanitizeAction::CryptoErase },
+                EraseMethod::AtaSanitize { action: SanitizeAction::BlockErase },
+                EraseMethod::AtaSecureErase { enhanced: true },
+                EraseMethod::AtaSecureErase { enhanced: false },
+                EraseMethod::Overwrite,
+            ]
+        );
+    }
+
+    #[test]
+    fn hdd_chain_uses_sanitize_overwrite_when_supported() {
+        let mut disk = DiskInfo::for_test("/dev/sdb", MediaKind::Hdd, Transport::Sata);
+        assert_eq!(ErasePolicy::default().chain(&disk).unwrap().methods, vec![EraseMethod::Overwrite]);
+
+        disk.capabilities.ata_sanitize_overwrite_supported = true;
+        assert_eq!(
+            ErasePolicy::default().chain(&disk).unwrap().methods,
+            vec![EraseMethod::AtaSanitize { action: SanitizeAction::Overwrite }, EraseMethod::Overwrite]
+        );
+    }
+
+    #[test]
+    fn skips_methods_that_affect_other_namespaces() {
+        let mut disk = nvme_ssd();
+        disk.capabilities.nvme_format_affects_other_namespaces = true;
+        disk.sibling_namespaces = vec![SiblingNamespace {
+            device_name: "/dev/nvme0n2".to_string(),
+            protected: false,
+        }];
+
+        let chain = ErasePolicy::default().chain(&disk).unwrap();
+        let format = [EraseMethod::NvmeFormat { ses: 2 }, EraseMethod::NvmeFormat { ses: 1 }];
+        for method in format {
+            assert!(!chain.methods.contains(&method));
+            let attempt = chain.skipped.iter().find(|a| a.method == method).unwrap();
+            assert!(attempt.reason.as_deref().unwrap().contains("明示的に選択した場合のみ"));
+            // 明示的に選択した場合は実行できる
+            assert!(method_support::unavailable_reasons(&disk, method).is_empty());
+        }
+        assert!(chain.methods.contains(&EraseMethod::NvmeSanitize { action: SanitizeAction::CryptoErase }));
+    }
+
+    #[test]
+    fn ignores_duplicate_methods() {
+        let policy = ErasePolicy {
+            ssd: vec![EraseMethod::Overwrite, EraseMethod::NvmeFormat { ses: 2 }, EraseMethod::Overwrite],
+            hdd: Vec::new(),
+        };
+        let chain = policy.chain(&nvme_ssd()).unwrap();
+        assert_eq!(chain.methods, vec![EraseMethod::Overwrite, EraseMethod::NvmeFormat { ses: 2 }]);
+    }
+
+    #[test]
+    fn fails_without_any_runnable_method() {
+        let unknown = DiskInfo::for_test("/dev/sdc", MediaKind::Unknown, Transport::Sata);
+        assert!(ErasePolicy::default().chain(&unknown).is_err());
+
+        let mut read_only = nvme_ssd();
+        read_only.read_only = true;
+        let error = ErasePolicy::default().chain(&read_only).unwrap_err();
+        assert!(error.contains("読み取り専用"), "{}", error);
+
+        let policy = ErasePolicy {
+            ssd: vec![EraseMethod::AtaSecureErase { enhanced: false }],
+            hdd: Vec::new(),
+        };
+        assert!(policy.chain(&nvme_ssd()).is_err());
+    }
+}
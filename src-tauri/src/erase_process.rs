@@ -1,96 +1,194 @@
 /// # 消去プロセスモジュール
 /// 
 /// このモジュールは、選択されたディスクの消去プロセスを管理します。
-/// ディスクの種類（HDD/SSD）に応じて適切な消去方法を選択します。
+/// 検証済みの消去計画に従って、ディスクごとに消去方式を実行します。
 
+use crate::cancellation::{DiskControl, JobControl};
+use crate::checkpoint::Checkpoint;
 use crate::disk_selection;
+use crate::erase_method::{EraseError, EraseMethod};
+use crate::erase_plan::{self, ErasePlan, PostEraseAction};
+use crate::erase_policy::{AttemptOutcome, MethodAttempt};
 use crate::hdd_erase;
+use crate::job_manager::{DiskJobState, JobManager};
 use crate::logger::log_message;
+use crate::progress::ProgressReporter;
+use crate::safety;
+use crate::simulation;
 use crate::ssd_erase;
+use crate::DiskInfo;
 use rayon::prelude::*;
+use std::collections::HashMap;
+use tauri::AppHandle;
 
 /// 消去プロセスのエントリーポイント
 /// 
 /// # 引数
 /// 
+/// * `app_handle` - 進捗イベントの送信に使用するアプリケーションハンドル
+/// * `jobs` - ディスクごとの消去状態を記録するジョブ管理
+/// * `job` - キャンセル要求を受け取るジョブの制御オブジェクト
 /// * `selected_disks` - 消去対象のディスクのリスト
+/// * `plans` - ディスクごとの検証済みの消去計画
+/// * `resume` - 中断した上書き消去を再開するディスクとそのチェックポイント
 /// 
 /// # 戻り値
 /// 
 /// * `Result<(), String>` - 成功時は`Ok(())`、失敗時はエラーメッセージを含む`Err`
-pub fn start(selected_disks: &Vec<String>) -> Result<(), String> {
+pub fn start(
+    app_handle: &AppHandle,
+    jobs: &JobManager,
+    job: &JobControl,
+    selected_disks: &[String],
+    plans: &HashMap<String, ErasePlan>,
+    resume: &HashMap<String, Checkpoint>,
+) -> Result<(), String> {
     log_message(
         "消去プロセス開始",
         "進行中",
         &format!("選択されたディスク: {:?}", selected_disks),
     );
 
-    // 利用可能なディスク情報を取得
-    let available_disks = disk_selection::get_available_disks();
+    // 利用可能なディスク情報を取得（検出に失敗した場合は各ディスクの失敗理由として報告する）
+    let (available_disks, detection_error) = match disk_selection::get_available_disks() {
+        Ok(disks) => (disks, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
 
     let errors: Vec<String> = selected_disks
         .par_iter()
         .map(|disk_path| {
+            let control = match job.disk(disk_path) {
+                Some(control) => control,
+                None => return format!("ジョブに登録されていないディスクです: {}", disk_path),
+            };
+
+            // 開始前にキャンセルされた場合は何も書き込まずに終了する
+            if control.is_cancelled() {
+                log_message(
+                    &format!("{}の消去キャンセル", disk_path),
+                    "キャンセル",
+                    "消去開始前にキャンセルされたため、ディスクは変更されていません。",
+                );
+                let message = format!("{}の消去はキャンセルされました。", disk_path);
+                jobs.update_disk(job.id(), disk_path, DiskJobState::Cancelled, Some(message.clone()));
+                return message;
+            }
+
+            jobs.update_disk(job.id(), disk_path, DiskJobState::Running, None);
+
             log_message(
                 &format!("{}の消去開始", disk_path),
                 "進行中",
                 "ディスク消去プロセスを開始します。",
             );
 
+            let mut reporter = ProgressReporter::new(Some(app_handle.clone()), job.id(), disk_path);
+
             // 選択されたディスクパスに対応するディスク情報を検索
             let disk_info = available_disks.iter().find(|d| d.device_name == *disk_path);
+            let checkpoint = resume.get(disk_path);
+
+            // システムで使用中のディスクは、設定ファイルで明示的に許可されていない限り消去しない
+            if let Some(Err(e)) = disk_info.map(safety::ensure_erasable) {
+                log_message(&format!("{}の消去拒否", disk_path), "エラー", &e);
+                jobs.update_disk(job.id(), disk_path, DiskJobState::Failed, Some(e.clone()));
+                reporter.finish(&Err(e.clone()));
+                return e;
+            }
+
+            let Some(plan) = plans.get(disk_path) else {
+                let message = format!("消去計画が指定されていません: {}", disk_path);
+                jobs.update_disk(job.id(), disk_path, DiskJobState::Failed, Some(message.clone()));
+                reporter.finish(&Err(message.clone()));
+                return message;
+            };
 
             let result = match disk_info {
-                Some(info) => {
-                    if info.device_type == "HDD" {
-                        log_message(
-                            &format!("{}はHDDとして検出されました", disk_path),
-                            "情報",
-                            "DoD 5220.22-M方式で消去します。",
-                        );
-                        hdd_erase::erase_hdd_with_dod5220(disk_path)
-                    } else if info.device_type == "SSD" {
-                        log_message(
-                            &format!("{}はSSDとして検出されました", disk_path),
-                            "情報",
-                            "Secure Erase方式で消去します。",
-                        );
-                        ssd_erase::secure_erase_ssd(disk_path)
-                    } else {
-                        log_message(
-                            &format!("{}は不明なディスクタイプです: {}", disk_path, info.device_type),
-                            "警告",
-                            "ディスクタイプが不明なため、消去をスキップします。",
-                        );
-                        Err(format!("不明なディスクタイプ: {}。スキップします...", info.device_type))
-                    }
-                },
+                Some(info) => run_plan(disk_path, plan, &control, &mut reporter, |method, reporter| {
+                    run_method(info, method, plan, checkpoint, &control, reporter)
+                }),
                 None => {
                     log_message(
                         &format!("{}の情報が見つかりません", disk_path),
                         "エラー",
                         "ディスク情報が取得できないため、消去をスキップします。",
                     );
-                    Err(format!("ディスク情報が見つかりません: {}。スキップします...", disk_path))
+                    Err(EraseError::NotStarted(match &detection_error {
+                        Some(e) => format!("ディスクを検出できません: {}。スキップします...", e),
+                        None => format!("ディスク情報が見つかりません: {}。スキップします...", disk_path),
+                    }))
                 }
             };
 
+            let state = final_state(&result, reporter.bad_sector_count(), control.is_cancelled());
+            let result = result.map_err(EraseError::into_message);
+            reporter.finish(&result);
+
             match result {
+                // 消去は完了したが書き込めない領域が残っているため、物理破壊が必要
+                Ok(_) if state == DiskJobState::RequiresPhysicalDestruction => {
+                    let message = format!(
+                        "{}に書き込めない不良セクタが{}個あります。物理破壊が必要です。",
+                        disk_path,
+                        reporter.bad_sector_count()
+                    );
+                    log_message(&format!("{}の消去完了", disk_path), "物理破壊が必要", &message);
+                    jobs.update_disk(job.id(), disk_path, state, Some(message.clone()));
+                    message
+                }
                 Ok(_) => {
                     log_message(
                         &format!("{}の消去完了", disk_path),
                         "成功",
                         "ディスクは正常に消去されました。",
                     );
+                    // 消去後の処理の失敗は消去結果に影響しないため、ディスクの状態に記録するのみとする
+                    let post_erase = match (plan.post_erase, disk_info.is_some_and(|d| d.simulated)) {
+                        (PostEraseAction::None, _) => None,
+                        (action, true) => {
+                            log_message(
+                                &format!("{}の消去後の処理", disk_path),
+                                "情報",
+                                &format!("{}をシミュレーションしました。", action.name()),
+                            );
+                            None
+                        }
+                        (action, false) => erase_plan::run_post_erase(disk_path, action)
+                            .inspect_err(|e| {
+                                log_message(&format!("{}の消去後の処理", disk_path), "警告", e)
+                            })
+                            .err()
+                            .map(|e| format!("消去は完了しましたが、消去後の処理に失敗しました: {}", e)),
+                    };
+                    jobs.update_disk(job.id(), disk_path, state, post_erase);
                     String::new() // エラーなし
                 }
+                Err(e) if state == DiskJobState::CompletedUnverified => {
+                    log_message(&format!("{}の消去完了（検証未完了）", disk_path), "検証未完了", &e);
+                    jobs.update_disk(job.id(), disk_path, state, Some(e.clone()));
+                    e
+                }
+                Err(e) if state == DiskJobState::PartiallyErased => {
+                    log_message(
+                        &format!("{}の消去中断", disk_path),
+                        "部分消去",
+                        &format!(
+                            "キャンセルにより消去が中断されました。ディスクは部分的にしか消去されていません: {}",
+                            e
+                        ),
+                    );
+                    jobs.update_disk(job.id(), disk_path, state, Some(e.clone()));
+                    e
+                }
                 Err(e) => {
                     log_message(
                         &format!("{}の消去失敗", disk_path),
                         "エラー",
                         &e,
                     );
-                    e.to_string() // エラーメッセージを返す
+                    jobs.update_disk(job.id(), disk_path, state, Some(e.clone()));
+                    e // エラーメッセージを返す
                 }
             }
         })
@@ -113,4 +211,270 @@ pub fn start(selected_disks: &Vec<String>) -> Result<(), String> {
     }
 
     Ok(())
-} 
\ No newline at end of file
+} 
+
+/// 消去の結果からディスクの最終的な状態を決める関数
+///
+/// # 引数
+///
+/// * `result` - 消去の結果
+/// * `bad_sector_count` - 書き込めなかった不良セクタ数
+/// * `cancelled` - キャンセルが要求されたか
+fn final_state(result: &Result<(), EraseError>, bad_sector_count: u64, cancelled: bool) -> DiskJobState {
+    match result {
+        Ok(()) if bad_sector_count > 0 => DiskJobState::RequiresPhysicalDestruction,
+        Ok(()) => DiskJobState::Succeeded,
+        // 消去のコマンドは完了しているため、部分的な消去として扱わない
+        Err(EraseError::Unverified(_)) => DiskJobState::CompletedUnverified,
+        Err(_) if cancelled => DiskJobState::PartiallyErased,
+        Err(_) => DiskJobState::Failed,
+    }
+}
+
+/// 消去計画の消去方式を順に試し、成功するまでフォールバックする関数
+///
+/// スキップした方式と各方式の試行結果はジョブの状態に記録されます。
+/// キャンセルされた場合と、消去のコマンドをドライブが受け付けた後に失敗した場合は次の方式を試しません。
+///
+/// # 引数
+///
+/// * `disk_path` - 対象のデバイス名
+/// * `plan` - 検証済みの消去計画
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+/// * `run` - 1つの消去方式を実行する関数
+fn run_plan(
+    disk_path: &str,
+    plan: &ErasePlan,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+    mut run: impl FnMut(EraseMethod, &mut ProgressReporter) -> Result<(), EraseError>,
+) -> Result<(), EraseError> {
+    for attempt in &plan.skipped {
+        log_message(
+            &format!("{}の消去方式をスキップ: {}", disk_path, attempt.method.name()),
+            "情報",
+            attempt.reason.as_deref().unwrap_or(""),
+        );
+        reporter.record_attempt(attempt.clone());
+    }
+
+    let mut result = Err(EraseError::NotStarted(format!("{}の消去計画に消去方式がありません。", disk_path)));
+    for (index, &method) in plan.methods.iter().enumerate() {
+        log_message(
+            &format!("{}の消去方式: {}", disk_path, method.name()),
+            "情報",
+            &format!("消去方式の候補{}/{}を実行します。", index + 1, plan.methods.len()),
+        );
+        reporter.record_method(method);
+
+        let error = match run(method, reporter) {
+            Ok(()) => {
+                reporter.record_attempt(MethodAttempt {
+                    method,
+                    outcome: AttemptOutcome::Succeeded,
+                    reason: None,
+                });
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        let outcome = if control.is_cancelled() {
+            AttemptOutcome::Cancelled
+        } else {
+            AttemptOutcome::Failed
+        };
+        reporter.record_attempt(MethodAttempt {
+            method,
+            outcome,
+            reason: Some(error.message().to_string()),
+        });
+        if outcome == AttemptOutcome::Cancelled {
+            return Err(error);
+        }
+
+        // ドライブが消去を続けていたり、ロックや失敗状態のまま残っていたりする可能性があるため、
+        // 他の消去方式のコマンドを発行しない
+        if !error.allows_fallback() {
+            if index + 1 < plan.methods.len() {
+                log_message(
+                    &format!("{}の消去方式をフォールバックしません", disk_path),
+                    "エラー",
+                    &format!(
+                        "{}のコマンドをドライブが受け付けた後に失敗したため、他の消去方式は試しません: {}",
+                        method.name(),
+                        error.message()
+                    ),
+                );
+            }
+            return Err(error);
+        }
+
+        if let Some(next) = plan.methods.get(index + 1) {
+            log_message(
+                &format!("{}の消去方式をフォールバック", disk_path),
+                "警告",
+                &format!("{}に失敗したため、{}を試します: {}", method.name(), next.name(), error.message()),
+            );
+        }
+        result = Err(error);
+    }
+
+    result
+}
+
+/// 1つの消去方式を実行する関数
+fn run_method(
+    info: &DiskInfo,
+    method: EraseMethod,
+    plan: &ErasePlan,
+    checkpoint: Option<&Checkpoint>,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let disk_path = info.device_name.as_str();
+    match (info.simulated, method) {
+        (true, method) => {
+            log_message(
+                &format!("{}は仮想ディスクです", disk_path),
+                "情報",
+                "シミュレーションモードで消去します。ディスクへの書き込みは行いません。",
+            );
+            simulation::erase(info, method, plan, control, reporter)
+        }
+        (false, EraseMethod::Overwrite) => {
+            log_message(
+                &format!("{}は{}として検出されました", disk_path, info.device_type.label()),
+                "情報",
+                &format!(
+                    "{}方式で{}します。",
+                    plan.scheme.name,
+                    if checkpoint.is_some() { "消去を再開" } else { "消去" }
+                ),
+            );
+            hdd_erase::erase_hdd_with_scheme(info, plan, checkpoint, control, reporter)
+        }
+        (false, method) => {
+            ssd_erase::secure_erase_ssd(info, method, plan, control, reporter)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancellation::CancelRegistry;
+    use crate::erase_method::SanitizeAction;
+    use crate::erase_standards;
+    use crate::verification::VerificationLevel;
+    use std::sync::Arc;
+
+    const DEVICE: &str = "/dev/sdz";
+
+    const CRYPTO: EraseMethod = EraseMethod::AtaSanitize { action: SanitizeAction::CryptoErase };
+    const SECURE_ERASE: EraseMethod = EraseMethod::AtaSecureErase { enhanced: false };
+
+    fn plan(methods: &[EraseMethod]) -> ErasePlan {
+        let scheme = erase_standards::find_scheme(erase_plan::SSD_OVERWRITE_SCHEME_ID).unwrap();
+        ErasePlan {
+            methods: methods.to_vec(),
+            ..ErasePlan::resume(scheme, VerificationLevel::Full)
+        }
+    }
+
+    fn control() -> Arc<DiskControl> {
+        CancelRegistry::default()
+            .register(&[DEVICE.to_string()])
+            .disk(DEVICE)
+            .unwrap()
+    }
+
+    /// 消去方式ごとの結果を指定して計画を実行し、結果と実行した消去方式を返す
+    fn run(
+        methods: &[EraseMethod],
+        control: &DiskControl,
+        mut outcome: impl FnMut(EraseMethod, &DiskControl) -> Result<(), EraseError>,
+    ) -> (Result<(), String>, Vec<EraseMethod>) {
+        let mut reporter = ProgressReporter::new(None, "test", DEVICE);
+        let mut tried = Vec::new();
+        let result = run_plan(DEVICE, &plan(methods), control, &mut reporter, |method, _| {
+            tried.push(method);
+            outcome(method, control)
+        });
+        (result.map_err(EraseError::into_message), tried)
+    }
+
+    #[test]
+    fn falls_back_while_nothing_was_started() {
+        let (result, tried) = run(&[CRYPTO, SECURE_ERASE, EraseMethod::Overwrite], &control(), |method, _| {
+            if method == EraseMethod::Overwrite {
+                Ok(())
+            } else {
+                Err(EraseError::NotStarted(format!("{}に対応していません", method.name())))
+            }
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(tried, vec![CRYPTO, SECURE_ERASE, EraseMethod::Overwrite]);
+    }
+
+    #[test]
+    fn reports_the_last_error_when_every_method_fails() {
+        let (result, tried) = run(&[CRYPTO, SECURE_ERASE], &control(), |method, _| {
+            Err(EraseError::NotStarted(method.name().to_string()))
+        });
+        assert_eq!(result, Err(SECURE_ERASE.name().to_string()));
+        assert_eq!(tried, vec![CRYPTO, SECURE_ERASE]);
+    }
+
+    #[test]
+    fn stops_after_a_started_failure() {
+        let (result, tried) = run(&[CRYPTO, SECURE_ERASE, EraseMethod::Overwrite], &control(), |_, _| {
+            Err(EraseError::Started("Sanitizeが失敗状態です".to_string()))
+        });
+        assert!(!EraseError::Started(String::new()).allows_fallback());
+        assert_eq!(result, Err("Sanitizeが失敗状態です".to_string()));
+        assert_eq!(tried, vec![CRYPTO]);
+    }
+
+    #[test]
+    fn stops_when_cancelled() {
+        let (result, tried) = run(&[CRYPTO, SECURE_ERASE, EraseMethod::Overwrite], &control(), |_, control| {
+            control.request_cancel().unwrap();
+            Err(EraseError::NotStarted(control.check().unwrap_err()))
+        });
+        assert!(result.is_err());
+        assert_eq!(tried, vec![CRYPTO]);
+    }
+
+    #[test]
+    fn rejects_a_plan_without_methods() {
+        let (result, tried) = run(&[], &control(), |_, _| Ok(()));
+        assert!(result.is_err());
+        assert!(tried.is_empty());
+    }
+
+    #[test]
+    fn stops_when_verification_is_cancelled() {
+        let (result, tried) = run(&[CRYPTO, EraseMethod::Overwrite], &control(), |_, control| {
+            control.request_cancel().unwrap();
+            Err(EraseError::Unverified("読み戻し検証がキャンセルされました".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(tried, vec![CRYPTO]);
+    }
+
+    #[test]
+    fn maps_results_to_disk_states() {
+        let failed = Err(EraseError::Started("失敗".to_string()));
+        let unverified = Err(EraseError::Unverified("検証がキャンセルされました".to_string()));
+        assert_eq!(final_state(&Ok(()), 0, false), DiskJobState::Succeeded);
+        assert_eq!(final_state(&Ok(()), 3, false), DiskJobState::RequiresPhysicalDestruction);
+        // 不良セクタを記録しつつ最後まで書き込んだ場合は、完了後のキャンセル要求より物理破壊を優先する
+        assert_eq!(final_state(&Ok(()), 3, true), DiskJobState::RequiresPhysicalDestruction);
+        assert_eq!(final_state(&failed, 0, false), DiskJobState::Failed);
+        assert_eq!(final_state(&failed, 3, false), DiskJobState::Failed);
+        assert_eq!(final_state(&failed, 0, true), DiskJobState::PartiallyErased);
+        assert_eq!(final_state(&unverified, 0, true), DiskJobState::CompletedUnverified);
+    }
+}
@@ -0,0 +1,629 @@
+/// # 上書き消去規格モジュール
+///
+/// このモジュールは、名前付きの上書き消去方式（規格）のカタログを提供します。
+/// 各方式は、書き込むパターンと読み戻し検証の有無を持つパスの列として定義されます。
+/// 組み込みの方式に加えて、設定ファイルで定義された独自の方式も検証のうえ利用できます。
+use crate::config::{self, PatternDefinition, SchemeDefinition};
+use crate::overwrite::FillPattern;
+use serde::Serialize;
+
+/// ユーザー定義の方式に許可する最大パス数
+const MAX_CUSTOM_PASSES: usize = 64;
+
+/// 繰り返しパターンに許可する最大バイト数
+const MAX_PATTERN_BYTES: usize = 512;
+
+/// 既定の上書き消去方式のID
+pub const DEFAULT_SCHEME_ID: &str = "dod-5220.22-m";
+
+/// 1パスの定義
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassSpec {
+    /// 書き込むパターン
+    pub pattern: FillPattern,
+    /// 書き込み後に読み戻して検証するか
+    pub verify: bool,
+}
+
+impl PassSpec {
+    /// 検証を行わないパスを作成する
+    fn write(pattern: FillPattern) -> Self {
+        PassSpec {
+            pattern,
+            verify: false,
+        }
+    }
+
+    /// 書き込み後に検証を行うパスを作成する
+    fn verified(pattern: FillPattern) -> Self {
+        PassSpec {
+            pattern,
+            verify: true,
+        }
+    }
+}
+
+/// 上書き消去方式の定義
+#[derive(Debug, Clone)]
+pub struct OverwriteScheme {
+    /// 方式のID（例: "dod-5220.22-m"）
+    pub id: String,
+    /// 表示名
+    pub name: String,
+    /// 方式の説明
+    pub description: String,
+    /// パスの列
+    pub passes: Vec<PassSpec>,
+}
+
+/// フロントエンドへ返す上書き消去方式の概要
+#[derive(Serialize, Clone)]
+pub struct SchemeSummary {
+    /// 方式のID
+    pub id: String,
+    /// 表示名
+    pub name: String,
+    /// 方式の説明
+    pub description: String,
+    /// 各パスのパターンの説明
+    pub passes: Vec<String>,
+    /// 検証を行うパスの番号（1始まり）
+    pub verified_passes: Vec<u32>,
+}
+
+impl OverwriteScheme {
+    /// 組み込みの方式を作成する
+    fn builtin(id: &str, name: &str, description: &str, passes: Vec<PassSpec>) -> Self {
+        OverwriteScheme {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            passes,
+        }
+    }
+
+    /// フロントエンド向けの概要を作成する
+    pub fn summary(&self) -> SchemeSummary {
+        SchemeSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            passes: self.passes.iter().map(|p| p.pattern.description()).collect(),
+            verified_passes: self
+                .passes
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.verify)
+                .map(|(i, _)| i as u32 + 1)
+                .collect(),
+        }
+    }
+}
+
+/// 固定バイト値のパターン
+fn byte(value: u8) -> FillPattern {
+    FillPattern::Byte(value)
+}
+
+/// 複数バイトの繰り返しパターン
+fn repeat(bytes: &[u8]) -> FillPattern {
+    FillPattern::Repeat(bytes.to_vec())
+}
+
+/// Gutmann方式の35パス
+///
+/// 先頭と末尾の4パスはランダムデータ、5〜31パス目はMFM/RLLエンコーディングを
+/// 想定した固定パターンです。
+fn gutmann_passes() -> Vec<PassSpec> {
+    let patterns: [&[u8]; 27] = [
+        &[0x55],
+        &[0xAA],
+        &[0x92, 0x49, 0x24],
+        &[0x49, 0x24, 0x92],
+        &[0x24, 0x92, 0x49],
+        &[0x00],
+        &[0x11],
+        &[0x22],
+        &[0x33],
+        &[0x44],
+        &[0x55],
+        &[0x66],
+        &[0x77],
+        &[0x88],
+        &[0x99],
+        &[0xAA],
+        &[0xBB],
+        &[0xCC],
+        &[0xDD],
+        &[0xEE],
+        &[0xFF],
+        &[0x92, 0x49, 0x24],
+        &[0x49, 0x24, 0x92],
+        &[0x24, 0x92, 0x49],
+        &[0x6D, 0xB6, 0xDB],
+        &[0xB6, 0xDB, 0x6D],
+        &[0xDB, 0x6D, 0xB6],
+    ];
+
+    let mut passes = vec![PassSpec::write(FillPattern::Random); 4];
+    passes.extend(patterns.iter().map(|bytes| {
+        if bytes.len() == 1 {
+            PassSpec::write(byte(bytes[0]))
+        } else {
+            PassSpec::write(repeat(bytes))
+        }
+    }));
+    passes.extend(vec![PassSpec::write(FillPattern::Random); 4]);
+    passes
+}
+
+/// 組み込みの上書き消去方式の一覧を返す関数
+pub fn builtin_schemes() -> Vec<OverwriteScheme> {
+    vec![
+        OverwriteScheme::builtin(
+            "nist-800-88-clear",
+            "NIST SP 800-88 Clear",
+            "ゼロを1回書き込み、読み戻して検証します。",
+            vec![PassSpec::verified(byte(0x00))],
+        ),
+        OverwriteScheme::builtin(
+            DEFAULT_SCHEME_ID,
+            "DoD 5220.22-M（3パス）",
+            "0x00、0xFF、ランダムデータの順に書き込み、最終パスを検証します。",
+            vec![
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(byte(0xFF)),
+                PassSpec::verified(FillPattern::Random),
+            ],
+        ),
+        OverwriteScheme::builtin(
+            "dod-5220.22-m-ece",
+            "DoD 5220.22-M ECE（7パス）",
+            "DoD 5220.22-M（E）の3パス、ランダムデータ1パス、再度DoD 5220.22-M（E）の3パスを書き込みます。",
+            vec![
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(byte(0xFF)),
+                PassSpec::write(FillPattern::Random),
+                PassSpec::write(FillPattern::Random),
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(byte(0xFF)),
+                PassSpec::verified(FillPattern::Random),
+            ],
+        ),
+        OverwriteScheme::builtin(
+            "gutmann",
+            "Gutmann（35パス）",
+            "ランダムデータ4パス、27種類の固定パターン、ランダムデータ4パスを書き込みます。",
+            gutmann_passes(),
+        ),
+        OverwriteScheme::builtin(
+            "hmg-is5-baseline",
+            "HMG IS5 Baseline",
+            "ゼロを1回書き込み、読み戻して検証します。",
+            vec![PassSpec::verified(byte(0x00))],
+        ),
+        OverwriteScheme::builtin(
+            "hmg-is5-enhanced",
+            "HMG IS5 Enhanced",
+            "0x00、0xFF、ランダムデータの順に書き込み、最終パスを検証します。",
+            vec![
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(byte(0xFF)),
+                PassSpec::verified(FillPattern::Random),
+            ],
+        ),
+        OverwriteScheme::builtin(
+            "bsi-vsitr",
+            "BSI VSITR（7パス）",
+            "0x00と0xFFを交互に6回書き込み、最後に0xAAを書き込みます。",
+            vec![
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(byte(0xFF)),
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(byte(0xFF)),
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(byte(0xFF)),
+                PassSpec::write(byte(0xAA)),
+            ],
+        ),
+        OverwriteScheme::builtin(
+            "schneier",
+            "Schneier（7パス）",
+            "0xFF、0x00を書き込んだ後、ランダムデータを5回書き込みます。",
+            vec![
+                PassSpec::write(byte(0xFF)),
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(FillPattern::Random),
+                PassSpec::write(FillPattern::Random),
+                PassSpec::write(FillPattern::Random),
+                PassSpec::write(FillPattern::Random),
+                PassSpec::write(FillPattern::Random),
+            ],
+        ),
+        OverwriteScheme::builtin(
+            "gost-r-50739-95",
+            "GOST R 50739-95（2パス）",
+            "ゼロを書き込んだ後、ランダムデータを書き込みます。",
+            vec![
+                PassSpec::write(byte(0x00)),
+                PassSpec::write(FillPattern::Random),
+            ],
+        ),
+    ]
+}
+
+/// ユーザー定義の方式を検証し、上書き消去方式に変換する関数
+///
+/// 補数パスは直前のパスのパターンをビット反転した固定パターンに展開されます。
+///
+/// # 引数
+///
+/// * `definition` - 設定ファイルで定義された方式
+///
+/// # 戻り値
+///
+/// * `Result<OverwriteScheme, String>` - 定義が不正な場合は理由を含む`Err`
+pub fn scheme_from_definition(definition: &SchemeDefinition) -> Result<OverwriteScheme, String> {
+    let id = definition.id.trim();
+    if id.is_empty() {
+        return Err("上書き消去方式のIDが空です。".to_string());
+    }
+    if definition.name.trim().is_empty() {
+        return Err(format!("上書き消去方式{}の名前が空です。", id));
+    }
+    if definition.passes.is_empty() {
+        return Err(format!("上書き消去方式{}にパスが定義されていません。", id));
+    }
+    if definition.passes.len() > MAX_CUSTOM_PASSES {
+        return Err(format!(
+            "上書き消去方式{}のパス数{}が上限{}を超えています。",
+            id,
+            definition.passes.len(),
+            MAX_CUSTOM_PASSES
+        ));
+    }
+
+    let mut passes: Vec<PassSpec> = Vec::with_capacity(definition.passes.len());
+    for (i, pass) in definition.passes.iter().enumerate() {
+        let pattern = match &pass.pattern {
+            PatternDefinition::Byte { value } => FillPattern::Byte(*value),
+            PatternDefinition::Pattern { bytes } => {
+                if bytes.is_empty() || bytes.len() > MAX_PATTERN_BYTES {
+                    return Err(format!(
+                        "上書き消去方式{}のパス{}: パターンは1〜{}バイトで指定してください。",
+                        id,
+                        i + 1,
+                        MAX_PATTERN_BYTES
+                    ));
+                }
+                FillPattern::Repeat(bytes.clone())
+            }
+            PatternDefinition::Random => FillPattern::Random,
+            PatternDefinition::Lba => FillPattern::LbaStamp,
+            PatternDefinition::Complement => {
+                let previous = passes.last().ok_or_else(|| {
+                    format!("上書き消去方式{}: 最初のパスに補数は指定できません。", id)
+                })?;
+                match &previous.pattern {
+                    FillPattern::Byte(value) => FillPattern::Byte(!value),
+                    FillPattern::Repeat(bytes) => {
+                        FillPattern::Repeat(bytes.iter().map(|b| !b).collect())
+                    }
+                    FillPattern::Random | FillPattern::LbaStamp => {
+                        return Err(format!(
+                            "上書き消去方式{}のパス{}: ランダムデータまたはLBAスタンプの補数は指定できません。",
+                            id,
+                            i + 1
+                        ));
+                    }
+                }
+            }
+        };
+
+        passes.push(PassSpec {
+            pattern,
+            verify: pass.verify,
+        });
+    }
+
+    Ok(OverwriteScheme {
+        id: id.to_string(),
+        name: definition.name.trim().to_string(),
+        description: definition.description.clone(),
+        passes,
+    })
+}
+
+/// 組み込みの方式と設定ファイルで定義された方式をすべて返す関数
+///
+/// # 戻り値
+///
+/// * `Result<Vec<OverwriteScheme>, String>` - 設定ファイルが不正な場合は理由を含む`Err`
+pub fn all_schemes() -> Result<Vec<OverwriteScheme>, String> {
+    let mut schemes = builtin_schemes();
+
+    for definition in &config::load()?.schemes {
+        let scheme = scheme_from_definition(definition)?;
+        if schemes.iter().any(|s| s.id == scheme.id) {
+            return Err(format!("上書き消去方式のIDが重複しています: {}", scheme.id));
+        }
+        schemes.push(scheme);
+    }
+
+    Ok(schemes)
+}
+
+/// IDから上書き消去方式を検索する関数
+///
+/// 組み込みの方式は設定ファイルを読み込まずに返すため、
+/// 設定ファイルに誤りがあっても組み込みの方式は利用できます。
+///
+/// # 引数
+///
+/// * `id` - 方式のID
+///
+/// # 戻り値
+///
+/// * `Result<OverwriteScheme, String>` - 見つからない場合はエラーメッセージを含む`Err`
+pub fn find_scheme(id: &str) -> Result<OverwriteScheme, String> {
+    if let Some(scheme) = builtin_schemes().into_iter().find(|scheme| scheme.id == id) {
+        return Ok(scheme);
+    }
+
+    all_schemes()?
+        .into_iter()
+        .find(|scheme| scheme.id == id)
+        .ok_or_else(|| format!("不明な上書き消去方式です: {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PassDefinition;
+
+    /// 指定したパターンのパスからなるユーザー定義の方式
+    fn definition(patterns: Vec<PatternDefinition>) -> SchemeDefinition {
+        SchemeDefinition {
+            id: "custom".to_string(),
+            name: "独自方式".to_string(),
+            description: String::new(),
+            passes: patterns
+                .into_iter()
+                .map(|pattern| PassDefinition { pattern, verify: false })
+                .collect(),
+        }
+    }
+
+    fn patterns(scheme: &OverwriteScheme) -> Vec<FillPattern> {
+        scheme.passes.iter().map(|p| p.pattern.clone()).collect()
+    }
+
+    /// 組み込みの方式のパスを（パターン, 検証するか）の列で返す
+    fn builtin(id: &str) -> Vec<(FillPattern, bool)> {
+        builtin_schemes()
+            .into_iter()
+            .find(|scheme| scheme.id == id)
+            .unwrap_or_else(|| panic!("組み込みの方式{}がありません", id))
+            .passes
+            .into_iter()
+            .map(|p| (p.pattern, p.verify))
+            .collect()
+    }
+
+    const RANDOM: FillPattern = FillPattern::Random;
+
+    #[test]
+    fn builtin_pass_counts() {
+        let expected = [
+            ("nist-800-88-clear", 1),
+            (DEFAULT_SCHEME_ID, 3),
+            ("dod-5220.22-m-ece", 7),
+            ("gutmann", 35),
+            ("hmg-is5-baseline", 1),
+            ("hmg-is5-enhanced", 3),
+            ("bsi-vsitr", 7),
+            ("schneier", 7),
+            ("gost-r-50739-95", 2),
+        ];
+        let schemes = builtin_schemes();
+        assert_eq!(schemes.len(), expected.len());
+        for (id, count) in expected {
+            assert_eq!(builtin(id).len(), count, "{}", id);
+        }
+    }
+
+    #[test]
+    fn builtin_ids_are_unique() {
+        let mut ids: Vec<String> = builtin_schemes().into_iter().map(|s| s.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), builtin_schemes().len());
+    }
+
+    #[test]
+    fn builtin_patterns() {
+        let table: [(&str, Vec<(FillPattern, bool)>); 8] = [
+            ("nist-800-88-clear", vec![(byte(0x00), true)]),
+            (
+                DEFAULT_SCHEME_ID,
+                vec![(byte(0x00), false), (byte(0xFF), false), (RANDOM, true)],
+            ),
+            (
+                "dod-5220.22-m-ece",
+                vec![
+                    (byte(0x00), false),
+                    (byte(0xFF), false),
+                    (RANDOM, false),
+                    (RANDOM, false),
+                    (byte(0x00), false),
+                    (byte(0xFF), false),
+                    (RANDOM, true),
+                ],
+            ),
+            ("hmg-is5-baseline", vec![(byte(0x00), true)]),
+            (
+                "hmg-is5-enhanced",
+                vec![(byte(0x00), false), (byte(0xFF), false), (RANDOM, true)],
+            ),
+            (
+                "bsi-vsitr",
+                vec![
+                    (byte(0x00), false),
+                    (byte(0xFF), false),
+                    (byte(0x00), false),
+                    (byte(0xFF), false),
+                    (byte(0x00), false),
+                    (byte(0xFF), false),
+                    (byte(0xAA), false),
+                ],
+            ),
+            (
+                "schneier",
+                vec![
+                    (byte(0xFF), false),
+                    (byte(0x00), false),
+                    (RANDOM, false),
+                    (RANDOM, false),
+                    (RANDOM, false),
+                    (RANDOM, false),
+                    (RANDOM, false),
+                ],
+            ),
+            ("gost-r-50739-95", vec![(byte(0x00), false), (RANDOM, false)]),
+        ];
+        for (id, passes) in table {
+            assert_eq!(builtin(id), passes, "{}", id);
+        }
+    }
+
+    #[test]
+    fn gutmann_patterns() {
+        let expected: Vec<FillPattern> = [RANDOM, RANDOM, RANDOM, RANDOM]
+            .into_iter()
+            .chain([
+                byte(0x55),
+                byte(0xAA),
+                repeat(&[0x92, 0x49, 0x24]),
+                repeat(&[0x49, 0x24, 0x92]),
+                repeat(&[0x24, 0x92, 0x49]),
+            ])
+            .chain((0x00..=0xFF).step_by(0x11).map(byte))
+            .chain([
+                repeat(&[0x92, 0x49, 0x24]),
+                repeat(&[0x49, 0x24, 0x92]),
+                repeat(&[0x24, 0x92, 0x49]),
+                repeat(&[0x6D, 0xB6, 0xDB]),
+                repeat(&[0xB6, 0xDB, 0x6D]),
+                repeat(&[0xDB, 0x6D, 0xB6]),
+            ])
+            .chain([RANDOM, RANDOM, RANDOM, RANDOM])
+            .collect();
+
+        let passes = builtin("gutmann");
+        assert_eq!(passes.iter().map(|(p, _)| p.clone()).collect::<Vec<_>>(), expected);
+        assert!(passes.iter().all(|(_, verify)| !verify));
+    }
+
+    #[test]
+    fn complement_reproduces_builtin_pass_pairs() {
+        // DoD 5220.22-Mの2パス目とGutmannの最後の固定パターンは直前のパスの補数
+        let dod = scheme_from_definition(&definition(vec![
+            PatternDefinition::Byte { value: 0x00 },
+            PatternDefinition::Complement,
+            PatternDefinition::Random,
+        ]))
+        .unwrap();
+        assert_eq!(
+            patterns(&dod),
+            builtin(DEFAULT_SCHEME_ID).into_iter().map(|(p, _)| p).collect::<Vec<_>>()
+        );
+
+        let gutmann = scheme_from_definition(&definition(vec![
+            PatternDefinition::Pattern { bytes: vec![0x24, 0x92, 0x49] },
+            PatternDefinition::Complement,
+        ]))
+        .unwrap();
+        assert_eq!(patterns(&gutmann)[1], builtin("gutmann")[30].0);
+    }
+
+    #[test]
+    fn expands_complement_of_a_byte() {
+        let scheme = scheme_from_definition(&definition(vec![
+            PatternDefinition::Byte { value: 0x35 },
+            PatternDefinition::Complement,
+            PatternDefinition::Complement,
+        ]))
+        .unwrap();
+        assert_eq!(patterns(&scheme), vec![byte(0x35), byte(0xCA), byte(0x35)]);
+    }
+
+    #[test]
+    fn expands_complement_of_a_repeat_pattern() {
+        let scheme = scheme_from_definition(&definition(vec![
+            PatternDefinition::Pattern { bytes: vec![0x92, 0x49, 0x24] },
+            PatternDefinition::Complement,
+        ]))
+        .unwrap();
+        assert_eq!(
+            patterns(&scheme),
+            vec![repeat(&[0x92, 0x49, 0x24]), repeat(&[0x6D, 0xB6, 0xDB])]
+        );
+    }
+
+    #[test]
+    fn keeps_the_verify_flag_of_each_pass() {
+        let mut definition = definition(vec![PatternDefinition::Byte { value: 0 }, PatternDefinition::Complement]);
+        definition.passes[1].verify = true;
+        let scheme = scheme_from_definition(&definition).unwrap();
+        assert_eq!(scheme.passes.iter().map(|p| p.verify).collect::<Vec<_>>(), vec![false, true]);
+    }
+
+    #[test]
+    fn rejects_complement_as_the_first_pass() {
+        assert!(scheme_from_definition(&definition(vec![PatternDefinition::Complement])).is_err());
+    }
+
+    #[test]
+    fn rejects_complement_of_random_or_lba_stamp() {
+        for previous in [PatternDefinition::Random, PatternDefinition::Lba] {
+            assert!(scheme_from_definition(&definition(vec![previous, PatternDefinition::Complement])).is_err());
+        }
+    }
+
+    #[test]
+    fn bounds_the_number_of_passes() {
+        assert!(scheme_from_definition(&definition(Vec::new())).is_err());
+        assert_eq!(
+            scheme_from_definition(&definition(vec![PatternDefinition::Random; MAX_CUSTOM_PASSES]))
+                .unwrap()
+                .passes
+                .len(),
+            MAX_CUSTOM_PASSES
+        );
+        assert!(scheme_from_definition(&definition(vec![PatternDefinition::Random; MAX_CUSTOM_PASSES + 1])).is_err());
+    }
+
+    #[test]
+    fn bounds_the_length_of_repeat_patterns() {
+        assert!(scheme_from_definition(&definition(vec![PatternDefinition::Pattern { bytes: Vec::new() }])).is_err());
+        assert!(scheme_from_definition(&definition(vec![PatternDefinition::Pattern {
+            bytes: vec![0xA5; MAX_PATTERN_BYTES]
+        }]))
+        .is_ok());
+        assert!(scheme_from_definition(&definition(vec![PatternDefinition::Pattern {
+            bytes: vec![0xA5; MAX_PATTERN_BYTES + 1]
+        }]))
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_blank_id_or_name() {
+        let mut blank_id = definition(vec![PatternDefinition::Random]);
+        blank_id.id = "  ".to_string();
+        assert!(scheme_from_definition(&blank_id).is_err());
+
+        let mut blank_name = definition(vec![PatternDefinition::Random]);
+        blank_name.name = String::new();
+        assert!(scheme_from_definition(&blank_name).is_err());
+    }
+}
@@ -1,123 +1,106 @@
 /// # HDD消去モジュール
-/// 
+///
 /// このモジュールは、HDDのデータを安全に消去するための機能を提供します。
-/// DoD 5220.22-M方式（3パス）を使用して、データを完全に消去します。
+/// 選択された上書き消去方式（既定はDoD 5220.22-M 3パス）を使用して、データを完全に消去します。
 
+use crate::cancellation::DiskControl;
+use crate::checkpoint::Checkpoint;
+use crate::erase_method::EraseError;
+use crate::erase_plan::ErasePlan;
 use crate::logger::log_message;
-use std::process::Command;
-use std::thread;
-use std::time::Duration;
+#[cfg(target_os = "linux")]
+use crate::overwrite;
+use crate::progress::ProgressReporter;
+use crate::DiskInfo;
+#[cfg(target_os = "linux")]
+use std::path::Path;
 
-/// DoD 5220.22-M方式でHDDを消去する関数
-/// 
+/// 指定した上書き消去方式でHDDを消去する関数
+///
 /// # 引数
-/// 
-/// * `device` - 消去対象のデバイス名（例: /dev/sda）
-/// 
+///
+/// * `disk` - 消去対象のディスク情報
+/// * `plan` - 上書き消去方式と読み戻し検証の範囲を含む消去計画
+/// * `resume` - 中断した消去を再開する場合はそのチェックポイント
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
 /// # 戻り値
-/// 
-/// * `Result<(), String>` - 成功時は`Ok(())`、失敗時はエラーメッセージを含む`Err`
-pub fn erase_hdd_with_dod5220(device: &str) -> Result<(), String> {
-    let device_name = device.split_whitespace().next().unwrap_or(device);
+///
+/// * `Result<(), EraseError>` - 成功時は`Ok(())`、失敗時は書き込みを開始したかどうかとエラーメッセージを含む`Err`
+pub fn erase_hdd_with_scheme(
+    disk: &DiskInfo,
+    plan: &ErasePlan,
+    resume: Option<&Checkpoint>,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let device_name = disk.device_name.as_str();
+    let scheme = &plan.scheme;
+    let total = scheme.passes.len();
 
     log_message(
-        &format!("DoD5220.22-M消去開始: {}", device_name),
+        &format!("{}消去開始: {}", scheme.name, device_name),
         "進行中",
-        "ランダムデータとゼロが複数パスで書き込まれます。",
+        &format!(
+            "{}パスで書き込みます: {}",
+            total,
+            scheme
+                .passes
+                .iter()
+                .map(|p| p.pattern.description())
+                .collect::<Vec<_>>()
+                .join(" → ")
+        ),
     );
 
-    // Windowsでの開発環境では実際の消去処理をシミュレーションする
+    // Linux以外では実際のディスクを消去できない（動作確認にはシミュレーションモードを使用する）
     #[cfg(not(target_os = "linux"))]
     {
-        // 開発環境では消去をシミュレーション
-        for i in 0..3 {
-            log_message(
-                &format!("パス {}/3: {}にランダムデータを書き込み中", i + 1, device_name),
-                "進行中",
-                "シミュレーションモード",
-            );
-            
-            // 進行状況のシミュレーション
-            thread::sleep(Duration::from_secs(2));
-        }
-        
-        log_message(
-            &format!("DoD5220.22-M消去完了: {}", device_name),
-            "成功",
-            "シミュレーションモードでの消去が完了しました。",
-        );
-        
-        return Ok(());
+        let _ = (resume, control, reporter);
+        Err(EraseError::NotStarted(format!(
+            "この環境では{}を消去できません。実際の消去はLinuxでのみ対応しています。動作確認には環境変数{}=1でシミュレーションモードを有効にしてください。",
+            device_name,
+            crate::simulation::SIMULATION_ENV
+        )))
     }
 
     // 実際のLinux環境での消去処理
     #[cfg(target_os = "linux")]
     {
-        for i in 0..3 {
-            let action = format!("パス {}/3: {}にランダムデータを書き込み中", i + 1, device_name);
-            log_message(&action, "進行中", "");
-
-            // デバイスサイズの取得
-            let device_size_output = Command::new("blockdev")
-                .arg("--getsize64")
-                .arg(device_name)
-                .output()
-                .map_err(|e| format!("デバイスサイズの取得に失敗しました: {}", e))?;
+        let report = overwrite::overwrite(
+            Path::new(device_name),
+            scheme,
+            plan.verification,
+            resume,
+            control,
+            reporter,
+        )?;
 
-            let device_size_str = String::from_utf8_lossy(&device_size_output.stdout);
-            let device_size: u64 = device_size_str.trim().parse().unwrap_or(0);
-
-            if device_size == 0 {
-                return Err(format!("デバイスサイズの取得に失敗しました: {}", device_name));
-            }
-
-            let block_size = 4 * 1024 * 1024; // 4MBブロック
-            let count = device_size / block_size as u64;
-
-            // パスに応じて異なるパターンを書き込む
-            let pattern = match i {
-                0 => "random", // 1パス目: ランダムデータ
-                1 => "0x00",   // 2パス目: オールゼロ
-                _ => "random", // 3パス目: ランダムデータ
-            };
-
-            let status = if pattern == "random" {
-                Command::new("dd")
-                    .arg("if=/dev/urandom")
-                    .arg(format!("of={}", device_name))
-                    .arg(format!("bs={}", block_size))
-                    .arg(format!("count={}", count))
-                    .arg("status=progress")
-                    .status()
-            } else {
-                Command::new("dd")
-                    .arg("if=/dev/zero")
-                    .arg(format!("of={}", device_name))
-                    .arg(format!("bs={}", block_size))
-                    .arg(format!("count={}", count))
-                    .arg("status=progress")
-                    .status()
-            };
-
-            match status {
-                Ok(exit_status) if exit_status.success() => {
-                    log_message(&format!("パス {}/3 完了", i + 1), "成功", "");
-                }
-                Ok(_) => {
-                    return Err(format!("パス {}/3 の実行に失敗しました", i + 1));
-                }
-                Err(e) => {
-                    return Err(format!("ddコマンドの実行に失敗しました: {}", e));
-                }
-            }
+        if report.bad_sector_count > 0 {
+            log_message(
+                &format!("{}消去完了（不良セクタあり）: {}", scheme.name, device_name),
+                "物理破壊が必要",
+                &format!(
+                    "書き込めない不良セクタが{}個（{}範囲）あります。",
+                    report.bad_sector_count,
+                    report.bad_sectors.len()
+                ),
+            );
+            return Ok(());
         }
 
         log_message(
-            &format!("DoD5220.22-M消去完了: {}", device_name),
+            &format!("{}消去完了: {}", scheme.name, device_name),
             "成功",
-            "すべてのパスが正常に完了しました。",
+            &format!(
+                "すべてのパスが正常に完了しました。書き込みバイト数: {:?}（デバイスサイズ: {}バイト）、検証済みパス: {}",
+                report.bytes_per_pass,
+                report.size,
+                report.verifications.len()
+            ),
         );
 
         Ok(())
     }
-} 
\ No newline at end of file
+}
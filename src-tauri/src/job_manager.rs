@@ -0,0 +1,323 @@
+/// # ジョブ管理モジュール
+///
+/// このモジュールは、バックグラウンドで実行される消去ジョブの状態を管理します。
+/// 各ジョブはジョブIDで識別され、ウィンドウを再読み込みした後でも
+/// 実行中のジョブの状態や完了したジョブの結果を照会できます。
+use crate::erase_method::{EraseMethod, SanitizeReport};
+use crate::erase_policy::MethodAttempt;
+use crate::progress::ProgressEvent;
+use crate::verification::{LbaRange, VerificationReport};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// ジョブ全体の状態
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    /// 実行中
+    Running,
+    /// すべてのディスクの消去に成功した
+    Completed,
+    /// 一部またはすべてのディスクの消去に失敗した
+    Failed,
+    /// キャンセルされた
+    Cancelled,
+}
+
+/// ディスクごとの消去状態
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiskJobState {
+    /// 消去開始待ち
+    Pending,
+    /// 消去中
+    Running,
+    /// 消去に成功した
+    Succeeded,
+    /// 消去に失敗した
+    Failed,
+    /// 消去開始前にキャンセルされた（ディスクは変更されていない）
+    Cancelled,
+    /// 消去中にキャンセルされた（ディスクは部分的に消去されている）
+    PartiallyErased,
+    /// 書き込めない不良セクタが残っている（物理破壊が必要）
+    RequiresPhysicalDestruction,
+    /// 消去のコマンドは完了したが、読み戻し検証がキャンセルされた（消去結果は確認されていない）
+    CompletedUnverified,
+}
+
+/// ディスクごとの消去状況
+#[derive(Serialize, Clone, Debug)]
+pub struct DiskJobStatus {
+    /// 対象のデバイス名
+    pub device: String,
+    /// 消去状態
+    pub state: DiskJobState,
+    /// 失敗またはキャンセル時のメッセージ
+    pub error: Option<String>,
+    /// 実行した消去方式（消去開始前は`None`）
+    pub method: Option<EraseMethod>,
+    /// 消去方式の試行結果（スキップとフォールバックを含む）
+    pub attempts: Vec<MethodAttempt>,
+    /// Sanitizeを実行した場合はその完了状態
+    pub sanitize: Option<SanitizeReport>,
+    /// 最後に受信した進捗情報
+    pub progress: Option<ProgressEvent>,
+    /// 読み戻し検証の結果
+    pub verification: Vec<VerificationReport>,
+    /// 書き込みまたは読み込みのできなかった不良セクタ数
+    pub bad_sector_count: u64,
+    /// 不良セクタのLBA範囲
+    pub bad_sectors: Vec<LbaRange>,
+}
+
+/// ジョブの状態
+#[derive(Serialize, Clone, Debug)]
+pub struct JobStatus {
+    /// ジョブID
+    pub job_id: String,
+    /// ジョブ全体の状態
+    pub state: JobState,
+    /// ジョブ開始時刻（UNIX時間、秒）
+    pub started_at: u64,
+    /// ジョブ終了時刻（UNIX時間、秒）
+    pub finished_at: Option<u64>,
+    /// ディスクごとの消去状況
+    pub disks: Vec<DiskJobStatus>,
+}
+
+/// 完了したジョブの結果
+#[derive(Serialize, Clone, Debug)]
+pub struct JobResult {
+    /// ジョブID
+    pub job_id: String,
+    /// ジョブ全体の状態
+    pub state: JobState,
+    /// 結果の概要メッセージ
+    pub message: String,
+    /// ディスクごとの消去結果
+    pub disks: Vec<DiskJobStatus>,
+}
+
+/// ジョブの内部記録
+struct JobRecord {
+    status: JobStatus,
+    message: Option<String>,
+}
+
+/// 消去ジョブの状態を管理する構造体
+#[derive(Default)]
+pub struct JobManager {
+    jobs: Mutex<HashMap<String, JobRecord>>,
+}
+
+impl JobManager {
+    /// 新しいジョブを登録する
+    ///
+    /// # 引数
+    ///
+    /// * `job_id` - ジョブID
+    /// * `disks` - ジョブで消去するディスクのリスト
+    pub fn create(&self, job_id: &str, disks: &[String]) {
+        let status = JobStatus {
+            job_id: job_id.to_string(),
+            state: JobState::Running,
+            started_at: unix_time(),
+            finished_at: None,
+            disks: disks
+                .iter()
+                .map(|device| DiskJobStatus {
+                    device: device.clone(),
+                    state: DiskJobState::Pending,
+                    error: None,
+                    method: None,
+                    attempts: Vec::new(),
+                    sanitize: None,
+                    progress: None,
+                    verification: Vec::new(),
+                    bad_sector_count: 0,
+                    bad_sectors: Vec::new(),
+                })
+                .collect(),
+        };
+        self.jobs.lock().unwrap().insert(
+            job_id.to_string(),
+            JobRecord {
+                status,
+                message: None,
+            },
+        );
+    }
+
+    /// ディスクの消去状態を更新する
+    ///
+    /// # 引数
+    ///
+    /// * `job_id` - ジョブID
+    /// * `device` - 対象のデバイス名
+    /// * `state` - 新しい消去状態
+    /// * `error` - 失敗またはキャンセル時のメッセージ
+    pub fn update_disk(&self, job_id: &str, device: &str, state: DiskJobState, error: Option<String>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(disk) = jobs
+            .get_mut(job_id)
+            .and_then(|job| job.status.disks.iter_mut().find(|d| d.device == device))
+        {
+            disk.state = state;
+            disk.error = error;
+        }
+    }
+
+    /// ディスクに対して実行する消去方式を記録する
+    pub fn record_method(&self, job_id: &str, device: &str, method: EraseMethod) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(disk) = jobs
+            .get_mut(job_id)
+            .and_then(|job| job.status.disks.iter_mut().find(|d| d.device == device))
+        {
+            disk.method = Some(method);
+        }
+    }
+
+    /// 消去方式の試行結果を記録する
+    pub fn record_attempt(&self, job_id: &str, device: &str, attempt: MethodAttempt) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(disk) = jobs
+            .get_mut(job_id)
+            .and_then(|job| job.status.disks.iter_mut().find(|d| d.device == device))
+        {
+            disk.attempts.push(attempt);
+        }
+    }
+
+    /// Sanitizeの完了状態を記録する
+    pub fn record_sanitize(&self, job_id: &str, device: &str, report: &SanitizeReport) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(disk) = jobs
+            .get_mut(job_id)
+            .and_then(|job| job.status.disks.iter_mut().find(|d| d.device == device))
+        {
+            disk.sanitize = Some(report.clone());
+        }
+    }
+
+    /// ディスクの最新の進捗情報を記録する
+    pub fn record_progress(&self, job_id: &str, event: &ProgressEvent) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(disk) = jobs
+            .get_mut(job_id)
+            .and_then(|job| job.status.disks.iter_mut().find(|d| d.device == event.device))
+        {
+            disk.progress = Some(event.clone());
+        }
+    }
+
+    /// ディスクの読み戻し検証の結果を記録する
+    pub fn record_verification(&self, job_id: &str, device: &str, report: &VerificationReport) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(disk) = jobs
+            .get_mut(job_id)
+            .and_then(|job| job.status.disks.iter_mut().find(|d| d.device == device))
+        {
+            disk.verification.push(report.clone());
+        }
+    }
+
+    /// ディスクの不良セクタを記録する（これまでの記録は置き換える）
+    pub fn record_bad_sectors(&self, job_id: &str, device: &str, ranges: &[LbaRange], count: u64) {
+        let mut jobs = self.jobs.lock().unwrap();
+        if let Some(disk) = jobs
+            .get_mut(job_id)
+            .and_then(|job| job.status.disks.iter_mut().find(|d| d.device == device))
+        {
+            disk.bad_sector_count = count;
+            disk.bad_sectors = ranges.to_vec();
+        }
+    }
+
+    /// ジョブの終了を記録する
+    ///
+    /// # 引数
+    ///
+    /// * `job_id` - ジョブID
+    /// * `result` - 消去プロセス全体の結果
+    pub fn finish(&self, job_id: &str, result: &Result<(), String>) {
+        let mut jobs = self.jobs.lock().unwrap();
+        let Some(job) = jobs.get_mut(job_id) else {
+            return;
+        };
+
+        let cancelled = job.status.disks.iter().any(|d| {
+            matches!(
+                d.state,
+                DiskJobState::Cancelled | DiskJobState::PartiallyErased | DiskJobState::CompletedUnverified
+            )
+        });
+
+        let (state, message) = match result {
+            Ok(_) => (JobState::Completed, "消去が完了しました。".to_string()),
+            Err(e) if cancelled => (JobState::Cancelled, e.clone()),
+            Err(e) => (JobState::Failed, e.clone()),
+        };
+
+        job.status.state = state;
+        job.status.finished_at = Some(unix_time());
+        job.message = Some(message);
+    }
+
+    /// ジョブの状態を取得する
+    pub fn status(&self, job_id: &str) -> Option<JobStatus> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .get(job_id)
+            .map(|job| job.status.clone())
+    }
+
+    /// すべてのジョブの状態を開始時刻順に取得する
+    pub fn list(&self) -> Vec<JobStatus> {
+        let mut jobs: Vec<JobStatus> = self
+            .jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|job| job.status.clone())
+            .collect();
+        jobs.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.job_id.cmp(&b.job_id)));
+        jobs
+    }
+
+    /// 完了したジョブの結果を取得する
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<JobResult, String>` - ジョブが終了していない場合や存在しない場合は`Err`
+    pub fn result(&self, job_id: &str) -> Result<JobResult, String> {
+        let jobs = self.jobs.lock().unwrap();
+        let job = jobs
+            .get(job_id)
+            .ok_or_else(|| format!("ジョブが見つかりません: {}", job_id))?;
+
+        let message = job
+            .message
+            .clone()
+            .ok_or_else(|| format!("ジョブ{}はまだ実行中です。", job_id))?;
+
+        Ok(JobResult {
+            job_id: job.status.job_id.clone(),
+            state: job.status.state,
+            message,
+            disks: job.status.disks.clone(),
+        })
+    }
+}
+
+/// 現在のUNIX時間（秒）を取得する関数
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -2,29 +2,72 @@
 /// # ディスク消去ユーティリティのライブラリモジュール
 /// 
 /// このライブラリは、HDDとSSDのデータを安全に消去するための機能を提供します。
-/// HDDにはDoD 5220.22-M方式、SSDにはSecure Erase方式を使用します。
+/// HDDには選択した上書き消去方式（既定はDoD 5220.22-M）、SSDにはSecure Erase方式を使用します。
 /// 
 /// ## 主な機能
 /// 
 /// - ディスク情報の取得と表示
-/// - HDDの安全な消去（DoD 5220.22-M方式）
+/// - HDDの安全な消去（NIST 800-88、DoD 5220.22-M、Gutmannなどの上書き消去方式）
 /// - SSDの安全な消去（Secure Erase方式）
 /// - 消去プロセスのログ記録
 
+mod ata_identify;
+#[cfg(target_os = "linux")]
+mod ata_passthrough;
+#[cfg(target_os = "linux")]
+mod ata_sanitize;
+#[cfg(target_os = "linux")]
+mod ata_security;
+mod cancellation;
+mod checkpoint;
+mod config;
+mod csprng;
+#[cfg(target_os = "linux")]
+mod device_backend;
 mod disk_selection;
+mod erase_method;
+mod erase_plan;
+mod erase_policy;
 mod erase_process;
+mod erase_standards;
 mod hdd_erase;
+mod job_manager;
 mod logger;
+mod method_support;
+#[cfg(target_os = "linux")]
+mod nvme_format;
+mod nvme_identify;
+#[cfg(target_os = "linux")]
+mod nvme_passthrough;
+#[cfg(target_os = "linux")]
+mod nvme_sanitize;
+mod overwrite;
+mod progress;
+mod safety;
+mod simulation;
 mod ssd_erase;
+mod verification;
 
+use cancellation::CancelRegistry;
+use checkpoint::{Checkpoint, ResumableErase};
+use erase_plan::{DiskPlan, ErasePlan};
+use erase_standards::SchemeSummary;
+use job_manager::{JobManager, JobResult, JobStatus};
+use method_support::DiskCapabilityReport;
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
 
 /// アプリケーションの状態を管理する構造体
 #[derive(Default)]
 pub struct AppState {
-    /// 選択されたディスクのリスト
-    pub selected_disks: Vec<String>,
+    /// 実行中のジョブで消去されているディスクのリスト
+    pub selected_disks: Mutex<Vec<String>>,
+    /// 実行中の消去ジョブのキャンセル管理
+    pub cancel_registry: CancelRegistry,
+    /// 消去ジョブの状態と結果
+    pub jobs: JobManager,
 }
 
 /// ディスク情報を表す構造体
@@ -35,27 +78,387 @@ pub struct DiskInfo {
     /// ディスクのモデル名
     pub model: String,
     /// ディスクの種類（HDD/SSD）
-    pub device_type: String,
+    pub device_type: MediaKind,
     /// ディスクの接続方式（SATA/USB等）
-    pub transport: String,
+    pub transport: Transport,
+    /// デバイスの種類
+    pub kind: DeviceKind,
+    /// 容量（バイト）
+    pub size: u64,
+    /// 論理セクタサイズ（バイト）
+    pub logical_sector_size: u32,
+    /// 物理セクタサイズ（バイト）
+    pub physical_sector_size: u32,
+    /// 回転型のディスクか
+    pub rotational: bool,
+    /// リムーバブルメディアか
+    pub removable: bool,
+    /// 読み取り専用か
+    pub read_only: bool,
+    /// ベンダー名
+    pub vendor: Option<String>,
+    /// シリアル番号
+    pub serial: Option<String>,
+    /// WWN（World Wide Name）
+    pub wwn: Option<String>,
+    /// ファームウェアのリビジョン
+    pub firmware: Option<String>,
+    /// ディスクが対応している消去機能
+    pub capabilities: EraseCapabilities,
+    /// 同じNVMeコントローラ上の他のネームスペース（NVMe以外は空）
+    #[serde(default)]
+    pub sibling_namespaces: Vec<SiblingNamespace>,
+    /// システムで使用中のため保護されているか（ルート、/boot、スワップ、マウント中など）
+    pub protected: bool,
+    /// 保護されている理由
+    pub protection_reasons: Vec<String>,
+    /// シミュレーション用の仮想ディスクか
+    pub simulated: bool,
+}
+
+#[cfg(test)]
+impl DiskInfo {
+    /// テスト用に、消去機能を持たない使用中でないディスクの情報を作成する
+    pub(crate) fn for_test(device_name: &str, device_type: MediaKind, transport: Transport) -> Self {
+        DiskInfo {
+            device_name: device_name.to_string(),
+            model: "TEST DISK".to_string(),
+            device_type,
+            transport,
+            kind: DeviceKind::Disk,
+            size: 1 << 30,
+            logical_sector_size: 512,
+            physical_sector_size: 4096,
+            rotational: device_type == MediaKind::Hdd,
+            removable: false,
+            read_only: false,
+            vendor: None,
+            serial: None,
+            wwn: None,
+            firmware: None,
+            capabilities: EraseCapabilities::default(),
+            sibling_namespaces: Vec::new(),
+            protected: false,
+            protection_reasons: Vec::new(),
+            simulated: false,
+        }
+    }
+}
+
+/// 同じNVMeコントローラ上の他のネームスペース
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SiblingNamespace {
+    /// デバイス名（例: /dev/nvme0n2）
+    pub device_name: String,
+    /// システムで使用中のため保護されているか
+    pub protected: bool,
+}
+
+/// ディスクの記録媒体の種類
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaKind {
+    /// 回転型の磁気ディスク
+    Hdd,
+    /// フラッシュメモリ（SSD、SDカード、eMMC）
+    Ssd,
+    /// 判別できない
+    Unknown,
+}
+
+impl MediaKind {
+    /// ログや画面に表示する名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            MediaKind::Hdd => "HDD",
+            MediaKind::Ssd => "SSD",
+            MediaKind::Unknown => "不明",
+        }
+    }
+}
+
+/// ディスクの接続方式
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    Sata,
+    Sas,
+    Nvme,
+    Usb,
+    Mmc,
+    Virtio,
+    /// 判別できない
+    Unknown,
+}
+
+impl Transport {
+    /// ログや画面に表示する名前
+    pub fn label(&self) -> &'static str {
+        match self {
+            Transport::Sata => "SATA",
+            Transport::Sas => "SAS",
+            Transport::Nvme => "NVMe",
+            Transport::Usb => "USB",
+            Transport::Mmc => "MMC",
+            Transport::Virtio => "virtio",
+            Transport::Unknown => "不明",
+        }
+    }
+}
+
+/// ブロックデバイスの種類
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceKind {
+    /// HDD、SSDなどのディスク
+    Disk,
+    /// CD/DVD/BDドライブ
+    Cdrom,
+    /// ループデバイス
+    Loop,
+    /// SDカード、eMMC
+    Mmc,
+    /// zram（メモリ上の圧縮ブロックデバイス）
+    Zram,
+}
+
+impl DeviceKind {
+    /// 消去対象にできる種類か
+    pub fn is_erasable(&self) -> bool {
+        matches!(self, DeviceKind::Disk | DeviceKind::Mmc)
+    }
+}
+
+/// ディスクが対応している消去機能を表す構造体
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct EraseCapabilities {
+    /// ATA Security機能セットに対応しているか
+    pub ata_security_supported: bool,
+    /// ATA Enhanced Secure Eraseに対応しているか
+    pub ata_enhanced_erase_supported: bool,
+    /// ATA Securityがfrozen状態か（frozen中はSecure Eraseを実行できない）
+    pub ata_security_frozen: bool,
+    /// ATA Securityのユーザーパスワードが設定されているか
+    pub ata_security_enabled: bool,
+    /// ATA Securityでロックされているか
+    pub ata_security_locked: bool,
+    /// ATA Sanitizeのブロック消去（BLOCK ERASE EXT）に対応しているか
+    pub ata_sanitize_block_supported: bool,
+    /// ATA Sanitizeの暗号化消去（CRYPTO SCRAMBLE EXT）に対応しているか
+    pub ata_sanitize_crypto_supported: bool,
+    /// ATA Sanitizeの上書き（OVERWRITE EXT）に対応しているか
+    pub ata_sanitize_overwrite_supported: bool,
+    /// NVMe Format NVMコマンドに対応しているか
+    pub nvme_format_supported: bool,
+    /// NVMe Formatの暗号化消去に対応しているか
+    pub nvme_crypto_erase_supported: bool,
+    /// NVMe Formatがコントローラ上の他のネームスペースも消去してしまうか
+    pub nvme_format_affects_other_namespaces: bool,
+    /// NVMe Sanitizeの暗号化消去に対応しているか
+    pub nvme_sanitize_crypto_supported: bool,
+    /// NVMe Sanitizeのブロック消去に対応しているか
+    pub nvme_sanitize_block_supported: bool,
+    /// NVMe Sanitizeの上書きに対応しているか
+    pub nvme_sanitize_overwrite_supported: bool,
+    /// NVMe Sanitizeがコントローラ上の他のネームスペースも消去してしまうか
+    pub nvme_sanitize_affects_other_namespaces: bool,
+    /// Security Send/Receive（TCG Opalなどのセキュリティプロトコル）に対応しているか
+    pub security_protocol_supported: bool,
+    /// ブロックデバイスがDiscard（TRIM）に対応しているか
+    pub discard_supported: bool,
 }
 
 /// 利用可能なディスクの一覧を取得するコマンド
+///
+/// シミュレーションが有効な場合は仮想ディスクのみを返します。
+/// ディスクの検出に失敗した場合はその理由を返します。
 #[tauri::command]
-fn get_available_disks() -> Vec<DiskInfo> {
+fn get_available_disks() -> Result<Vec<DiskInfo>, String> {
     disk_selection::get_available_disks()
 }
 
+/// ディスクが対応している消去方式を取得するコマンド
+///
+/// 消去方式ごとに実行できるかどうかと、実行できない理由（frozen状態、USB変換アダプタなど）、
+/// 消去方式を指定しない場合に消去ポリシーに従って試す消去方式を返します。
+/// 指定したディスクが見つからない場合はその理由を返します。
+#[tauri::command]
+fn get_disk_capabilities(device: String) -> Result<DiskCapabilityReport, String> {
+    let disks = disk_selection::get_available_disks()?;
+    let disk = disks
+        .iter()
+        .find(|disk| disk.device_name == device)
+        .ok_or_else(|| format!("{}が見つかりません。", device))?;
+    Ok(method_support::report(disk, &config::load()?.policy))
+}
+
+/// 選択可能な上書き消去方式の一覧を取得するコマンド
+///
+/// 組み込みの方式に加えて、設定ファイルで定義された方式を含みます。
+/// 設定ファイルの定義に誤りがある場合はその理由を返します。
+#[tauri::command]
+fn get_overwrite_schemes() -> Result<Vec<SchemeSummary>, String> {
+    Ok(erase_standards::all_schemes()?
+        .iter()
+        .map(|scheme| scheme.summary())
+        .collect())
+}
+
 /// 選択されたディスクを消去するコマンド
+///
+/// `plans`でディスクごとに消去方式、読み戻し検証の範囲、上書き消去方式、消去後の処理を指定します。
+/// 消去方式を省略した場合は、HDDは上書き消去（既定はDoD 5220.22-M）、SSDは対応機能に基づいて自動選択されます。
+/// すべての計画をディスクの対応機能と照合し、1台でも実行できない計画があれば何も開始せずに理由を返します。
+/// 消去はバックグラウンドで実行され、このコマンドはジョブIDを即座に返します。
+/// 消去中の進捗は`erase-progress`イベントとしてディスクごとに通知され、
+/// `get_job_status`や`get_job_result`でジョブの状態を照会できます。
 #[tauri::command]
-fn erase_disks(app_handle: AppHandle, disks: Vec<String>) -> Result<String, String> {
-    erase_process::start(&disks)?;
-    Ok("消去が完了しました。".to_string())
+fn erase_disks(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    plans: Vec<DiskPlan>,
+) -> Result<String, String> {
+    let resolved = erase_plan::resolve(&plans)?;
+    let disks = resolved.iter().map(|(device, _)| device.clone()).collect();
+    spawn_erase_job(app_handle, &state, disks, resolved.into_iter().collect(), HashMap::new())
+}
+
+/// 中断した上書き消去の一覧を取得するコマンド
+///
+/// 状態ファイルに記録されたチェックポイントごとに、同じ物理ディスクが接続されていて
+/// 再開できるかどうかを返します。
+#[tauri::command]
+fn get_resumable_erasures() -> Result<Vec<ResumableErase>, String> {
+    checkpoint::resumable_erasures()
+}
+
+/// 中断した上書き消去をチェックポイントから再開するコマンド
+///
+/// シリアル番号またはWWNで同じ物理ディスクであることを確認してから、
+/// 中断したパスとオフセットから消去を続行します。ジョブIDを即座に返します。
+#[tauri::command]
+fn resume_erase(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    device: String,
+) -> Result<String, String> {
+    let saved = checkpoint::find(&device)?
+        .ok_or_else(|| format!("{}のチェックポイントが見つかりません。", device))?;
+    checkpoint::validate_resume(&saved)
+        .map_err(|e| format!("{}の消去を再開できません: {}", device, e))?;
+    let scheme = erase_standards::find_scheme(&saved.scheme_id)?;
+    saved.matches_scheme(&scheme)?;
+
+    let plans = HashMap::from([(device.clone(), ErasePlan::resume(scheme, saved.verification))]);
+    let resume = HashMap::from([(device.clone(), saved)]);
+    spawn_erase_job(app_handle, &state, vec![device], plans, resume)
+}
+
+/// 中断した上書き消去のチェックポイントを破棄するコマンド
+#[tauri::command]
+fn discard_checkpoint(state: State<'_, AppState>, device: String) -> Result<(), String> {
+    if state.selected_disks.lock().unwrap().contains(&device) {
+        return Err(format!("{}は消去中のため、チェックポイントを破棄できません。", device));
+    }
+    checkpoint::remove(&device)
+}
+
+/// 消去ジョブを登録し、バックグラウンドで実行する関数
+///
+/// # 戻り値
+///
+/// * `Result<String, String>` - 成功時はジョブID、既に消去中のディスクが含まれる場合は`Err`
+fn spawn_erase_job(
+    app_handle: AppHandle,
+    state: &AppState,
+    disks: Vec<String>,
+    plans: HashMap<String, ErasePlan>,
+    resume: HashMap<String, Checkpoint>,
+) -> Result<String, String> {
+    // 既に別のジョブで消去中のディスクは受け付けない
+    {
+        let mut selected = state.selected_disks.lock().unwrap();
+        let busy: Vec<&String> = disks.iter().filter(|d| selected.contains(d)).collect();
+        if !busy.is_empty() {
+            return Err(format!("既に消去中のディスクが含まれています: {:?}", busy));
+        }
+        selected.extend(disks.iter().cloned());
+    }
+
+    let job = state.cancel_registry.register(&disks);
+    state.jobs.create(job.id(), &disks);
+    let job_id = job.id().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app_handle.state::<AppState>();
+        let result = erase_process::start(
+            &app_handle,
+            &state.jobs,
+            &job,
+            &disks,
+            &plans,
+            &resume,
+        );
+        state.jobs.finish(job.id(), &result);
+        state.cancel_registry.unregister(job.id());
+        state
+            .selected_disks
+            .lock()
+            .unwrap()
+            .retain(|d| !disks.contains(d));
+    });
+
+    Ok(job_id)
+}
+
+/// 消去ジョブの状態を取得するコマンド
+#[tauri::command]
+fn get_job_status(state: State<'_, AppState>, job_id: String) -> Result<JobStatus, String> {
+    state
+        .jobs
+        .status(&job_id)
+        .ok_or_else(|| format!("ジョブが見つかりません: {}", job_id))
+}
+
+/// すべての消去ジョブの状態を取得するコマンド
+#[tauri::command]
+fn list_jobs(state: State<'_, AppState>) -> Vec<JobStatus> {
+    state.jobs.list()
+}
+
+/// 終了した消去ジョブの結果を取得するコマンド
+#[tauri::command]
+fn get_job_result(state: State<'_, AppState>, job_id: String) -> Result<JobResult, String> {
+    state.jobs.result(&job_id)
 }
 
 /// 消去プロセスをキャンセルするコマンド
+///
+/// `device`を指定した場合はそのディスクのみ、`job_id`を指定した場合はそのジョブ全体、
+/// どちらも省略した場合は実行中のすべての消去を中断します。
+/// ATA Secure Eraseなど中断できない処理を実行中のディスクについては、理由を含むエラーを返します。
 #[tauri::command]
-fn cancel_erase() -> Result<String, String> {
+fn cancel_erase(
+    state: State<'_, AppState>,
+    job_id: Option<String>,
+    device: Option<String>,
+) -> Result<String, String> {
+    if let Some(device) = device {
+        state.cancel_registry.cancel_disk(&device)?;
+        return Ok(format!("{}の消去をキャンセルしました。", device));
+    }
+
+    let refused = match job_id {
+        Some(job_id) => state.cancel_registry.cancel_job(&job_id)?,
+        None => state.cancel_registry.cancel_all(),
+    };
+
+    if !refused.is_empty() {
+        return Err(format!(
+            "一部のディスクは中断できません。その他のディスクの消去はキャンセルされました。\n{}",
+            refused.join("\n")
+        ));
+    }
     Ok("消去がキャンセルされました。".to_string())
 }
 
@@ -65,8 +468,16 @@ pub fn run() {
         .manage(AppState::default())
         .invoke_handler(tauri::generate_handler![
             get_available_disks,
+            get_disk_capabilities,
+            get_overwrite_schemes,
             erase_disks,
-            cancel_erase
+            get_job_status,
+            list_jobs,
+            get_job_result,
+            cancel_erase,
+            get_resumable_erasures,
+            resume_erase,
+            discard_checkpoint
         ])
         .plugin(tauri_plugin_opener::init())
         .run(tauri::generate_context!())
@@ -5,8 +5,21 @@
 
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// ログファイルのパス
+#[cfg(not(test))]
+fn log_path() -> PathBuf {
+    PathBuf::from("erasure_log.txt")
+}
+
+/// ログファイルのパス（テストでは作業ディレクトリのログを変更しない）
+#[cfg(test)]
+pub(crate) fn log_path() -> PathBuf {
+    std::env::temp_dir().join(format!("disk_eraser_test_log_{}.txt", std::process::id()))
+}
+
 /// ログファイルへメッセージを記録する関数
 /// 
 /// # 引数
@@ -29,7 +42,7 @@ pub fn log_message(action: &str, result: &str, details: &str) {
     let mut file = match OpenOptions::new()
         .create(true)
         .append(true)
-        .open("erasure_log.txt") {
+        .open(log_path()) {
             Ok(file) => file,
             Err(e) => {
                 eprintln!("ログファイルを開けませんでした: {}", e);
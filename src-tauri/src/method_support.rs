@@ -0,0 +1,262 @@
+/// # 消去方式の対応状況モジュール
+///
+/// このモジュールは、ディスクの接続方式と検出した消去機能から、
+/// 各消去方式を実行できるかどうかとその理由を判定します。
+/// 判定結果は`get_disk_capabilities`コマンドでフロントエンドに送信されます。
+use crate::erase_method::{EraseMethod, SanitizeAction};
+use crate::erase_policy::ErasePolicy;
+use crate::safety;
+use crate::{DiskInfo, MediaKind, Transport};
+use serde::Serialize;
+
+/// ディスクが対応している消去方式の一覧
+#[derive(Serialize, Clone)]
+pub struct DiskCapabilityReport {
+    /// デバイス名
+    pub device_name: String,
+    /// モデル名
+    pub model: String,
+    /// ディスクの種類
+    pub device_type: MediaKind,
+    /// 接続方式
+    pub transport: Transport,
+    /// 消去方式ごとの対応状況
+    pub methods: Vec<MethodSupport>,
+    /// 消去方式を指定しない場合に消去ポリシーに従って試す消去方式（試す順）
+    pub policy_chain: Vec<EraseMethod>,
+    /// 消去ポリシーに実行できる消去方式がない場合はその理由
+    pub policy_error: Option<String>,
+}
+
+/// 消去方式の対応状況
+#[derive(Serialize, Clone)]
+pub struct MethodSupport {
+    /// 消去方式
+    pub method: EraseMethod,
+    /// 消去方式の名前
+    pub name: String,
+    /// 実行できるか
+    pub available: bool,
+    /// 実行できない理由（実行できる場合は空）
+    pub reasons: Vec<String>,
+    /// 実行できるが注意が必要な点（他のネームスペースへの影響など）
+    pub warnings: Vec<String>,
+}
+
+/// ディスクが対応している消去方式の一覧を作成する関数
+///
+/// # 引数
+///
+/// * `disk` - 対象のディスク情報
+/// * `policy` - 消去方式を指定しない場合の消去ポリシー
+///
+/// # 戻り値
+///
+/// * `DiskCapabilityReport` - 消去方式ごとの対応状況と消去ポリシーに従って試す消去方式
+pub fn report(disk: &DiskInfo, policy: &ErasePolicy) -> DiskCapabilityReport {
+    let methods = EraseMethod::all()
+        .into_iter()
+        .map(|method| {
+            let reasons = unavailable_reasons(disk, method);
+            MethodSupport {
+                method,
+                name: method.name().to_string(),
+                available: reasons.is_empty(),
+                reasons,
+                warnings: warnings(disk, method),
+            }
+        })
+        .collect();
+
+    let (policy_chain, policy_error) = match policy.chain(disk) {
+        Ok(chain) => (chain.methods, None),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+
+    DiskCapabilityReport {
+        device_name: disk.device_name.clone(),
+        model: disk.model.clone(),
+        device_type: disk.device_type,
+        transport: disk.transport,
+        methods,
+        policy_chain,
+        policy_error,
+    }
+}
+
+/// 消去方式を実行できない理由を判定する関数
+///
+/// # 戻り値
+///
+/// * `Vec<String>` - 実行できない理由（実行できる場合は空）
+pub fn unavailable_reasons(disk: &DiskInfo, method: EraseMethod) -> Vec<String> {
+    let caps = &disk.capabilities;
+    let mut reasons = Vec::new();
+
+    if let Err(e) = safety::ensure_erasable(disk) {
+        reasons.push(e);
+    }
+
+    match method {
+        EraseMethod::Overwrite => {}
+        EraseMethod::AtaSecureErase { enhanced } => {
+            reasons.extend(ata_passthrough_reason(disk));
+            if !caps.ata_security_supported {
+                reasons.push("ドライブがATA Security機能セットに対応していません。".to_string());
+            } else {
+                if enhanced && !caps.ata_enhanced_erase_supported {
+                    reasons.push("ドライブがEnhanced Secure Eraseに対応していません。".to_string());
+                }
+                if caps.ata_security_frozen {
+                    reasons.push(
+                        "ATA Securityがfrozen状態です。スリープからの復帰や電源ケーブルの抜き差しで解除できる場合があります。"
+                            .to_string(),
+                    );
+                }
+                if caps.ata_security_locked {
+                    reasons.push("ドライブがATA Securityでロックされています（パスワードが必要です）。".to_string());
+                } else if caps.ata_security_enabled {
+                    reasons.push("ドライブにATA Securityのユーザーパスワードが設定されています。".to_string());
+                }
+            }
+        }
+        EraseMethod::AtaSanitize { action } => {
+            reasons.extend(ata_passthrough_reason(disk));
+            let supported = match action {
+                SanitizeAction::BlockErase => caps.ata_sanitize_block_supported,
+                SanitizeAction::CryptoErase => caps.ata_sanitize_crypto_supported,
+                SanitizeAction::Overwrite => caps.ata_sanitize_overwrite_supported,
+            };
+            if !supported {
+                reasons.push(format!("ドライブが{}に対応していません。", method.name()));
+            }
+        }
+        EraseMethod::NvmeFormat { ses } => {
+            reasons.extend(nvme_reason(disk));
+            if !caps.nvme_format_supported {
+                reasons.push("NVMeコントローラがFormat NVMコマンドに対応していません。".to_string());
+            } else if ses == 2 && !caps.nvme_crypto_erase_supported {
+                reasons.push("NVMeコントローラがFormatの暗号化消去に対応していません。".to_string());
+            }
+            if caps.nvme_format_affects_other_namespaces {
+                reasons.extend(protected_sibling_reason(disk, method));
+            }
+        }
+        EraseMethod::NvmeSanitize { action } => {
+            reasons.extend(nvme_reason(disk));
+            let supported = match action {
+                SanitizeAction::BlockErase => caps.nvme_sanitize_block_supported,
+                SanitizeAction::CryptoErase => caps.nvme_sanitize_crypto_supported,
+                SanitizeAction::Overwrite => caps.nvme_sanitize_overwrite_supported,
+            };
+            if !supported {
+                reasons.push(format!("NVMeコントローラが{}に対応していません。", method.name()));
+            }
+            if caps.nvme_sanitize_affects_other_namespaces {
+                reasons.extend(protected_sibling_reason(disk, method));
+            }
+        }
+        EraseMethod::Discard => {
+            if !caps.discard_supported {
+                reasons.push("ブロックデバイスがDiscard（TRIM）に対応していません。".to_string());
+            }
+        }
+        EraseMethod::OpalRevert => {
+            if !caps.security_protocol_supported {
+                reasons.push("ドライブがTCG Opalに対応していることを確認できません。".to_string());
+            }
+        }
+    }
+
+    if !is_implemented(method) {
+        reasons.push(format!("{}の実行にはまだ対応していません。", method.name()));
+    }
+
+    reasons
+}
+
+/// 消去方式を実行できるが注意が必要な点を判定する関数
+///
+/// # 戻り値
+///
+/// * `Vec<String>` - 注意が必要な点（ない場合は空）
+pub fn warnings(disk: &DiskInfo, method: EraseMethod) -> Vec<String> {
+    let caps = &disk.capabilities;
+    let affects_siblings = match method {
+        EraseMethod::NvmeFormat { .. } => caps.nvme_format_affects_other_namespaces,
+        EraseMethod::NvmeSanitize { .. } => caps.nvme_sanitize_affects_other_namespaces,
+        _ => false,
+    };
+    if !affects_siblings {
+        return Vec::new();
+    }
+
+    let siblings: Vec<&str> = disk
+        .sibling_namespaces
+        .iter()
+        .map(|sibling| sibling.device_name.as_str())
+        .collect();
+    vec![if siblings.is_empty() {
+        format!("{}はコントローラ上の他のネームスペースも消去します。", method.name())
+    } else {
+        format!(
+            "{}は同じコントローラ上の他のネームスペース（{}）も消去します。",
+            method.name(),
+            siblings.join("、")
+        )
+    }]
+}
+
+/// 影響する他のネームスペースがシステムで使用中の場合にその理由を返す関数
+fn protected_sibling_reason(disk: &DiskInfo, method: EraseMethod) -> Option<String> {
+    let protected: Vec<&str> = disk
+        .sibling_namespaces
+        .iter()
+        .filter(|sibling| sibling.protected)
+        .map(|sibling| sibling.device_name.as_str())
+        .collect();
+    (!protected.is_empty()).then(|| {
+        format!(
+            "{}は同じコントローラ上のシステムで使用中のネームスペース（{}）も消去してしまいます。",
+            method.name(),
+            protected.join("、")
+        )
+    })
+}
+
+/// ATAコマンドをドライブに送信できない理由を判定する関数
+fn ata_passthrough_reason(disk: &DiskInfo) -> Option<String> {
+    match disk.transport {
+        Transport::Sata => None,
+        Transport::Usb => Some(
+            "USB変換アダプタ経由のため、ATAコマンドがドライブに届かない場合があります。".to_string(),
+        ),
+        Transport::Unknown => Some("接続方式が不明なため、ATAコマンドを送信できません。".to_string()),
+        Transport::Sas | Transport::Nvme | Transport::Mmc | Transport::Virtio => Some(format!(
+            "接続方式{}ではATAコマンドを使用できません。",
+            disk.transport.label()
+        )),
+    }
+}
+
+/// NVMeコマンドをドライブに送信できない理由を判定する関数
+fn nvme_reason(disk: &DiskInfo) -> Option<String> {
+    (disk.transport != Transport::Nvme).then(|| {
+        format!(
+            "接続方式{}ではNVMeコマンドを使用できません。",
+            disk.transport.label()
+        )
+    })
+}
+
+/// このアプリケーションが実行できる消去方式か
+fn is_implemented(method: EraseMethod) -> bool {
+    matches!(
+        method,
+        EraseMethod::Overwrite
+            | EraseMethod::AtaSecureErase { .. }
+            | EraseMethod::AtaSanitize { .. }
+            | EraseMethod::NvmeFormat { .. }
+            | EraseMethod::NvmeSanitize { .. }
+    )
+}
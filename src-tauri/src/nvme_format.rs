@@ -0,0 +1,327 @@
+/// # NVMe Formatモジュール
+///
+/// このモジュールは、NVMe Format NVMによるユーザーデータ消去（SES=1）と暗号化消去（SES=2）を実行します。
+/// コマンドはNVMeのioctl、使用できない場合は`nvme`（nvme-cli）で発行します。
+///
+/// 1. 事前確認: コントローラのFNAで暗号化消去への対応とFormatが適用されるネームスペースを確認する
+/// 2. Format: 現在のLBAフォーマットと保護情報の設定を明示的に指定し、ネームスペースの形式を変えずに消去する
+/// 3. 事後確認: LBAフォーマットが変わっていないこと、割り当て済みのブロックが残っていないこと、
+///    割り当て解除されたブロックの読み出し値（DLFEAT）が読み戻せることを確認する
+use crate::cancellation::DiskControl;
+use crate::device_backend::{FormatNvm, NvmeBackend};
+use crate::disk_selection;
+use crate::erase_method::{EraseError, EraseMethod};
+use crate::logger::log_message;
+use crate::nvme_identify::NvmeNamespace;
+use crate::overwrite::{FillPattern, OverwriteTarget};
+use crate::progress::ProgressReporter;
+use crate::verification::VerificationLevel;
+use std::path::Path;
+
+/// NVMe Formatを実行する関数
+///
+/// # 引数
+///
+/// * `device` - 消去対象のネームスペースのデバイス名（例: /dev/nvme0n1）
+/// * `backend` - NVMe管理コマンドを発行するバックエンド
+/// * `ses` - Secure Erase Settings（1=ユーザーデータ消去、2=暗号化消去）
+/// * `verification` - 消去後の読み戻し検証の範囲
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
+/// # 戻り値
+///
+/// * `Result<(), EraseError>` - 成功時は`Ok(())`、失敗時はFormatを発行したかどうかとエラーメッセージを含む`Err`
+pub fn format(
+    device: &str,
+    mut backend: Box<dyn NvmeBackend>,
+    ses: u8,
+    verification: VerificationLevel,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let method = EraseMethod::NvmeFormat { ses };
+    reporter.set_method(method.name(), 1, 0);
+    reporter.start_pass(1);
+
+    let nsid = disk_selection::nvme_namespace_id(device).ok_or_else(|| {
+        EraseError::NotStarted(format!("{}のネームスペースIDを取得できませんでした。", device))
+    })?;
+    precheck(device, backend.as_mut(), nsid, ses, reporter)
+        .inspect_err(|e| log_message("NVMe Format", "中止", e))
+        .map_err(EraseError::NotStarted)?;
+    let before = backend.identify_namespace(nsid).map_err(EraseError::NotStarted)?;
+    let lba_format = before.format().copied().ok_or_else(|| {
+        EraseError::NotStarted(format!("{}の現在のLBAフォーマットを取得できませんでした。", device))
+    })?;
+
+    {
+        // Formatはコントローラ内部で実行されるため中断できない
+        let _guard = control
+            .enter_non_interruptible(method.name())
+            .map_err(EraseError::NotStarted)?;
+        reporter.report_message(&format!(
+            "{}を実行中です（LBAフォーマット{}: {}バイト）。",
+            method.name(),
+            before.current_format,
+            lba_format.data_size
+        ));
+        // 現在のLBAフォーマットと保護情報の設定を指定し、ネームスペースの形式を維持する
+        backend
+            .format(&FormatNvm {
+                nsid,
+                lba_format: before.current_format as u8,
+                ses,
+                metadata_extended: before.metadata_extended,
+                protection_type: before.protection_type,
+                protection_first: before.protection_first,
+            })
+            .map_err(|e| {
+                let message = format!("{}の{}に失敗しました: {}", device, method.name(), e);
+                log_message("NVMe Format", "失敗", &message);
+                // Formatは完了するまで結果が返らないため、失敗した場合も途中まで実行された可能性がある
+                EraseError::Started(message)
+            })?;
+    }
+
+    verify(device, backend.as_mut(), method, &before, verification, control, reporter).map_err(|e| {
+        // Formatは完了しているため、読み戻し検証のキャンセルは部分的な消去ではない
+        if control.is_cancelled() {
+            let message = format!(
+                "{}の{}は完了しましたが、読み戻し検証がキャンセルされたため消去結果を確認していません: {}",
+                device,
+                method.name(),
+                e
+            );
+            log_message("NVMe Format", "検証未完了", &message);
+            return EraseError::Unverified(message);
+        }
+        let message = format!("{}の{}の事後確認に失敗しました: {}", device, method.name(), e);
+        log_message("NVMe Format", "失敗", &message);
+        EraseError::Started(message)
+    })?;
+
+    log_message(
+        "NVMe Format",
+        "成功",
+        &format!("{}の{}が正常に完了しました。", device, method.name()),
+    );
+    Ok(())
+}
+
+/// コントローラの対応状況とFormatが適用されるネームスペースを確認する関数
+fn precheck(
+    device: &str,
+    backend: &mut dyn NvmeBackend,
+    nsid: u32,
+    ses: u8,
+    reporter: &mut ProgressReporter,
+) -> Result<(), String> {
+    let controller = backend.identify_controller()?;
+    if !controller.oacs.format_nvm {
+        return Err(format!("{}のコントローラはFormat NVMコマンドに対応していません。", device));
+    }
+    if ses == 2 && !controller.fna.crypto_erase {
+        return Err(format!("{}のコントローラはFormatの暗号化消去に対応していません。", device));
+    }
+
+    // FNAによってはFormatがコントローラ上のすべてのネームスペースに適用される
+    if controller.fna.all_namespaces || controller.fna.secure_erase_all_namespaces {
+        let siblings: Vec<String> = disk_selection::nvme_controller_path(device)
+            .and_then(|path| {
+                let namespaces = backend.namespace_list().ok()?;
+                Some(
+                    namespaces
+                        .into_iter()
+                        .filter(|id| *id != nsid)
+                        .map(|id| format!("{}n{}", path, id))
+                        .collect(),
+                )
+            })
+            .unwrap_or_default();
+        if !siblings.is_empty() {
+            let warning = format!(
+                "Formatは同じコントローラ上の他のネームスペース（{}）にも適用されます。",
+                siblings.join("、")
+            );
+            log_message("NVMe Format", "警告", &format!("{}: {}", device, warning));
+            reporter.report_message(&warning);
+        }
+    }
+    Ok(())
+}
+
+/// Format後にネームスペースの形式が維持され、割り当てが解除されたことを確認する関数
+fn verify(
+    device: &str,
+    backend: &mut dyn NvmeBackend,
+    method: EraseMethod,
+    before: &NvmeNamespace,
+    verification: VerificationLevel,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), String> {
+    let after = backend.identify_namespace(before.nsid)?;
+    if after.current_format != before.current_format
+        || after.format().map(|f| f.data_size) != before.format().map(|f| f.data_size)
+    {
+        return Err(format!(
+            "LBAフォーマットが{}から{}に変わりました。",
+            before.current_format, after.current_format
+        ));
+    }
+    if after.thin_provisioning && after.utilization_blocks != 0 {
+        return Err(format!(
+            "{}個の論理ブロックが割り当てられたまま残っています。",
+            after.utilization_blocks
+        ));
+    }
+
+    let Some(value) = after.deallocated_read_value else {
+        log_message(
+            "NVMe Format",
+            "情報",
+            &format!(
+                "{}は割り当てを解除したブロックの読み出し値を規定していないため、読み戻し検証を省略します。",
+                device
+            ),
+        );
+        return Ok(());
+    };
+
+    // 割り当てを解除したブロックはDLFEATで規定された値として読み出される
+    let mut target = OverwriteTarget::open(Path::new(device))?;
+    reporter.set_method(method.name(), 1, target.size());
+    let report = target.verify_pass(1, &FillPattern::Byte(value), None, verification, control, reporter)?;
+    reporter.record_verification(&report);
+    if !report.is_clean() {
+        return Err(format!("割り当てが解除されていない領域があります: {}", report.summary()));
+    }
+    log_message("NVMe Format", "成功", &format!("{}: {}", device, report.summary()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancellation::CancelRegistry;
+    use crate::erase_method::{NvmeSanitizeOptions, SanitizeAction};
+    use crate::nvme_identify::{LbaFormat, NvmeController};
+    use crate::nvme_sanitize::SanitizeLog;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    /// 読み戻し検証で読み出すネームスペースのサイズ
+    const IMAGE_SIZE: usize = 64 * 1024;
+
+    /// Formatを受け付け、Format後のIdentify Namespaceで指定した処理を行う偽のコントローラ
+    struct FakeController {
+        namespace: NvmeNamespace,
+        /// Format後のIdentify Namespaceでキャンセルを要求する対象
+        cancel: Option<Arc<DiskControl>>,
+        formatted: bool,
+    }
+
+    impl NvmeBackend for FakeController {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn identify_controller(&mut self) -> Result<NvmeController, String> {
+            let mut controller = NvmeController::default();
+            controller.oacs.format_nvm = true;
+            controller.fna.crypto_erase = true;
+            Ok(controller)
+        }
+
+        fn identify_namespace(&mut self, _nsid: u32) -> Result<NvmeNamespace, String> {
+            if self.formatted {
+                if let Some(control) = &self.cancel {
+                    control.request_cancel()?;
+                }
+            }
+            Ok(self.namespace.clone())
+        }
+
+        fn namespace_list(&mut self) -> Result<Vec<u32>, String> {
+            Ok(vec![self.namespace.nsid])
+        }
+
+        fn format(&mut self, _format: &FormatNvm) -> Result<(), String> {
+            self.formatted = true;
+            Ok(())
+        }
+
+        fn sanitize(&mut self, _action: SanitizeAction, _options: &NvmeSanitizeOptions) -> Result<(), String> {
+            Err("未対応".to_string())
+        }
+
+        fn sanitize_log(&mut self) -> Result<SanitizeLog, String> {
+            Err("未対応".to_string())
+        }
+    }
+
+    /// ゼロで埋めたネームスペースのイメージを`nvme0n1`という名前で作成する
+    fn namespace_image() -> (TempDir, String) {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nvme0n1");
+        std::fs::write(&path, vec![0u8; IMAGE_SIZE]).unwrap();
+        let device = path.display().to_string();
+        (dir, device)
+    }
+
+    fn namespace() -> NvmeNamespace {
+        NvmeNamespace {
+            nsid: 1,
+            size_blocks: (IMAGE_SIZE / 512) as u64,
+            capacity_blocks: (IMAGE_SIZE / 512) as u64,
+            lba_formats: vec![LbaFormat {
+                data_size: 512,
+                metadata_size: 0,
+                relative_performance: 0,
+            }],
+            deallocated_read_value: Some(0x00),
+            ..NvmeNamespace::default()
+        }
+    }
+
+    fn run(device: &str, namespace: NvmeNamespace, cancel_during_verify: bool) -> Result<(), EraseError> {
+        let control = CancelRegistry::default()
+            .register(&[device.to_string()])
+            .disk(device)
+            .unwrap();
+        let backend = FakeController {
+            namespace,
+            cancel: cancel_during_verify.then(|| control.clone()),
+            formatted: false,
+        };
+        let mut reporter = ProgressReporter::new(None, "test", device);
+        format(device, Box::new(backend), 2, VerificationLevel::Full, &control, &mut reporter)
+    }
+
+    #[test]
+    fn verifies_deallocated_blocks() {
+        let (_dir, device) = namespace_image();
+        assert_eq!(run(&device, namespace(), false), Ok(()));
+    }
+
+    #[test]
+    fn cancelled_read_back_is_reported_as_unverified() {
+        let (_dir, device) = namespace_image();
+        match run(&device, namespace(), true) {
+            Err(EraseError::Unverified(message)) => assert!(message.contains("完了しました"), "{}", message),
+            other => panic!("予期しない結果: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn allocated_blocks_after_format_are_a_failure() {
+        let (_dir, device) = namespace_image();
+        let namespace = NvmeNamespace {
+            thin_provisioning: true,
+            utilization_blocks: 8,
+            ..namespace()
+        };
+        assert!(matches!(run(&device, namespace, false), Err(EraseError::Started(_))));
+    }
+}
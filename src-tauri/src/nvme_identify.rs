@@ -0,0 +1,516 @@
+/// # NVMe IDENTIFYモジュール
+///
+/// このモジュールは、`nvme id-ctrl`、`nvme id-ns`、`nvme list-ns`のJSON出力（`-o json`）、
+/// またはNVMeのioctlで取得したIdentifyのデータ構造（4096バイト）を構造化された情報に変換します。
+/// コントローラのSanitize対応状況（SANICAP）、Formatの属性（FNA）、オプションの管理コマンド（OACS）、
+/// ネームスペースの一覧、LBAフォーマットと容量を取得し、消去方式の選択に使用します。
+use crate::EraseCapabilities;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Identifyのデータ構造の長さ（バイト）
+pub const IDENTIFY_DATA_LEN: usize = 4096;
+
+/// コントローラの情報（Identify Controller）
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NvmeController {
+    /// モデル名（MN）
+    pub model: String,
+    /// シリアル番号（SN）
+    pub serial: String,
+    /// ファームウェアのリビジョン（FR）
+    pub firmware: String,
+    /// オプションの管理コマンドの対応状況（OACS）
+    pub oacs: AdminCommandSupport,
+    /// Formatの属性（FNA）
+    pub fna: FormatAttributes,
+    /// Sanitizeの対応状況（SANICAP）
+    pub sanicap: SanitizeCapabilities,
+    /// コントローラが対応するネームスペースの最大数（NN）
+    pub namespace_count: u32,
+    /// 総容量（TNVMCAP、バイト）
+    pub total_capacity: Option<u64>,
+}
+
+/// オプションの管理コマンドの対応状況（OACS）
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdminCommandSupport {
+    /// Security Send/Receiveに対応しているか（bit 0）
+    pub security_send_receive: bool,
+    /// Format NVMに対応しているか（bit 1）
+    pub format_nvm: bool,
+    /// ネームスペースの作成と削除に対応しているか（bit 3）
+    pub namespace_management: bool,
+}
+
+/// Formatの属性（FNA）
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FormatAttributes {
+    /// Formatがすべてのネームスペースに適用されるか（bit 0）
+    pub all_namespaces: bool,
+    /// Formatのユーザーデータ消去がすべてのネームスペースに適用されるか（bit 1）
+    pub secure_erase_all_namespaces: bool,
+    /// Formatの暗号化消去に対応しているか（bit 2）
+    pub crypto_erase: bool,
+}
+
+/// Sanitizeの対応状況（SANICAP）
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SanitizeCapabilities {
+    /// 暗号化消去に対応しているか（bit 0）
+    pub crypto_erase: bool,
+    /// ブロック消去に対応しているか（bit 1）
+    pub block_erase: bool,
+    /// 上書きに対応しているか（bit 2）
+    pub overwrite: bool,
+    /// No-Deallocate After Sanitizeの指定が禁止されているか（NDI、bit 29）
+    pub no_deallocate_inhibited: bool,
+    /// No-Deallocateを指定した場合にSanitize後にメディアが追加で変更されるか（NODMMAS、bit 31:30、報告しない場合は`None`）
+    pub no_deallocate_modifies_media: Option<bool>,
+}
+
+/// ネームスペースの情報（Identify Namespace）
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NvmeNamespace {
+    /// ネームスペースID
+    pub nsid: u32,
+    /// ネームスペースのサイズ（NSZE、論理ブロック数）
+    pub size_blocks: u64,
+    /// ネームスペースの容量（NCAP、論理ブロック数）
+    pub capacity_blocks: u64,
+    /// 使用中の論理ブロック数（NUSE）
+    pub utilization_blocks: u64,
+    /// 対応しているLBAフォーマット
+    pub lba_formats: Vec<LbaFormat>,
+    /// 現在のLBAフォーマットの番号（FLBAS）
+    pub current_format: usize,
+    /// メタデータを論理ブロックの末尾に付加する拡張LBA形式か（FLBASのbit 4）
+    pub metadata_extended: bool,
+    /// エンドツーエンドデータ保護の種類（DPSのbit 2:0、0は無効）
+    pub protection_type: u8,
+    /// 保護情報をメタデータの先頭に置くか（DPSのbit 3）
+    pub protection_first: bool,
+    /// シンプロビジョニングに対応しているか（NSFEATのbit 0、対応している場合はNUSEが割り当て済みのブロック数を表す）
+    pub thin_provisioning: bool,
+    /// 割り当てを解除した論理ブロックの読み出し値（DLFEATのbit 2:0、規定されていない場合は`None`）
+    pub deallocated_read_value: Option<u8>,
+}
+
+/// LBAフォーマット
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LbaFormat {
+    /// 論理ブロックのサイズ（バイト）
+    pub data_size: u32,
+    /// 論理ブロックごとのメタデータのサイズ（バイト）
+    pub metadata_size: u16,
+    /// 相対的な性能（0が最良）
+    pub relative_performance: u8,
+}
+
+impl NvmeNamespace {
+    /// 現在のLBAフォーマット
+    pub fn format(&self) -> Option<&LbaFormat> {
+        self.lba_formats.get(self.current_format)
+    }
+
+    /// ネームスペースのサイズ（バイト）
+    pub fn size_bytes(&self) -> Option<u64> {
+        self.format()
+            .map(|f| self.size_blocks.saturating_mul(u64::from(f.data_size)))
+    }
+}
+
+/// `nvme id-ctrl -o json`の出力の必要な項目
+#[derive(Deserialize)]
+struct RawController {
+    #[serde(default)]
+    sn: String,
+    #[serde(default)]
+    mn: String,
+    #[serde(default)]
+    fr: String,
+    #[serde(default)]
+    oacs: u16,
+    #[serde(default)]
+    fna: u8,
+    #[serde(default)]
+    sanicap: u32,
+    #[serde(default)]
+    nn: u32,
+    #[serde(default)]
+    tnvmcap: Option<Value>,
+}
+
+/// `nvme id-ns -o json`の出力の必要な項目
+#[derive(Deserialize)]
+struct RawNamespace {
+    nsze: u64,
+    ncap: u64,
+    #[serde(default)]
+    nuse: u64,
+    #[serde(default)]
+    nsfeat: u8,
+    #[serde(default)]
+    flbas: u8,
+    #[serde(default)]
+    dps: u8,
+    #[serde(default)]
+    dlfeat: u8,
+    #[serde(default)]
+    lbafs: Vec<RawLbaFormat>,
+}
+
+/// `nvme id-ns -o json`のLBAフォーマット
+#[derive(Deserialize)]
+struct RawLbaFormat {
+    #[serde(default)]
+    ms: u16,
+    ds: u8,
+    #[serde(default)]
+    rp: u8,
+}
+
+impl RawController {
+    /// 取得した項目からコントローラの情報を作成する
+    fn into_controller(self) -> NvmeController {
+        NvmeController {
+            model: self.mn.trim().to_string(),
+            serial: self.sn.trim().to_string(),
+            firmware: self.fr.trim().to_string(),
+            oacs: AdminCommandSupport {
+                security_send_receive: self.oacs & 0x1 != 0,
+                format_nvm: self.oacs & 0x2 != 0,
+                namespace_management: self.oacs & 0x8 != 0,
+            },
+            fna: FormatAttributes {
+                all_namespaces: self.fna & 0x1 != 0,
+                secure_erase_all_namespaces: self.fna & 0x2 != 0,
+                crypto_erase: self.fna & 0x4 != 0,
+            },
+            sanicap: SanitizeCapabilities {
+                crypto_erase: self.sanicap & 0x1 != 0,
+                block_erase: self.sanicap & 0x2 != 0,
+                overwrite: self.sanicap & 0x4 != 0,
+                no_deallocate_inhibited: self.sanicap & (1 << 29) != 0,
+                no_deallocate_modifies_media: match self.sanicap >> 30 {
+                    1 => Some(false),
+                    2 => Some(true),
+                    _ => None,
+                },
+            },
+            namespace_count: self.nn,
+            // nvme-cliのバージョンによってはTNVMCAP（128ビット）が浮動小数点数で出力される
+            total_capacity: self
+                .tnvmcap
+                .and_then(|v| v.as_u64().or_else(|| v.as_f64().map(|f| f as u64))),
+        }
+    }
+}
+
+impl RawNamespace {
+    /// 取得した項目からネームスペースの情報を作成する
+    fn into_namespace(self, nsid: u32) -> NvmeNamespace {
+        // FLBASのbit 3:0が下位4ビット、bit 6:5が上位2ビットのフォーマット番号
+        let current_format = usize::from((self.flbas & 0x0f) | ((self.flbas >> 1) & 0x30));
+
+        NvmeNamespace {
+            nsid,
+            size_blocks: self.nsze,
+            capacity_blocks: self.ncap,
+            utilization_blocks: self.nuse,
+            lba_formats: self
+                .lbafs
+                .iter()
+                .map(|f| LbaFormat {
+                    data_size: 1u32.checked_shl(u32::from(f.ds)).unwrap_or(0),
+                    metadata_size: f.ms,
+                    relative_performance: f.rp,
+                })
+                .collect(),
+            current_format,
+            metadata_extended: self.flbas & 0x10 != 0,
+            protection_type: self.dps & 0x7,
+            protection_first: self.dps & 0x8 != 0,
+            thin_provisioning: self.nsfeat & 0x1 != 0,
+            deallocated_read_value: match self.dlfeat & 0x7 {
+                1 => Some(0x00),
+                2 => Some(0xff),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl NvmeController {
+    /// 消去方式の選択に使用する消去機能を取得する
+    ///
+    /// # 引数
+    ///
+    /// * `attached_namespaces` - コントローラに存在するネームスペースの数
+    pub fn erase_capabilities(&self, attached_namespaces: usize) -> EraseCapabilities {
+        EraseCapabilities {
+            nvme_format_supported: self.oacs.format_nvm,
+            nvme_crypto_erase_supported: self.fna.crypto_erase,
+            nvme_format_affects_other_namespaces: attached_namespaces > 1
+                && (self.fna.all_namespaces || self.fna.secure_erase_all_namespaces),
+            nvme_sanitize_crypto_supported: self.sanicap.crypto_erase,
+            nvme_sanitize_block_supported: self.sanicap.block_erase,
+            nvme_sanitize_overwrite_supported: self.sanicap.overwrite,
+            nvme_sanitize_affects_other_namespaces: attached_namespaces > 1,
+            security_protocol_supported: self.oacs.security_send_receive,
+            ..Default::default()
+        }
+    }
+}
+
+/// `nvme id-ctrl -o json`の出力を解析する関数
+///
+/// # 戻り値
+///
+/// * `Result<NvmeController, String>` - 解析したコントローラの情報、JSONが不正な場合は`Err`
+pub fn parse_controller(json: &str) -> Result<NvmeController, String> {
+    let raw: RawController = serde_json::from_str(json)
+        .map_err(|e| format!("nvme id-ctrlの出力の解析に失敗しました: {}", e))?;
+    Ok(raw.into_controller())
+}
+
+/// Identify Controllerのデータ構造を解析する関数
+///
+/// # 引数
+///
+/// * `data` - Identify（CNS 01h）で取得した4096バイトのデータ
+///
+/// # 戻り値
+///
+/// * `Result<NvmeController, String>` - 解析したコントローラの情報、データが短い場合は`Err`
+pub fn parse_controller_data(data: &[u8]) -> Result<NvmeController, String> {
+    let data = identify_data(data)?;
+    let raw = RawController {
+        sn: ascii_field(&data[4..24]),
+        mn: ascii_field(&data[24..64]),
+        fr: ascii_field(&data[64..72]),
+        oacs: u16::from_le_bytes(le_field(data, 256)),
+        fna: data[524],
+        sanicap: u32::from_le_bytes(le_field(data, 328)),
+        nn: u32::from_le_bytes(le_field(data, 516)),
+        // TNVMCAPは128ビットのため、64ビットに収まる場合のみ使用する
+        tnvmcap: (u64::from_le_bytes(le_field(data, 288)) == 0)
+            .then(|| Value::from(u64::from_le_bytes(le_field(data, 280)))),
+    };
+    Ok(raw.into_controller())
+}
+
+/// `nvme id-ns -o json`の出力を解析する関数
+///
+/// # 引数
+///
+/// * `nsid` - ネームスペースID
+/// * `json` - `nvme id-ns -o json`の標準出力
+///
+/// # 戻り値
+///
+/// * `Result<NvmeNamespace, String>` - 解析したネームスペースの情報、JSONが不正な場合は`Err`
+pub fn parse_namespace(nsid: u32, json: &str) -> Result<NvmeNamespace, String> {
+    let raw: RawNamespace = serde_json::from_str(json)
+        .map_err(|e| format!("nvme id-nsの出力の解析に失敗しました: {}", e))?;
+    Ok(raw.into_namespace(nsid))
+}
+
+/// Identify Namespaceのデータ構造を解析する関数
+///
+/// # 引数
+///
+/// * `nsid` - ネームスペースID
+/// * `data` - Identify（CNS 00h）で取得した4096バイトのデータ
+///
+/// # 戻り値
+///
+/// * `Result<NvmeNamespace, String>` - 解析したネームスペースの情報、データが短い場合は`Err`
+pub fn parse_namespace_data(nsid: u32, data: &[u8]) -> Result<NvmeNamespace, String> {
+    let data = identify_data(data)?;
+    // LBAフォーマットは128バイト目から4バイトずつ、NLBAF+1個（最大64個）格納されている
+    let count = (usize::from(data[25]) + 1).min(64);
+    let raw = RawNamespace {
+        nsze: u64::from_le_bytes(le_field(data, 0)),
+        ncap: u64::from_le_bytes(le_field(data, 8)),
+        nuse: u64::from_le_bytes(le_field(data, 16)),
+        nsfeat: data[24],
+        flbas: data[26],
+        dps: data[29],
+        dlfeat: data[33],
+        lbafs: data[128..128 + count * 4]
+            .chunks_exact(4)
+            .map(|f| RawLbaFormat {
+                ms: u16::from_le_bytes([f[0], f[1]]),
+                ds: f[2],
+                rp: f[3] & 0x3,
+            })
+            .collect(),
+    };
+    Ok(raw.into_namespace(nsid))
+}
+
+/// `nvme list-ns -o json`の出力からネームスペースIDの一覧を取得する関数
+///
+/// nvme-cliのバージョンによって`{"nsid_list":[{"nsid":1}]}`または`{"nsid_list":[1]}`の形式で出力されます。
+pub fn parse_namespace_list(json: &str) -> Result<Vec<u32>, String> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| format!("nvme list-nsの出力の解析に失敗しました: {}", e))?;
+    let list = value
+        .get("nsid_list")
+        .and_then(Value::as_array)
+        .ok_or_else(|| "nvme list-nsの出力にnsid_listがありません。".to_string())?;
+
+    Ok(list
+        .iter()
+        .filter_map(|entry| entry.get("nsid").unwrap_or(entry).as_u64())
+        .filter_map(|nsid| u32::try_from(nsid).ok())
+        .filter(|nsid| *nsid != 0)
+        .collect())
+}
+
+/// Identify（CNS 02h）で取得したアクティブなネームスペースIDの一覧を解析する関数
+///
+/// 一覧は4バイトのネームスペースIDが昇順に並び、0で終わります。
+pub fn parse_namespace_list_data(data: &[u8]) -> Result<Vec<u32>, String> {
+    Ok(identify_data(data)?
+        .chunks_exact(4)
+        .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+        .take_while(|nsid| *nsid != 0)
+        .collect())
+}
+
+/// Identifyのデータ構造が完全に取得できていることを確認する
+fn identify_data(data: &[u8]) -> Result<&[u8], String> {
+    data.get(..IDENTIFY_DATA_LEN)
+        .ok_or_else(|| format!("Identifyのデータが{}バイトしかありません。", data.len()))
+}
+
+/// データ構造の指定した位置からリトルエンディアンの値のバイト列を取り出す
+fn le_field<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    data[offset..offset + N].try_into().unwrap_or([0; N])
+}
+
+/// 空白で埋められたASCII文字列の項目を取り出す
+fn ascii_field(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end_matches(['\0', ' ']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/nvme/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn parses_client_controller() {
+        let controller = parse_controller(&fixture("samsung_pm9a1_id_ctrl.json")).unwrap();
+
+        assert_eq!(controller.model, "SAMSUNG MZVL2512HCJQ-00B00");
+        assert_eq!(controller.serial, "S676NF0R512345");
+        assert_eq!(controller.firmware, "GXA7601Q");
+        assert!(controller.oacs.format_nvm);
+        assert!(controller.oacs.security_send_receive);
+        assert!(!controller.oacs.namespace_management);
+        assert_eq!(
+            controller.fna,
+            FormatAttributes { all_namespaces: true, secure_erase_all_namespaces: false, crypto_erase: true }
+        );
+        assert_eq!(
+            controller.sanicap,
+            SanitizeCapabilities {
+                crypto_erase: false,
+                block_erase: true,
+                overwrite: false,
+                no_deallocate_inhibited: false,
+                no_deallocate_modifies_media: None,
+            }
+        );
+        assert_eq!(controller.namespace_count, 1);
+        assert_eq!(controller.total_capacity, Some(512_110_190_592));
+
+        let capabilities = controller.erase_capabilities(1);
+        assert!(capabilities.nvme_format_supported);
+        assert!(capabilities.nvme_crypto_erase_supported);
+        assert!(!capabilities.nvme_format_affects_other_namespaces);
+        assert!(capabilities.nvme_sanitize_block_supported);
+        assert!(!capabilities.nvme_sanitize_crypto_supported);
+    }
+
+    #[test]
+    fn parses_enterprise_controller_with_namespace_management() {
+        let controller = parse_controller(&fixture("intel_p4510_id_ctrl.json")).unwrap();
+
+        assert_eq!(controller.model, "INTEL SSDPE2KX040T8");
+        assert!(controller.oacs.format_nvm);
+        assert!(controller.oacs.namespace_management);
+        assert_eq!(
+            controller.fna,
+            FormatAttributes { all_namespaces: false, secure_erase_all_namespaces: false, crypto_erase: true }
+        );
+        assert_eq!(
+            controller.sanicap,
+            SanitizeCapabilities {
+                crypto_erase: true,
+                block_erase: true,
+                overwrite: false,
+                no_deallocate_inhibited: false,
+                no_deallocate_modifies_media: None,
+            }
+        );
+        assert_eq!(controller.namespace_count, 128);
+
+        // Formatがネームスペースごとに適用されるため、複数のネームスペースがあっても他に影響しない
+        assert!(!controller.erase_capabilities(2).nvme_format_affects_other_namespaces);
+        // Sanitizeは常にNVMサブシステム全体に適用される
+        assert!(controller.erase_capabilities(2).nvme_sanitize_affects_other_namespaces);
+    }
+
+    #[test]
+    fn format_on_shared_controller_affects_other_namespaces() {
+        let controller = parse_controller(&fixture("samsung_pm9a1_id_ctrl.json")).unwrap();
+        assert!(controller.erase_capabilities(2).nvme_format_affects_other_namespaces);
+    }
+
+    #[test]
+    fn parses_namespace_formats() {
+        let namespace = parse_namespace(1, &fixture("samsung_pm9a1_id_ns.json")).unwrap();
+        assert_eq!(namespace.size_blocks, 1_000_215_216);
+        assert_eq!(namespace.utilization_blocks, 312_458_112);
+        assert_eq!(namespace.current_format, 0);
+        assert_eq!(
+            namespace.format(),
+            Some(&LbaFormat { data_size: 512, metadata_size: 0, relative_performance: 0 })
+        );
+        assert_eq!(namespace.size_bytes(), Some(512_110_190_592));
+        assert!(!namespace.thin_provisioning);
+        assert_eq!(namespace.protection_type, 0);
+        // DLFEAT=9: 割り当てを解除したブロックは0x00として読み出される
+        assert_eq!(namespace.deallocated_read_value, Some(0x00));
+
+        let namespace = parse_namespace(1, &fixture("intel_p4510_id_ns.json")).unwrap();
+        assert_eq!(namespace.lba_formats.len(), 2);
+        assert_eq!(namespace.current_format, 1);
+        assert_eq!(namespace.format().map(|f| f.data_size), Some(4096));
+        assert_eq!(namespace.size_bytes(), Some(4_000_787_030_016));
+        assert!(!namespace.metadata_extended);
+        assert_eq!(namespace.deallocated_read_value, Some(0x00));
+    }
+
+    #[test]
+    fn parses_namespace_lists() {
+        assert_eq!(parse_namespace_list(&fixture("samsung_pm9a1_list_ns.json")).unwrap(), vec![1]);
+        assert_eq!(parse_namespace_list(&fixture("intel_p4510_list_ns.json")).unwrap(), vec![1, 2]);
+        assert_eq!(parse_namespace_list(r#"{"nsid_list":[1,3]}"#).unwrap(), vec![1, 3]);
+        assert!(parse_namespace_list("{}").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_output() {
+        assert!(parse_controller("NVMe status: INVALID_OPCODE").is_err());
+        assert!(parse_namespace(1, "{}").is_err());
+    }
+}
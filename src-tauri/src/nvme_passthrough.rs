@@ -0,0 +1,437 @@
+/// # NVMe管理コマンドモジュール
+///
+/// このモジュールは、NVMe管理コマンド（Identify、Get Log Page、Format NVM、Sanitize）を
+/// NVMeドライバのioctl（`NVME_IOCTL_ADMIN_CMD`）でネームスペースのデバイスに直接発行します。
+/// 返されたデータ構造は`nvme_identify`と`nvme_sanitize`の解析関数で構造化された情報に変換します。
+///
+/// コマンドの送信は`NvmeTransport`トレイトで抽象化しており、テストでは偽のトランスポートに置き換えます。
+use crate::device_backend::{FormatNvm, NvmeBackend};
+use crate::erase_method::{NvmeSanitizeOptions, SanitizeAction};
+use crate::nvme_identify::{self, NvmeController, NvmeNamespace, IDENTIFY_DATA_LEN};
+use crate::nvme_sanitize::{self, SanitizeLog};
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+/// Get Log Page
+const GET_LOG_PAGE: u8 = 0x02;
+/// Identify
+const IDENTIFY: u8 = 0x06;
+/// Format NVM
+const FORMAT_NVM: u8 = 0x80;
+/// Sanitize
+const SANITIZE: u8 = 0x84;
+
+/// IdentifyのCNS: Identify Namespace
+const CNS_NAMESPACE: u32 = 0x00;
+/// IdentifyのCNS: Identify Controller
+const CNS_CONTROLLER: u32 = 0x01;
+/// IdentifyのCNS: アクティブなネームスペースIDの一覧
+const CNS_ACTIVE_NAMESPACES: u32 = 0x02;
+
+/// Sanitize StatusログのログID
+const LOG_SANITIZE_STATUS: u32 = 0x81;
+/// Sanitize Statusログの長さ（バイト）
+const SANITIZE_LOG_LEN: usize = 512;
+
+/// すべてのネームスペースを示すネームスペースID
+const NSID_ALL: u32 = 0xffff_ffff;
+
+/// 管理コマンドのタイムアウト
+const ADMIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Format NVMのタイムアウト（ユーザーデータ消去はメディア全体の消去を待つ）
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// 発行するNVMe管理コマンド
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AdminCommand {
+    /// 操作コード
+    pub opcode: u8,
+    /// ネームスペースID
+    pub nsid: u32,
+    /// コマンド固有のDword 10
+    pub cdw10: u32,
+    /// コマンド固有のDword 11
+    pub cdw11: u32,
+}
+
+/// NVMe管理コマンドをコントローラに送信するトランスポート
+pub trait NvmeTransport: Send {
+    /// 管理コマンドを送信し、完了を待つ
+    ///
+    /// # 引数
+    ///
+    /// * `command` - 送信するコマンド
+    /// * `data` - コントローラから読み出すデータのバッファ（データ転送がない場合は`None`）
+    /// * `timeout` - コマンドのタイムアウト
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<u32, String>` - 完了キューエントリのDword 0、失敗した場合は`Err`
+    fn admin(&mut self, command: &AdminCommand, data: Option<&mut [u8]>, timeout: Duration) -> Result<u32, String>;
+}
+
+/// NVMeのステータスフィールドをエラーメッセージに変換する関数
+///
+/// # 引数
+///
+/// * `status` - ステータスコードの種類（bit 10:8）とステータスコード（bit 7:0）
+pub fn status_message(status: u32) -> String {
+    let status = status & 0x7ff;
+    let description = match status {
+        0x001 => "コントローラが対応していないコマンドです",
+        0x002 => "コマンドのフィールドが不正です",
+        0x00b => "ネームスペースIDまたはフォーマットが不正です",
+        0x01c => "前回のSanitizeが失敗した状態です",
+        0x01d => "Sanitizeを実行中です",
+        0x10a => "LBAフォーマットが不正です",
+        _ => "コマンドがエラーで終了しました",
+    };
+    format!("{}（NVMeステータス 0x{:03x}）。", description, status)
+}
+
+/// NVMeのioctlで管理コマンドを発行するバックエンド
+pub struct AdminBackend<T: NvmeTransport = NvmeIoctlTransport> {
+    transport: T,
+}
+
+impl AdminBackend {
+    /// ネームスペースのデバイスを開き、Identify Controllerに応答することを確認する
+    pub fn open(device: &str) -> Result<Self, String> {
+        AdminBackend::probe(NvmeIoctlTransport::open(device)?)
+    }
+}
+
+impl<T: NvmeTransport> AdminBackend<T> {
+    /// トランスポートを指定してバックエンドを作成する
+    pub fn new(transport: T) -> Self {
+        AdminBackend { transport }
+    }
+
+    /// トランスポートを指定してバックエンドを作成し、Identify Controllerに応答することを確認する
+    pub fn probe(transport: T) -> Result<Self, String> {
+        let mut backend = AdminBackend::new(transport);
+        backend.identify_controller()?;
+        Ok(backend)
+    }
+
+    /// Identifyでデータ構造を取得する
+    fn identify(&mut self, cns: u32, nsid: u32) -> Result<Vec<u8>, String> {
+        let mut data = vec![0u8; IDENTIFY_DATA_LEN];
+        let command = AdminCommand {
+            opcode: IDENTIFY,
+            nsid,
+            cdw10: cns,
+            ..Default::default()
+        };
+        self.transport.admin(&command, Some(&mut data), ADMIN_TIMEOUT)?;
+        Ok(data)
+    }
+}
+
+impl<T: NvmeTransport> NvmeBackend for AdminBackend<T> {
+    fn name(&self) -> &'static str {
+        "NVMe ioctl"
+    }
+
+    fn identify_controller(&mut self) -> Result<NvmeController, String> {
+        nvme_identify::parse_controller_data(&self.identify(CNS_CONTROLLER, 0)?)
+    }
+
+    fn identify_namespace(&mut self, nsid: u32) -> Result<NvmeNamespace, String> {
+        nvme_identify::parse_namespace_data(nsid, &self.identify(CNS_NAMESPACE, nsid)?)
+    }
+
+    fn namespace_list(&mut self) -> Result<Vec<u32>, String> {
+        // NSIDに0を指定すると、1以上のアクティブなネームスペースIDが返される
+        nvme_identify::parse_namespace_list_data(&self.identify(CNS_ACTIVE_NAMESPACES, 0)?)
+    }
+
+    fn format(&mut self, format: &FormatNvm) -> Result<(), String> {
+        // LBAFの下位4ビット(3:0)、MSET(4)、PI(7:5)、PIL(8)、SES(11:9)、LBAFの上位2ビット(13:12)
+        let lba_format = u32::from(format.lba_format);
+        let cdw10 = (lba_format & 0xf)
+            | u32::from(format.metadata_extended) << 4
+            | u32::from(format.protection_type & 0x7) << 5
+            | u32::from(format.protection_first) << 8
+            | u32::from(format.ses & 0x7) << 9
+            | ((lba_format >> 4) & 0x3) << 12;
+        let command = AdminCommand {
+            opcode: FORMAT_NVM,
+            nsid: format.nsid,
+            cdw10,
+            ..Default::default()
+        };
+        self.transport.admin(&command, None, FORMAT_TIMEOUT).map(|_| ())
+    }
+
+    fn sanitize(&mut self, action: SanitizeAction, options: &NvmeSanitizeOptions) -> Result<(), String> {
+        // SANACT(2:0)、AUSE(3)、OWPASS(7:4、16回は0)、OIPBP(8)、NDAS(9)
+        // AUSEは0（制限付き完了モード）で開始する
+        let mut cdw10 = u32::from(nvme_sanitize::sanitize_action_code(action));
+        let mut cdw11 = 0;
+        if action == SanitizeAction::Overwrite {
+            cdw10 |= u32::from(options.overwrite_passes & 0xf) << 4;
+            cdw10 |= u32::from(options.invert_pattern) << 8;
+            cdw11 = options.overwrite_pattern;
+        }
+        cdw10 |= u32::from(options.no_deallocate) << 9;
+        let command = AdminCommand {
+            opcode: SANITIZE,
+            nsid: 0,
+            cdw10,
+            cdw11,
+        };
+        self.transport.admin(&command, None, ADMIN_TIMEOUT).map(|_| ())
+    }
+
+    fn sanitize_log(&mut self) -> Result<SanitizeLog, String> {
+        let mut data = vec![0u8; SANITIZE_LOG_LEN];
+        // LID(7:0)、取得するDword数-1の下位16ビット(31:16)
+        let dwords = (SANITIZE_LOG_LEN / 4 - 1) as u32;
+        let command = AdminCommand {
+            opcode: GET_LOG_PAGE,
+            nsid: NSID_ALL,
+            cdw10: LOG_SANITIZE_STATUS | dwords << 16,
+            ..Default::default()
+        };
+        self.transport.admin(&command, Some(&mut data), ADMIN_TIMEOUT)?;
+        nvme_sanitize::parse_sanitize_log_data(&data)
+    }
+}
+
+/// NVMe管理コマンドのioctl（`_IOWR('N', 0x41, struct nvme_passthru_cmd)`）
+const NVME_IOCTL_ADMIN_CMD: libc::c_ulong = 0xc048_4e41;
+
+/// Linuxの`struct nvme_passthru_cmd`
+#[repr(C)]
+#[derive(Default)]
+struct NvmePassthruCmd {
+    opcode: u8,
+    flags: u8,
+    rsvd1: u16,
+    nsid: u32,
+    cdw2: u32,
+    cdw3: u32,
+    metadata: u64,
+    addr: u64,
+    metadata_len: u32,
+    data_len: u32,
+    cdw10: u32,
+    cdw11: u32,
+    cdw12: u32,
+    cdw13: u32,
+    cdw14: u32,
+    cdw15: u32,
+    timeout_ms: u32,
+    result: u32,
+}
+
+/// NVMeのioctlで管理コマンドを送信するトランスポート
+pub struct NvmeIoctlTransport {
+    file: File,
+}
+
+impl NvmeIoctlTransport {
+    /// デバイスを開く（管理コマンドの発行にはCAP_SYS_ADMINが必要です）
+    pub fn open(device: &str) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .read(true)
+            .open(device)
+            .map_err(|e| format!("{}を開けませんでした: {}", device, e))?;
+        Ok(NvmeIoctlTransport { file })
+    }
+}
+
+impl NvmeTransport for NvmeIoctlTransport {
+    fn admin(&mut self, command: &AdminCommand, data: Option<&mut [u8]>, timeout: Duration) -> Result<u32, String> {
+        let (addr, data_len) = match data {
+            Some(data) => (data.as_mut_ptr() as u64, data.len() as u32),
+            None => (0, 0),
+        };
+        let mut cmd = NvmePassthruCmd {
+            opcode: command.opcode,
+            nsid: command.nsid,
+            addr,
+            data_len,
+            cdw10: command.cdw10,
+            cdw11: command.cdw11,
+            timeout_ms: timeout.as_millis().min(u128::from(u32::MAX)) as u32,
+            ..Default::default()
+        };
+
+        // SAFETY: cmdは有効な構造体で、addrはこの呼び出しの間有効なdata_lenバイトのバッファを指している
+        let ret = unsafe { libc::ioctl(self.file.as_raw_fd(), NVME_IOCTL_ADMIN_CMD, &mut cmd) };
+        match ret {
+            0 => Ok(cmd.result),
+            // 正の値はコマンドの完了ステータス
+            status if status > 0 => Err(status_message(status as u32)),
+            _ => Err(format!(
+                "NVMe管理コマンドの発行に失敗しました: {}",
+                std::io::Error::last_os_error()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvme_sanitize::SanitizeStatus;
+    use std::collections::VecDeque;
+
+    /// 送信したコマンドを記録し、用意した応答を返す偽のトランスポート
+    #[derive(Default)]
+    struct FakeTransport {
+        commands: Vec<AdminCommand>,
+        responses: VecDeque<Result<Vec<u8>, String>>,
+    }
+
+    impl FakeTransport {
+        fn respond(mut self, response: Result<Vec<u8>, String>) -> Self {
+            self.responses.push_back(response);
+            self
+        }
+    }
+
+    impl NvmeTransport for FakeTransport {
+        fn admin(&mut self, command: &AdminCommand, data: Option<&mut [u8]>, _timeout: Duration) -> Result<u32, String> {
+            self.commands.push(*command);
+            let response = self.responses.pop_front().ok_or("応答がありません")??;
+            if let Some(data) = data {
+                data[..response.len()].copy_from_slice(&response);
+            }
+            Ok(0)
+        }
+    }
+
+    fn put(data: &mut [u8], offset: usize, bytes: &[u8]) {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    #[test]
+    fn identifies_controller_and_namespace() {
+        let mut controller = vec![0u8; IDENTIFY_DATA_LEN];
+        put(&mut controller, 4, b"S676NF0R512345      ");
+        put(&mut controller, 24, format!("{:<40}", "SAMSUNG MZVL2512HCJQ-00B00").as_bytes());
+        put(&mut controller, 64, b"GXA7601Q");
+        put(&mut controller, 256, &0x0017u16.to_le_bytes());
+        put(&mut controller, 280, &512_110_190_592u64.to_le_bytes());
+        put(&mut controller, 328, &0x2000_0002u32.to_le_bytes());
+        put(&mut controller, 516, &1u32.to_le_bytes());
+        controller[524] = 0x05;
+
+        let mut namespace = vec![0u8; IDENTIFY_DATA_LEN];
+        put(&mut namespace, 0, &976_754_646u64.to_le_bytes());
+        put(&mut namespace, 8, &976_754_646u64.to_le_bytes());
+        namespace[24] = 0x01;
+        namespace[25] = 1;
+        namespace[26] = 0x01;
+        namespace[33] = 0x09;
+        put(&mut namespace, 128, &[0, 0, 9, 2]);
+        put(&mut namespace, 132, &[0, 0, 12, 0]);
+
+        let transport = FakeTransport::default()
+            .respond(Ok(controller))
+            .respond(Ok(namespace));
+        let mut backend = AdminBackend::new(transport);
+
+        let controller = backend.identify_controller().unwrap();
+        assert_eq!(controller.model, "SAMSUNG MZVL2512HCJQ-00B00");
+        assert_eq!(controller.serial, "S676NF0R512345");
+        assert_eq!(controller.firmware, "GXA7601Q");
+        assert!(controller.oacs.format_nvm && controller.oacs.security_send_receive);
+        assert!(controller.fna.all_namespaces && controller.fna.crypto_erase);
+        assert!(controller.sanicap.block_erase && controller.sanicap.no_deallocate_inhibited);
+        assert_eq!(controller.namespace_count, 1);
+        assert_eq!(controller.total_capacity, Some(512_110_190_592));
+
+        let namespace = backend.identify_namespace(1).unwrap();
+        assert_eq!(namespace.size_blocks, 976_754_646);
+        assert_eq!(namespace.lba_formats.len(), 2);
+        assert_eq!(namespace.current_format, 1);
+        assert_eq!(namespace.format().map(|f| f.data_size), Some(4096));
+        assert!(namespace.thin_provisioning);
+        assert_eq!(namespace.deallocated_read_value, Some(0x00));
+
+        let commands = &backend.transport.commands;
+        assert_eq!(commands[0], AdminCommand { opcode: IDENTIFY, nsid: 0, cdw10: 1, cdw11: 0 });
+        assert_eq!(commands[1], AdminCommand { opcode: IDENTIFY, nsid: 1, cdw10: 0, cdw11: 0 });
+    }
+
+    #[test]
+    fn lists_active_namespaces() {
+        let mut list = vec![0u8; IDENTIFY_DATA_LEN];
+        put(&mut list, 0, &1u32.to_le_bytes());
+        put(&mut list, 4, &3u32.to_le_bytes());
+        let mut backend = AdminBackend::new(FakeTransport::default().respond(Ok(list)));
+        assert_eq!(backend.namespace_list().unwrap(), vec![1, 3]);
+        assert_eq!(backend.transport.commands[0].cdw10, CNS_ACTIVE_NAMESPACES);
+    }
+
+    #[test]
+    fn encodes_format_and_sanitize_commands() {
+        let transport = FakeTransport::default()
+            .respond(Ok(vec![]))
+            .respond(Ok(vec![]))
+            .respond(Ok(vec![]));
+        let mut backend = AdminBackend::new(transport);
+
+        backend
+            .format(&FormatNvm {
+                nsid: 2,
+                lba_format: 1,
+                ses: 2,
+                metadata_extended: false,
+                protection_type: 1,
+                protection_first: true,
+            })
+            .unwrap();
+        backend
+            .sanitize(
+                SanitizeAction::Overwrite,
+                &NvmeSanitizeOptions {
+                    overwrite_passes: 16,
+                    overwrite_pattern: 0xdead_beef,
+                    invert_pattern: true,
+                    no_deallocate: true,
+                },
+            )
+            .unwrap();
+        backend
+            .sanitize(SanitizeAction::BlockErase, &NvmeSanitizeOptions::default())
+            .unwrap();
+
+        let commands = &backend.transport.commands;
+        assert_eq!(commands[0], AdminCommand { opcode: FORMAT_NVM, nsid: 2, cdw10: 0x521, cdw11: 0 });
+        // 16回の上書きはOWPASSに0を指定する
+        assert_eq!(commands[1], AdminCommand { opcode: SANITIZE, nsid: 0, cdw10: 0x303, cdw11: 0xdead_beef });
+        assert_eq!(commands[2], AdminCommand { opcode: SANITIZE, nsid: 0, cdw10: 0x002, cdw11: 0 });
+    }
+
+    #[test]
+    fn reads_sanitize_status_log() {
+        let mut log = vec![0u8; SANITIZE_LOG_LEN];
+        // SPROG=0x6666、SSTAT=完了したパス1 | 実行中
+        put(&mut log, 0, &[0x66, 0x66, 0x0a, 0x00]);
+        let transport = FakeTransport::default()
+            .respond(Ok(log))
+            .respond(Err(status_message(0x401d)));
+        let mut backend = AdminBackend::new(transport);
+
+        let log = backend.sanitize_log().unwrap();
+        assert_eq!(log.status, SanitizeStatus::InProgress);
+        assert_eq!(log.progress, 0x6666);
+        assert_eq!(log.completed_passes, 1);
+        assert_eq!(
+            backend.transport.commands[0],
+            AdminCommand { opcode: GET_LOG_PAGE, nsid: NSID_ALL, cdw10: 0x007f_0081, cdw11: 0 }
+        );
+
+        // 完了ステータスはDNRビットを除いて説明に変換される
+        let error = backend.sanitize(SanitizeAction::CryptoErase, &NvmeSanitizeOptions::default()).unwrap_err();
+        assert!(error.contains("Sanitizeを実行中です"));
+        assert!(error.contains("0x01d"));
+    }
+}
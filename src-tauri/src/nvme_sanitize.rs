@@ -0,0 +1,430 @@
+/// # NVMe Sanitizeモジュール
+///
+/// このモジュールは、NVMe Sanitize（ブロック消去、暗号化消去、上書き）を実行します。
+/// コマンドはNVMeのioctl、使用できない場合は`nvme`（nvme-cli）で発行します。
+/// 実行できる操作はコントローラのSANICAPで確認し、開始後はSanitize Statusログページを
+/// ポーリングして、進捗（SPROG）を進捗イベントとして送信します。
+///
+/// Sanitizeはコントローラに接続されたすべてのネームスペースに適用され、開始後は中断できません。
+/// このモジュールはSanitizeを制限付き完了モード（AUSE=0）で開始するため、失敗した場合は
+/// 電源を入れ直してもコントローラが失敗状態のまま残り、再度Sanitizeが成功するまで解除されません。
+use crate::cancellation::DiskControl;
+use crate::device_backend::NvmeBackend;
+use crate::erase_method::{EraseError, EraseMethod, NvmeSanitizeOptions, SanitizeAction, SanitizeReport};
+use crate::logger::log_message;
+use crate::progress::ProgressReporter;
+use serde_json::Value;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sanitize Statusログを確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// 進捗メッセージを送信する間隔
+const REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// ログの取得に連続して失敗した場合に諦める回数
+const STATUS_ERROR_LIMIT: u32 = 30;
+
+/// SPROGの分母（SPROG / 65536が進捗の割合）
+const PROGRESS_DENOMINATOR: u64 = 65536;
+
+/// 最後のSanitizeの状態（SSTATのbit 2:0）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SanitizeStatus {
+    /// 0: NVMサブシステムは一度もSanitizeされていない
+    NeverSanitized,
+    /// 1: 最後のSanitizeが正常に完了した
+    Succeeded,
+    /// 2: Sanitizeを実行中
+    InProgress,
+    /// 3: 最後のSanitizeが失敗した
+    Failed,
+    /// 4: No-Deallocateを指定した最後のSanitizeが、割り当て解除を伴って正常に完了した
+    SucceededWithDeallocation,
+    /// 仕様で定義されていない値
+    Unknown(u8),
+}
+
+impl SanitizeStatus {
+    /// SSTATのbit 2:0から状態を作成する
+    fn from_code(code: u8) -> Self {
+        match code {
+            0 => SanitizeStatus::NeverSanitized,
+            1 => SanitizeStatus::Succeeded,
+            2 => SanitizeStatus::InProgress,
+            3 => SanitizeStatus::Failed,
+            4 => SanitizeStatus::SucceededWithDeallocation,
+            other => SanitizeStatus::Unknown(other),
+        }
+    }
+
+    /// 消去結果に記録する状態の説明
+    fn description(&self) -> String {
+        match self {
+            SanitizeStatus::NeverSanitized => "SSTAT 0: Sanitizeの実行履歴なし".to_string(),
+            SanitizeStatus::Succeeded => "SSTAT 1: 正常に完了".to_string(),
+            SanitizeStatus::InProgress => "SSTAT 2: 実行中".to_string(),
+            SanitizeStatus::Failed => "SSTAT 3: 失敗".to_string(),
+            SanitizeStatus::SucceededWithDeallocation => {
+                "SSTAT 4: 割り当て解除を伴って正常に完了".to_string()
+            }
+            SanitizeStatus::Unknown(code) => format!("SSTAT {}: 不明な状態", code),
+        }
+    }
+}
+
+/// Sanitize Statusログ
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SanitizeLog {
+    /// 進捗（SPROG、0〜65535）
+    pub progress: u16,
+    /// 最後のSanitizeの状態
+    pub status: SanitizeStatus,
+    /// 完了した上書きのパス数
+    pub completed_passes: u8,
+    /// 製造後またはSanitize後にユーザーデータが一度も書き込まれていないか（Global Data Erased）
+    pub global_erased: bool,
+}
+
+impl SanitizeLog {
+    /// 進捗率（%）
+    fn percent(&self) -> u64 {
+        u64::from(self.progress) * 100 / PROGRESS_DENOMINATOR
+    }
+}
+
+/// NVMe Sanitizeを実行する関数
+///
+/// # 引数
+///
+/// * `device` - 消去対象のデバイス名（例: /dev/nvme0n1）
+/// * `backend` - NVMe管理コマンドを発行するバックエンド
+/// * `size` - ディスクのサイズ（バイト、進捗の表示に使用する）
+/// * `action` - Sanitizeの操作
+/// * `options` - 上書きのパターンとパス数、No-Deallocateの指定
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
+/// # 戻り値
+///
+/// * `Result<(), EraseError>` - 成功時は`Ok(())`、失敗時はドライブがSanitizeを受け付けたかどうかとエラーメッセージを含む`Err`
+pub fn sanitize(
+    device: &str,
+    mut backend: Box<dyn NvmeBackend>,
+    size: u64,
+    action: SanitizeAction,
+    options: &NvmeSanitizeOptions,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let method = EraseMethod::NvmeSanitize { action };
+    reporter.set_method(method.name(), 1, size);
+    reporter.start_pass(1);
+
+    precheck(device, backend.as_mut(), action, options)
+        .inspect_err(|e| log_message("NVMe Sanitize", "中止", e))
+        .map_err(EraseError::NotStarted)?;
+
+    // Sanitizeは開始すると中断できない
+    let _guard = control
+        .enter_non_interruptible(method.name())
+        .map_err(EraseError::NotStarted)?;
+    backend.sanitize(action, options).map_err(|e| {
+        let message = format!("{}の{}を開始できませんでした: {}", device, method.name(), e);
+        log_message("NVMe Sanitize", "失敗", &message);
+        // ドライブがコマンドを拒否した場合は消去を開始していない
+        EraseError::NotStarted(message)
+    })?;
+    log_message(
+        "NVMe Sanitize",
+        "開始",
+        &format!("{}の{}を開始しました（{}）。", device, method.name(), backend.name()),
+    );
+
+    let last = wait_for_completion(device, size, backend.as_mut(), reporter)
+        .map_err(EraseError::Started)?;
+    let report = SanitizeReport {
+        method,
+        completed: matches!(
+            last.status,
+            SanitizeStatus::Succeeded | SanitizeStatus::SucceededWithDeallocation
+        ),
+        state: if last.global_erased {
+            format!("{}、Global Data Erased", last.status.description())
+        } else {
+            last.status.description()
+        },
+        failure_persists: last.status == SanitizeStatus::Failed,
+    };
+    reporter.record_sanitize(&report);
+
+    if !report.completed {
+        let message = if report.failure_persists {
+            format!(
+                "{}の{}が失敗しました（{}）。コントローラは電源を入れ直しても失敗状態のまま残り、\
+                 再度Sanitizeが成功するまで読み書きできない場合があります。",
+                device,
+                method.name(),
+                report.state
+            )
+        } else {
+            format!(
+                "{}の{}が正常に完了したことを確認できませんでした（{}）。",
+                device,
+                method.name(),
+                report.state
+            )
+        };
+        log_message("NVMe Sanitize", "失敗", &message);
+        return Err(EraseError::Started(message));
+    }
+
+    log_message(
+        "NVMe Sanitize",
+        "成功",
+        &format!("{}の{}が正常に完了しました（{}）。", device, method.name(), report.state),
+    );
+    Ok(())
+}
+
+/// Sanitizeの操作に対応するSANACTの値
+pub fn sanitize_action_code(action: SanitizeAction) -> u8 {
+    match action {
+        SanitizeAction::BlockErase => 2,
+        SanitizeAction::Overwrite => 3,
+        SanitizeAction::CryptoErase => 4,
+    }
+}
+
+/// コントローラのSANICAPと現在のSanitizeの状態から実行できるかを確認する関数
+fn precheck(
+    device: &str,
+    backend: &mut dyn NvmeBackend,
+    action: SanitizeAction,
+    options: &NvmeSanitizeOptions,
+) -> Result<(), String> {
+    let sanicap = backend.identify_controller()?.sanicap;
+    let supported = match action {
+        SanitizeAction::BlockErase => sanicap.block_erase,
+        SanitizeAction::CryptoErase => sanicap.crypto_erase,
+        SanitizeAction::Overwrite => sanicap.overwrite,
+    };
+    if !supported {
+        return Err(format!(
+            "{}のコントローラは{}に対応していません。",
+            device,
+            EraseMethod::NvmeSanitize { action }.name()
+        ));
+    }
+
+    if options.no_deallocate {
+        if sanicap.no_deallocate_inhibited {
+            return Err(format!(
+                "{}のコントローラはNo-Deallocate After Sanitizeの指定を禁止しています。\
+                 No-Deallocateを指定せずに実行してください。",
+                device
+            ));
+        }
+        // 割り当てを解除しない場合、Sanitize後の読み出し値はメディアの状態に依存する
+        let note = match sanicap.no_deallocate_modifies_media {
+            Some(true) => "コントローラはSanitize後にメディアを追加で変更します。",
+            Some(false) => "コントローラはSanitize後にメディアを追加で変更しません。",
+            None => "コントローラはSanitize後にメディアを変更するかを報告していません。",
+        };
+        log_message(
+            "NVMe Sanitize",
+            "情報",
+            &format!("{}: No-Deallocateを指定して実行します。{}", device, note),
+        );
+    }
+
+    let log = backend.sanitize_log()?;
+    match log.status {
+        SanitizeStatus::InProgress => Err(format!(
+            "{}では既にSanitizeが実行中です（{}%）。完了してから再度実行してください。",
+            device,
+            log.percent()
+        )),
+        SanitizeStatus::Failed => {
+            // 失敗状態は新たなSanitizeの成功で解除される
+            log_message(
+                "NVMe Sanitize",
+                "情報",
+                &format!("{}は前回のSanitizeが失敗した状態です。再度Sanitizeを実行します。", device),
+            );
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Sanitizeが終了するまでSanitize Statusログをポーリングし、進捗を通知する関数
+///
+/// # 戻り値
+///
+/// * `Result<SanitizeLog, String>` - 終了後のログ、ログを取得できなくなった場合は`Err`
+fn wait_for_completion(
+    device: &str,
+    size: u64,
+    backend: &mut dyn NvmeBackend,
+    reporter: &mut ProgressReporter,
+) -> Result<SanitizeLog, String> {
+    let started = Instant::now();
+    let mut last_report: Option<Instant> = None;
+    let mut errors = 0;
+    loop {
+        let log = match backend.sanitize_log() {
+            Ok(log) => {
+                errors = 0;
+                log
+            }
+            Err(e) => {
+                errors += 1;
+                if errors >= STATUS_ERROR_LIMIT {
+                    return Err(format!(
+                        "{}のSanitize Statusログを取得できません: {}。コントローラはSanitizeを続けている可能性があります。",
+                        device, e
+                    ));
+                }
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+        };
+
+        if log.status != SanitizeStatus::InProgress {
+            return Ok(log);
+        }
+
+        reporter.update((size as u128 * u128::from(log.progress) / PROGRESS_DENOMINATOR as u128) as u64);
+        if last_report.is_none_or(|t| t.elapsed() >= REPORT_INTERVAL) {
+            reporter.report_message(&format!(
+                "{}をSanitize中です（{}%、完了したパス {}、経過 {}分）。",
+                device,
+                log.percent(),
+                log.completed_passes,
+                started.elapsed().as_secs() / 60
+            ));
+            last_report = Some(Instant::now());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// `nvme sanitize-log -o json`の出力を解析する関数
+///
+/// nvme-cliのバージョンにより、ログはデバイス名をキーとしたオブジェクトの中に出力され、
+/// SSTATは数値またはフィールドごとに分解したオブジェクトとして出力されます。
+///
+/// # 戻り値
+///
+/// * `Result<SanitizeLog, String>` - 解析したログ、JSONが不正な場合は`Err`
+pub fn parse_sanitize_log(json: &str) -> Result<SanitizeLog, String> {
+    let root: Value = serde_json::from_str(json)
+        .map_err(|e| format!("nvme sanitize-logの出力の解析に失敗しました: {}", e))?;
+    let log = if root.get("sprog").is_some() {
+        &root
+    } else {
+        root.as_object()
+            .and_then(|object| object.values().find(|value| value.get("sprog").is_some()))
+            .ok_or_else(|| "nvme sanitize-logの出力にSPROGがありません。".to_string())?
+    };
+
+    let progress = log["sprog"].as_u64().unwrap_or(0).min(u64::from(u16::MAX)) as u16;
+    let (status, completed_passes, global_erased) = match &log["sstat"] {
+        Value::Number(sstat) => decode_sstat(sstat.as_u64().unwrap_or(0) as u16),
+        Value::Object(sstat) => {
+            // 例: "status": "(1) The most recent sanitize operation completed successfully"
+            let status = match sstat.get("status") {
+                Some(Value::Number(n)) => n.as_u64(),
+                Some(Value::String(s)) => s
+                    .trim_start_matches('(')
+                    .split(')')
+                    .next()
+                    .and_then(|n| n.trim().parse().ok()),
+                _ => None,
+            }
+            .ok_or_else(|| "nvme sanitize-logの出力のSSTATを解析できませんでした。".to_string())?;
+            (
+                status as u8,
+                sstat.get("no_cmplted_passes").and_then(Value::as_u64).unwrap_or(0) as u8,
+                sstat.get("global_erased").and_then(Value::as_u64).unwrap_or(0) != 0,
+            )
+        }
+        _ => return Err("nvme sanitize-logの出力にSSTATがありません。".to_string()),
+    };
+
+    Ok(SanitizeLog {
+        progress,
+        status: SanitizeStatus::from_code(status),
+        completed_passes,
+        global_erased,
+    })
+}
+
+/// Sanitize Statusログページ（ログID 81h）のデータを解析する関数
+///
+/// # 引数
+///
+/// * `data` - Get Log Pageで取得したログページの先頭（4バイト以上）
+///
+/// # 戻り値
+///
+/// * `Result<SanitizeLog, String>` - 解析したログ、データが短い場合は`Err`
+pub fn parse_sanitize_log_data(data: &[u8]) -> Result<SanitizeLog, String> {
+    let [sprog0, sprog1, sstat0, sstat1, ..] = *data else {
+        return Err(format!("Sanitize Statusログが{}バイトしかありません。", data.len()));
+    };
+    let (status, completed_passes, global_erased) = decode_sstat(u16::from_le_bytes([sstat0, sstat1]));
+    Ok(SanitizeLog {
+        progress: u16::from_le_bytes([sprog0, sprog1]),
+        status: SanitizeStatus::from_code(status),
+        completed_passes,
+        global_erased,
+    })
+}
+
+/// SSTATを状態（bit 2:0）、完了したパス数（bit 7:3）、Global Data Erased（bit 8）に分解する
+fn decode_sstat(sstat: u16) -> (u8, u8, bool) {
+    ((sstat & 0x7) as u8, ((sstat >> 3) & 0x1f) as u8, sstat & 0x100 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_log_with_decomposed_sstat() {
+        let json = r#"{
+  "nvme0n1" : {
+    "sprog" : 26214,
+    "sstat" : {
+      "global_erased" : 0,
+      "no_cmplted_passes" : 1,
+      "status" : "(2) A sanitize operation is currently in progress"
+    },
+    "cdw10_info" : 3,
+    "time_over_write" : 4294967295
+  }
+}"#;
+        let log = parse_sanitize_log(json).unwrap();
+        assert_eq!(log.status, SanitizeStatus::InProgress);
+        assert_eq!(log.progress, 26214);
+        assert_eq!(log.percent(), 39);
+        assert_eq!(log.completed_passes, 1);
+        assert!(!log.global_erased);
+    }
+
+    #[test]
+    fn parses_log_with_numeric_sstat() {
+        // SSTAT = Global Data Erased | 完了したパス 0 | 状態 1（正常に完了）
+        let log = parse_sanitize_log(r#"{"sprog": 65535, "sstat": 257, "cdw10_info": 4}"#).unwrap();
+        assert_eq!(log.status, SanitizeStatus::Succeeded);
+        assert_eq!(log.progress, 65535);
+        assert!(log.global_erased);
+
+        let failed = parse_sanitize_log(r#"{"sprog": 65535, "sstat": 3}"#).unwrap();
+        assert_eq!(failed.status, SanitizeStatus::Failed);
+        assert!(parse_sanitize_log(r#"{"nvme0": {}}"#).is_err());
+    }
+}
@@ -0,0 +1,1340 @@
+/// # 上書きエンジンモジュール
+///
+/// このモジュールは、ddを使わずにプロセス内でディスクへ上書きを行う機能を提供します。
+/// ブロックデバイスはO_DIRECTで開き、デバイスの論理/物理セクタサイズに
+/// 揃えたバッファで末尾の端数ブロックを含むすべてのバイトを書き込みます。
+/// 各パスの終了時にはキャッシュをフラッシュし、実際に書き込んだバイト数を報告します。
+/// 最終パスと検証指定のあるパスは書き込み後に読み戻し、期待したパターンと比較します。
+/// ランダムデータはパスごとのシードからプロセス内で生成するため、同じく検証できます。
+/// 実行中はチェックポイントを定期的に保存し、中断した消去を途中から再開できます。
+/// 書き込みや読み込みに失敗した領域はより小さいI/Oサイズで再試行し、
+/// それでも失敗したセクタは不良セクタとして記録したうえでパスを続行します。
+/// 通常のファイルやスパースイメージに対しても同じ処理を実行できます。
+use crate::cancellation::DiskControl;
+use crate::checkpoint::{self, Checkpoint, CheckpointPhase, CheckpointWriter, DiskIdentity};
+use crate::csprng::{PassSeed, RandomStream};
+use crate::erase_method::EraseError;
+use crate::erase_standards::OverwriteScheme;
+use crate::logger::log_message;
+use crate::progress::ProgressReporter;
+use crate::verification::{LbaRange, LbaRangeCollector, VerificationLevel, VerificationReport};
+use std::alloc::{self, Layout};
+use std::collections::BTreeSet;
+use std::fs::{File, OpenOptions};
+#[cfg(not(unix))]
+use std::io::{Read, Write};
+#[cfg(not(target_os = "linux"))]
+use std::io::{Seek, SeekFrom};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+/// 1回の書き込みで使用するバッファの目安サイズ（4MiB）
+const CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// セクタサイズを取得できない場合に使用する既定値
+const DEFAULT_SECTOR_SIZE: u32 = 512;
+
+/// 失敗した領域を再試行する際に最初に使用するI/Oサイズ（64KiB）
+const RETRY_IO_SIZE: usize = 64 * 1024;
+
+/// 不良セクタとして記録するセクタ数の上限
+///
+/// これを超える場合はディスクの切断やコントローラの故障とみなし、パスを中止します。
+const MAX_BAD_SECTORS: usize = 65536;
+
+/// 1パスで書き込むデータのパターン
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FillPattern {
+    /// 固定の1バイト値
+    Byte(u8),
+    /// 複数バイトの繰り返しパターン
+    Repeat(Vec<u8>),
+    /// ランダムデータ
+    Random,
+    /// 各論理セクタをそのLBA（64ビットリトルエンディアン）の繰り返しで埋めるパターン
+    LbaStamp,
+}
+
+impl FillPattern {
+    /// ログに記録するパターンの説明
+    pub fn description(&self) -> String {
+        match self {
+            FillPattern::Byte(value) => format!("0x{:02X}", value),
+            FillPattern::Repeat(bytes) => bytes
+                .iter()
+                .map(|b| format!("0x{:02X}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+            FillPattern::Random => "ランダムデータ".to_string(),
+            FillPattern::LbaStamp => "LBAスタンプ".to_string(),
+        }
+    }
+
+    /// 対象先頭からのオフセット`offset`に書き込むデータで`buffer`を埋める
+    ///
+    /// 繰り返しパターンはチャンク境界をまたいでも周期が途切れないよう、
+    /// 対象先頭からの位置に基づいて埋めます。ランダムデータは`PassData`で生成します。
+    ///
+    /// # 引数
+    ///
+    /// * `buffer` - 埋めるバッファ
+    /// * `offset` - バッファ先頭の対象先頭からのオフセット
+    /// * `sector_size` - LBAスタンプの計算に使用する論理セクタサイズ
+    fn fill(&self, buffer: &mut [u8], offset: u64, sector_size: u32) {
+        match self {
+            FillPattern::Byte(value) => buffer.fill(*value),
+            FillPattern::Repeat(bytes) if !bytes.is_empty() => {
+                let period = bytes.len() as u64;
+                for (i, b) in buffer.iter_mut().enumerate() {
+                    *b = bytes[((offset + i as u64) % period) as usize];
+                }
+            }
+            FillPattern::LbaStamp => {
+                let sector = u64::from(sector_size.max(8));
+                for (i, b) in buffer.iter_mut().enumerate() {
+                    let position = offset + i as u64;
+                    let lba = position / sector;
+                    *b = lba.to_le_bytes()[((position % sector) % 8) as usize];
+                }
+            }
+            FillPattern::Repeat(_) | FillPattern::Random => {}
+        }
+    }
+}
+
+/// 1パス分の書き込みデータを生成する構造体
+///
+/// ランダムデータのパスではシードから乱数列を生成し、それ以外はパターンから生成します。
+/// 書き込み時と検証時で同じ構造体を使うことで、検証側が同じデータを再生成できます。
+struct PassData<'a> {
+    pattern: &'a FillPattern,
+    random: Option<RandomStream>,
+}
+
+impl<'a> PassData<'a> {
+    /// パターンとシードから生成器を作成する
+    fn new(pattern: &'a FillPattern, seed: Option<&PassSeed>) -> Result<Self, String> {
+        let random = match (pattern, seed) {
+            (FillPattern::Random, Some(seed)) => Some(RandomStream::new(seed)),
+            (FillPattern::Random, None) => {
+                return Err("ランダムデータのパスにシードが指定されていません。".to_string())
+            }
+            _ => None,
+        };
+        Ok(PassData { pattern, random })
+    }
+
+    /// 対象先頭からのオフセット`offset`に書き込むデータで`buffer`を埋める
+    fn fill(&mut self, buffer: &mut [u8], offset: u64, sector_size: u32) {
+        match self.random.as_mut() {
+            Some(stream) => stream.fill_at(buffer, offset),
+            None => self.pattern.fill(buffer, offset, sector_size),
+        }
+    }
+}
+
+/// セクタ境界に揃えられた書き込みバッファ
+///
+/// O_DIRECTでの書き込みではバッファのアドレスと長さがセクタサイズの倍数である必要があります。
+struct AlignedBuffer {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+impl AlignedBuffer {
+    /// `alignment`の境界に揃えた`size`バイトのバッファを確保する
+    fn new(size: usize, alignment: usize) -> Result<Self, String> {
+        let layout = Layout::from_size_align(size, alignment)
+            .map_err(|e| format!("書き込みバッファのレイアウトが不正です: {}", e))?;
+        // SAFETY: sizeは0より大きく、layoutは検証済み
+        let ptr = unsafe { alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            return Err("書き込みバッファの確保に失敗しました。".to_string());
+        }
+        Ok(AlignedBuffer { ptr, layout })
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: ptrはlayout.size()バイトの確保済み領域を指す
+        unsafe { std::slice::from_raw_parts(self.ptr, self.layout.size()) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: ptrはlayout.size()バイトの確保済み領域を指し、排他的に借用されている
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        // SAFETY: ptrは同じlayoutでalloc_zeroedにより確保された
+        unsafe { alloc::dealloc(self.ptr, self.layout) }
+    }
+}
+
+// SAFETY: AlignedBufferは確保した領域を排他的に所有する
+unsafe impl Send for AlignedBuffer {}
+
+/// 上書き対象への位置を指定したI/O
+///
+/// 通常はデバイスまたはファイルを開いた`File`を使用します。
+/// テストではメディアエラーを注入する実装に差し替えます。
+pub trait TargetIo {
+    /// 指定したオフセットにデータを書き込む
+    fn write_at(&mut self, data: &[u8], offset: u64) -> std::io::Result<()>;
+
+    /// 指定したオフセットからデータを読み込む
+    fn read_at(&mut self, buffer: &mut [u8], offset: u64) -> std::io::Result<()>;
+
+    /// 書き込んだデータをデバイスまで反映させる
+    fn sync_data(&mut self) -> std::io::Result<()>;
+
+    /// 書き込んだデータとメタデータをデバイスまで反映させる
+    fn sync_all(&mut self) -> std::io::Result<()>;
+
+    /// ブロックデバイスのバッファキャッシュを破棄する
+    fn discard_cache(&mut self) -> std::io::Result<()>;
+}
+
+impl TargetIo for File {
+    #[cfg(unix)]
+    fn write_at(&mut self, data: &[u8], offset: u64) -> std::io::Result<()> {
+        std::os::unix::fs::FileExt::write_all_at(self, data, offset)
+    }
+
+    #[cfg(not(unix))]
+    fn write_at(&mut self, data: &[u8], offset: u64) -> std::io::Result<()> {
+        self.seek(SeekFrom::Start(offset))?;
+        self.write_all(data)
+    }
+
+    #[cfg(unix)]
+    fn read_at(&mut self, buffer: &mut [u8], offset: u64) -> std::io::Result<()> {
+        std::os::unix::fs::FileExt::read_exact_at(self, buffer, offset)
+    }
+
+    #[cfg(not(unix))]
+    fn read_at(&mut self, buffer: &mut [u8], offset: u64) -> std::io::Result<()> {
+        self.seek(SeekFrom::Start(offset))?;
+        self.read_exact(buffer)
+    }
+
+    fn sync_data(&mut self) -> std::io::Result<()> {
+        File::sync_data(self)
+    }
+
+    fn sync_all(&mut self) -> std::io::Result<()> {
+        File::sync_all(self)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn discard_cache(&mut self) -> std::io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+        // SAFETY: 有効なファイルディスクリプタに対して引数なしのioctlを発行する
+        let ret = unsafe { libc::ioctl(self.as_raw_fd(), BLKFLSBUF) };
+        if ret != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn discard_cache(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 上書き対象のデバイスまたはファイル
+pub struct OverwriteTarget<D = File> {
+    io: D,
+    path: String,
+    size: u64,
+    logical_sector_size: u32,
+    physical_sector_size: u32,
+    is_block_device: bool,
+    bad_sectors: BTreeSet<u64>,
+}
+
+impl OverwriteTarget {
+    /// 上書き対象を書き込み可能な状態で開く
+    ///
+    /// ブロックデバイスの場合はO_DIRECTで開き、サイズとセクタサイズをカーネルから取得します。
+    /// 通常のファイルの場合はファイルサイズを対象とし、512バイトセクタとして扱います。
+    ///
+    /// # 引数
+    ///
+    /// * `path` - 対象のパス（例: /dev/sda、ディスクイメージファイル）
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let display = path.display().to_string();
+        let metadata = std::fs::metadata(path)
+            .map_err(|e| format!("{}の情報を取得できませんでした: {}", display, e))?;
+        let is_block_device = is_block_device(&metadata);
+
+        let mut options = OpenOptions::new();
+        options.read(true).write(true);
+        #[cfg(target_os = "linux")]
+        if is_block_device {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.custom_flags(libc::O_DIRECT);
+        }
+
+        let mut file = options
+            .open(path)
+            .map_err(|e| format!("{}を開けませんでした: {}", display, e))?;
+
+        let (size, logical_sector_size, physical_sector_size) = if is_block_device {
+            block_device_geometry(&mut file)
+                .map_err(|e| format!("{}のジオメトリを取得できませんでした: {}", display, e))?
+        } else {
+            (metadata.len(), DEFAULT_SECTOR_SIZE, DEFAULT_SECTOR_SIZE)
+        };
+
+        if size == 0 {
+            return Err(format!("{}のサイズが0バイトです。", display));
+        }
+
+        Ok(OverwriteTarget {
+            io: file,
+            path: display,
+            size,
+            logical_sector_size,
+            physical_sector_size,
+            is_block_device,
+            bad_sectors: BTreeSet::new(),
+        })
+    }
+}
+
+impl<D: TargetIo> OverwriteTarget<D> {
+    /// 対象の総バイト数
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// 論理セクタサイズ（バイト）
+    pub fn logical_sector_size(&self) -> u32 {
+        self.logical_sector_size
+    }
+
+    /// 物理セクタサイズ（バイト）
+    pub fn physical_sector_size(&self) -> u32 {
+        self.physical_sector_size
+    }
+
+    /// 対象がブロックデバイスかどうか
+    pub fn is_block_device(&self) -> bool {
+        self.is_block_device
+    }
+
+    /// これまでに書き込みまたは読み込みに失敗した不良セクタ数
+    pub fn bad_sector_count(&self) -> u64 {
+        self.bad_sectors.len() as u64
+    }
+
+    /// 不良セクタを連続するLBA範囲にまとめて返す
+    pub fn bad_sector_ranges(&self) -> Vec<LbaRange> {
+        LbaRangeCollector::from_sorted(self.bad_sectors.iter().copied())
+            .ranges()
+            .to_vec()
+    }
+
+    /// チェックポイントに記録するため、不良セクタを件数の上限なしで連続するLBA範囲にまとめて返す
+    pub fn bad_sector_spans(&self) -> Vec<LbaRange> {
+        LbaRangeCollector::uncapped()
+            .extend_sorted(self.bad_sectors.iter().copied())
+            .into_ranges()
+    }
+
+    /// チェックポイントに記録された不良セクタを引き継ぐ
+    ///
+    /// 中断した位置より前の不良セクタは再開後に書き込まれないため、
+    /// 記録を引き継がないと再開した消去の結果から漏れてしまいます。
+    ///
+    /// # 引数
+    ///
+    /// * `ranges` - チェックポイントに記録された不良セクタのLBA範囲
+    pub fn restore_bad_sectors(&mut self, ranges: &[LbaRange]) -> Result<(), String> {
+        let sector = u64::from(self.logical_sector_size);
+        let sectors = self.size.div_ceil(sector);
+        for range in ranges {
+            if range.start > range.end
+                || range.end >= sectors
+                || range.end - range.start >= MAX_BAD_SECTORS as u64
+            {
+                return Err(format!(
+                    "チェックポイントに記録された不良セクタのLBA範囲{}-{}が不正です。",
+                    range.start, range.end
+                ));
+            }
+            self.mark_bad(range.start * sector, ((range.end - range.start + 1) * sector) as usize)?;
+        }
+        Ok(())
+    }
+
+    /// 1回の書き込みサイズを物理セクタサイズの倍数に揃えて返す
+    fn chunk_size(&self) -> usize {
+        let sector = self.physical_sector_size.max(self.logical_sector_size) as usize;
+        (CHUNK_SIZE / sector).max(1) * sector
+    }
+
+    /// 1パス分の上書きを行う
+    ///
+    /// `start`から末尾まで、最後の端数ブロックを含むすべてのバイトを書き込み、
+    /// パス終了時にキャッシュをフラッシュします。
+    /// 一定間隔ごとに書き込み済みのデータをデバイスまで反映させ、チェックポイントを保存します。
+    /// 書き込めないセクタは不良セクタとして記録し、残りの領域の書き込みを続けます。
+    ///
+    /// # 引数
+    ///
+    /// * `pattern` - 書き込むデータのパターン
+    /// * `seed` - ランダムデータのパスで使用する乱数のシード
+    /// * `start` - 書き込みを開始するオフセット（再開時以外は0）
+    /// * `checkpoint` - チェックポイントの保存先
+    /// * `control` - キャンセル要求を受け取る制御オブジェクト
+    /// * `reporter` - 進捗の通知先
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<u64, String>` - 成功時は書き込んだバイト数、失敗時はエラーメッセージを含む`Err`
+    pub fn write_pass(
+        &mut self,
+        pattern: &FillPattern,
+        seed: Option<&PassSeed>,
+        start: u64,
+        checkpoint: &mut CheckpointWriter,
+        control: &DiskControl,
+        reporter: &mut ProgressReporter,
+    ) -> Result<u64, String> {
+        let chunk_size = self.chunk_size();
+        let alignment = self.physical_sector_size.max(self.logical_sector_size) as usize;
+        let mut buffer = AlignedBuffer::new(chunk_size, alignment)?;
+        let mut data = PassData::new(pattern, seed)?;
+        if let FillPattern::Byte(_) = pattern {
+            data.fill(&mut buffer, 0, self.logical_sector_size);
+        }
+
+        let mut written: u64 = start;
+        while written < self.size {
+            control.check()?;
+
+            let length = (self.size - written).min(chunk_size as u64) as usize;
+            if self.is_block_device && !length.is_multiple_of(self.logical_sector_size as usize) {
+                return Err(format!(
+                    "{}の末尾{}バイトが論理セクタサイズ{}の倍数ではありません。",
+                    self.path, length, self.logical_sector_size
+                ));
+            }
+
+            if !matches!(pattern, FillPattern::Byte(_)) {
+                data.fill(&mut buffer[..length], written, self.logical_sector_size);
+            }
+
+            self.write_with_retry(&buffer[..length], written)?;
+
+            written += length as u64;
+            reporter.update(written);
+
+            if checkpoint.is_due() {
+                self.io.sync_data().map_err(|e| {
+                    format!("{}のキャッシュのフラッシュに失敗しました: {}", self.path, e)
+                })?;
+                checkpoint.record_bad_sectors(self.bad_sector_spans());
+                checkpoint.advance(written);
+            }
+        }
+
+        self.flush()?;
+        Ok(written - start)
+    }
+
+    /// 書き込んだパターンを読み戻して検証する
+    ///
+    /// 対象をチャンク単位に区切り、検証範囲に含まれるチャンクを読み戻して
+    /// 論理セクタごとに期待したパターンと比較します。
+    /// ランダムデータは書き込み時と同じシードから乱数列を再生成して比較します。
+    /// 読み込めないセクタは不良セクタとして記録し、不一致には含めません。
+    ///
+    /// # 引数
+    ///
+    /// * `pass` - 検証するパス番号（1始まり）
+    /// * `pattern` - 書き込んだデータのパターン
+    /// * `seed` - ランダムデータのパスで書き込み時に使用したシード
+    /// * `level` - 検証範囲
+    /// * `control` - キャンセル要求を受け取る制御オブジェクト
+    /// * `reporter` - 進捗の通知先
+    ///
+    /// # 戻り値
+    ///
+    /// * `Result<VerificationReport, String>` - 成功時は検証結果、読み込みに失敗した場合はエラーメッセージを含む`Err`
+    pub fn verify_pass(
+        &mut self,
+        pass: u32,
+        pattern: &FillPattern,
+        seed: Option<&PassSeed>,
+        level: VerificationLevel,
+        control: &DiskControl,
+        reporter: &mut ProgressReporter,
+    ) -> Result<VerificationReport, String> {
+        let mut data = PassData::new(pattern, seed)?;
+        let chunk_size = self.chunk_size();
+        let alignment = self.physical_sector_size.max(self.logical_sector_size) as usize;
+        let sector = self.logical_sector_size as usize;
+        let mut buffer = AlignedBuffer::new(chunk_size, alignment)?;
+        let mut expected = vec![0u8; chunk_size];
+        let chunks = self.size.div_ceil(chunk_size as u64);
+        let mut mismatches = LbaRangeCollector::default();
+        let mut checked: u64 = 0;
+
+        reporter.start_verification(pass);
+
+        for index in 0..chunks {
+            if !level.includes(index, chunks) {
+                continue;
+            }
+            control.check()?;
+
+            let offset = index * chunk_size as u64;
+            let length = (self.size - offset).min(chunk_size as u64) as usize;
+            self.read_with_retry(&mut buffer[..length], offset)?;
+            data.fill(&mut expected[..length], offset, self.logical_sector_size);
+
+            // 不良セクタは別途報告するため、不一致には含めない
+            for (i, (actual, wanted)) in buffer[..length]
+                .chunks(sector)
+                .zip(expected[..length].chunks(sector))
+                .enumerate()
+            {
+                let lba = offset / sector as u64 + i as u64;
+                if actual != wanted && !self.bad_sectors.contains(&lba) {
+                    mismatches.add(lba);
+                }
+            }
+
+            checked += length as u64;
+            reporter.update(offset + length as u64);
+        }
+
+        Ok(mismatches.into_report(pass, pattern.description(), level, checked))
+    }
+
+    /// 指定したオフセットにデータを書き込み、失敗した場合は小さいI/Oサイズで再試行する
+    ///
+    /// メディアエラー以外（デバイスの切断など）の失敗はそのままエラーとして返します。
+    fn write_with_retry(&mut self, data: &[u8], offset: u64) -> Result<(), String> {
+        match self.write_at(data, offset) {
+            Ok(()) => Ok(()),
+            Err(e) if is_media_error(&e) => {
+                let sizes = self.retry_sizes(data.len());
+                self.write_pieces(data, offset, &sizes)
+            }
+            Err(e) => Err(format!(
+                "{}のオフセット{}への書き込みに失敗しました: {}",
+                self.path, offset, e
+            )),
+        }
+    }
+
+    /// データを`sizes`の先頭のサイズに分割して書き込み、失敗した部分を次のサイズで再試行する
+    ///
+    /// 最小のサイズでも書き込めなかった部分は不良セクタとして記録します。
+    fn write_pieces(&mut self, data: &[u8], offset: u64, sizes: &[usize]) -> Result<(), String> {
+        let Some((&size, smaller)) = sizes.split_first() else {
+            return self.mark_bad(offset, data.len());
+        };
+
+        for (i, piece) in data.chunks(size).enumerate() {
+            let piece_offset = offset + (i * size) as u64;
+            match self.write_at(piece, piece_offset) {
+                Ok(()) => {}
+                Err(e) if is_media_error(&e) => self.write_pieces(piece, piece_offset, smaller)?,
+                Err(e) => {
+                    return Err(format!(
+                        "{}のオフセット{}への書き込みに失敗しました: {}",
+                        self.path, piece_offset, e
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 指定したオフセットからデータを読み込み、失敗した場合はセクタごとに再試行する
+    ///
+    /// 読み込めなかったセクタは不良セクタとして記録し、バッファの該当部分はゼロで埋めます。
+    fn read_with_retry(&mut self, buffer: &mut [u8], offset: u64) -> Result<(), String> {
+        let error = match self.read_at(buffer, offset) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        if !is_media_error(&error) {
+            return Err(format!(
+                "{}のオフセット{}からの読み込みに失敗しました: {}",
+                self.path, offset, error
+            ));
+        }
+
+        let sector = self.logical_sector_size as usize;
+        for (i, piece) in buffer.chunks_mut(sector).enumerate() {
+            let piece_offset = offset + (i * sector) as u64;
+            match self.read_at(piece, piece_offset) {
+                Ok(()) => {}
+                Err(e) if is_media_error(&e) => {
+                    piece.fill(0);
+                    self.mark_bad(piece_offset, piece.len())?;
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "{}のオフセット{}からの読み込みに失敗しました: {}",
+                        self.path, piece_offset, e
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 失敗した`length`バイトの領域を再試行する際のI/Oサイズを大きい順に返す
+    fn retry_sizes(&self, length: usize) -> Vec<usize> {
+        let mut sizes: Vec<usize> = [
+            RETRY_IO_SIZE,
+            self.physical_sector_size as usize,
+            self.logical_sector_size as usize,
+        ]
+        .into_iter()
+        .filter(|&size| size < length && size.is_multiple_of(self.logical_sector_size as usize))
+        .collect();
+        sizes.dedup();
+        sizes
+    }
+
+    /// 指定した領域のセクタを不良セクタとして記録する
+    fn mark_bad(&mut self, offset: u64, length: usize) -> Result<(), String> {
+        let sector = u64::from(self.logical_sector_size);
+        let first = offset / sector;
+        let last = (offset + length as u64 - 1) / sector;
+        self.bad_sectors.extend(first..=last);
+
+        if self.bad_sectors.len() > MAX_BAD_SECTORS {
+            return Err(format!(
+                "{}で書き込みまたは読み込みのできないセクタが{}を超えました。ディスクが切断されたか故障している可能性があります。",
+                self.path, MAX_BAD_SECTORS
+            ));
+        }
+        Ok(())
+    }
+
+    /// 指定したオフセットにデータを書き込む
+    fn write_at(&mut self, data: &[u8], offset: u64) -> std::io::Result<()> {
+        self.io.write_at(data, offset)
+    }
+
+    /// 指定したオフセットからデータを読み込む
+    fn read_at(&mut self, buffer: &mut [u8], offset: u64) -> std::io::Result<()> {
+        self.io.read_at(buffer, offset)
+    }
+
+    /// 書き込んだデータをデバイスまで確実に反映させる
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.io
+            .sync_all()
+            .map_err(|e| format!("{}のキャッシュのフラッシュに失敗しました: {}", self.path, e))?;
+
+        if self.is_block_device {
+            self.io.discard_cache().map_err(|e| {
+                format!("{}のバッファキャッシュの破棄に失敗しました: {}", self.path, e)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// 上書き処理全体の結果
+pub struct OverwriteReport {
+    /// 対象の総バイト数
+    pub size: u64,
+    /// 各パスで書き込んだバイト数
+    pub bytes_per_pass: Vec<u64>,
+    /// 読み戻し検証の結果
+    pub verifications: Vec<VerificationReport>,
+    /// 書き込みまたは読み込みのできなかった不良セクタ数
+    pub bad_sector_count: u64,
+    /// 不良セクタのLBA範囲
+    pub bad_sectors: Vec<LbaRange>,
+}
+
+/// 指定した上書き消去方式で対象を上書きする関数
+///
+/// 最終パスと検証指定のあるパスは書き込み後に読み戻して検証し、
+/// 残存データが見つかった場合は不一致のLBA範囲を含むエラーを返します。
+/// 実行中はチェックポイントを保存し、正常に完了した場合はチェックポイントを削除します。
+/// 不良セクタは各パスの終了時にログと進捗の通知先へ報告され、結果にも含まれます。
+/// 不良セクタはチェックポイントにも記録され、再開した場合は中断前の不良セクタを引き継ぎます。
+///
+/// # 引数
+///
+/// * `path` - 対象のパス
+/// * `scheme` - 上書き消去方式
+/// * `verification` - 読み戻し検証の範囲
+/// * `resume` - 中断した消去を再開する場合はそのチェックポイント
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
+/// # 戻り値
+///
+/// * `Result<OverwriteReport, EraseError>` - 成功時は書き込み結果、失敗時は書き込みを開始したかどうかとエラーメッセージを含む`Err`
+pub fn overwrite(
+    path: &Path,
+    scheme: &OverwriteScheme,
+    verification: VerificationLevel,
+    resume: Option<&Checkpoint>,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<OverwriteReport, EraseError> {
+    verification.validate().map_err(EraseError::NotStarted)?;
+    let mut target = OverwriteTarget::open(path).map_err(EraseError::NotStarted)?;
+    let total = scheme.passes.len();
+    reporter.set_method(&scheme.name, total as u32, target.size());
+
+    let identity = DiskIdentity::read(path).map_err(EraseError::NotStarted)?;
+    let device = path.display().to_string();
+    let mut checkpoint = CheckpointWriter::new(&device, identity.clone(), scheme, verification);
+
+    // 再開する場合は同じ物理ディスクと同じ方式であることを確認してから、中断した位置を復元する
+    let (first_pass, resume_phase, resume_offset, mut resume_seed) = match resume {
+        Some(saved) => {
+            saved
+                .identity
+                .confirm(&identity, !target.is_block_device())
+                .map_err(EraseError::NotStarted)?;
+            saved.matches_scheme(scheme).map_err(EraseError::NotStarted)?;
+            target
+                .restore_bad_sectors(&saved.bad_sectors)
+                .map_err(EraseError::NotStarted)?;
+            log_message(
+                &format!("{}の上書き再開", device),
+                "進行中",
+                &format!(
+                    "パス {}/{} のオフセット{}から再開します（{}）。",
+                    saved.pass + 1,
+                    total,
+                    saved.offset,
+                    match saved.phase {
+                        CheckpointPhase::Write => "書き込み",
+                        CheckpointPhase::Verify => "検証",
+                    }
+                ),
+            );
+            (saved.pass, saved.phase, saved.offset, saved.seed().map_err(EraseError::NotStarted)?)
+        }
+        None => (0, CheckpointPhase::Write, 0, None),
+    };
+
+    log_message(
+        &format!("{}の上書き開始", device),
+        "進行中",
+        &format!(
+            "方式: {}、サイズ: {}バイト、論理セクタ: {}バイト、物理セクタ: {}バイト",
+            scheme.name,
+            target.size(),
+            target.logical_sector_size(),
+            target.physical_sector_size()
+        ),
+    );
+
+    let mut bytes_per_pass = Vec::with_capacity(total);
+    let mut verifications = Vec::new();
+    // 書き込みを開始した後の失敗では、対象は部分的に上書きされている
+    (|| {
+        // 再開した場合は中断前に見つかった不良セクタを最初に報告する
+        let mut reported_bad_sectors = 0;
+        report_bad_sectors(&target, &mut reported_bad_sectors, &device, reporter);
+        for (i, pass) in scheme.passes.iter().enumerate().skip(first_pass) {
+            control.check()?;
+            let resuming = resume.is_some() && i == first_pass;
+            let (phase, start) = if resuming {
+                (resume_phase, resume_offset)
+            } else {
+                (CheckpointPhase::Write, 0)
+            };
+
+            // ランダムデータのシードはこのパスの検証が終わるまでのみ保持し、ループの終わりで破棄する
+            let seed = match pass.pattern {
+                FillPattern::Random if resuming => Some(resume_seed.take().ok_or_else(|| {
+                    "チェックポイントに乱数のシードが記録されていません。".to_string()
+                })?),
+                FillPattern::Random => Some(PassSeed::generate()?),
+                _ => None,
+            };
+
+            reporter.start_pass(i as u32 + 1);
+
+            if phase == CheckpointPhase::Write {
+                log_message(
+                    &format!(
+                        "パス {}/{}: {}に{}を書き込み中",
+                        i + 1,
+                        total,
+                        device,
+                        pass.pattern.description()
+                    ),
+                    "進行中",
+                    "",
+                );
+                checkpoint.record_bad_sectors(target.bad_sector_spans());
+                checkpoint.begin(i, CheckpointPhase::Write, start, seed.as_ref());
+
+                let written = target
+                    .write_pass(&pass.pattern, seed.as_ref(), start, &mut checkpoint, control, reporter)
+                    .map_err(|e| format!("パス {}/{} の実行に失敗しました: {}", i + 1, total, e))?;
+
+                log_message(
+                    &format!("パス {}/{} 完了", i + 1, total),
+                    "成功",
+                    &format!("{}バイトを書き込みました。", written),
+                );
+                bytes_per_pass.push(written);
+                report_bad_sectors(&target, &mut reported_bad_sectors, &device, reporter);
+            }
+
+            if !pass.verify && i + 1 < total {
+                continue;
+            }
+            checkpoint.record_bad_sectors(target.bad_sector_spans());
+            checkpoint.begin(i, CheckpointPhase::Verify, target.size(), seed.as_ref());
+            let report = target
+                .verify_pass(
+                    i as u32 + 1,
+                    &pass.pattern,
+                    seed.as_ref(),
+                    verification,
+                    control,
+                    reporter,
+                )
+                .map_err(|e| format!("パス {}/{} の検証に失敗しました: {}", i + 1, total, e))?;
+            reporter.record_verification(&report);
+            report_bad_sectors(&target, &mut reported_bad_sectors, &device, reporter);
+
+            if !report.is_clean() {
+                log_message(&format!("パス {}/{} の検証", i + 1, total), "失敗", &report.summary());
+                // 再開しても同じ結果になるため、チェックポイントは残さない
+                discard_checkpoint(&device);
+                return Err(format!("残存データが見つかりました: {}", report.summary()));
+            }
+            log_message(&format!("パス {}/{} の検証", i + 1, total), "成功", &report.summary());
+            verifications.push(report);
+        }
+        Ok(())
+    })()
+    .map_err(EraseError::Started)?;
+
+    discard_checkpoint(&device);
+
+    Ok(OverwriteReport {
+        size: target.size(),
+        bytes_per_pass,
+        verifications,
+        bad_sector_count: target.bad_sector_count(),
+        bad_sectors: target.bad_sector_ranges(),
+    })
+}
+
+/// 前回の報告以降に増えた不良セクタをログと進捗の通知先へ報告する関数
+fn report_bad_sectors<D: TargetIo>(
+    target: &OverwriteTarget<D>,
+    reported: &mut u64,
+    device: &str,
+    reporter: &mut ProgressReporter,
+) {
+    let count = target.bad_sector_count();
+    if count == *reported {
+        return;
+    }
+    *reported = count;
+
+    let ranges = target.bad_sector_ranges();
+    log_message(
+        &format!("{}の不良セクタ", device),
+        "警告",
+        &format!(
+            "書き込みまたは読み込みのできないセクタが{}個あります。物理破壊が必要です。LBA範囲: {}",
+            count,
+            ranges
+                .iter()
+                .map(|r| format!("{}-{}", r.start, r.end))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    );
+    reporter.record_bad_sectors(&ranges, count);
+}
+
+/// 上書きの終了後にチェックポイントを削除する関数
+fn discard_checkpoint(device: &str) {
+    if let Err(e) = checkpoint::remove(device) {
+        log_message(
+            &format!("{}のチェックポイント削除", device),
+            "警告",
+            &format!("チェックポイントを削除できませんでした: {}", e),
+        );
+    }
+}
+
+/// I/Oエラーがメディアエラー（不良セクタ）によるものかどうかを判定する関数
+#[cfg(unix)]
+fn is_media_error(error: &std::io::Error) -> bool {
+    error.raw_os_error() == Some(libc::EIO)
+}
+
+/// I/Oエラーがメディアエラー（不良セクタ）によるものかどうかを判定する関数
+#[cfg(not(unix))]
+fn is_media_error(_error: &std::io::Error) -> bool {
+    false
+}
+
+/// メタデータがブロックデバイスを示すかどうかを判定する関数
+#[cfg(unix)]
+fn is_block_device(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    metadata.file_type().is_block_device()
+}
+
+/// メタデータがブロックデバイスを示すかどうかを判定する関数
+#[cfg(not(unix))]
+fn is_block_device(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// ブロックデバイスのサイズを取得するioctl
+#[cfg(target_os = "linux")]
+const BLKGETSIZE64: libc::c_ulong = 0x8008_1272;
+/// 論理セクタサイズを取得するioctl
+#[cfg(target_os = "linux")]
+const BLKSSZGET: libc::c_ulong = 0x1268;
+/// 物理セクタサイズを取得するioctl
+#[cfg(target_os = "linux")]
+const BLKPBSZGET: libc::c_ulong = 0x127b;
+/// バッファキャッシュを破棄するioctl
+#[cfg(target_os = "linux")]
+const BLKFLSBUF: libc::c_ulong = 0x1261;
+
+/// ブロックデバイスのサイズと論理/物理セクタサイズを取得する関数
+#[cfg(target_os = "linux")]
+fn block_device_geometry(file: &mut File) -> std::io::Result<(u64, u32, u32)> {
+    use std::os::unix::io::AsRawFd;
+
+    let fd = file.as_raw_fd();
+    let mut size: u64 = 0;
+    let mut logical: libc::c_int = 0;
+    let mut physical: libc::c_uint = 0;
+
+    // SAFETY: 各ioctlには対応する型の有効なポインタを渡している
+    unsafe {
+        if libc::ioctl(fd, BLKGETSIZE64, &mut size) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        if libc::ioctl(fd, BLKSSZGET, &mut logical) != 0 {
+            logical = DEFAULT_SECTOR_SIZE as libc::c_int;
+        }
+        if libc::ioctl(fd, BLKPBSZGET, &mut physical) != 0 {
+            physical = logical as libc::c_uint;
+        }
+    }
+
+    Ok((size, logical.max(1) as u32, physical.max(1)))
+}
+
+/// ブロックデバイスのサイズと論理/物理セクタサイズを取得する関数
+#[cfg(not(target_os = "linux"))]
+fn block_device_geometry(file: &mut File) -> std::io::Result<(u64, u32, u32)> {
+    let size = file.seek(SeekFrom::End(0))?;
+    Ok((size, DEFAULT_SECTOR_SIZE, DEFAULT_SECTOR_SIZE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancellation::CancelRegistry;
+    use crate::erase_standards::PassSpec;
+    use std::io::Write;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    /// 指定した内容のディスクイメージを作成する
+    fn image(content: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    /// 対象のパスのキャンセル制御と進捗の通知先を作成する
+    fn job(path: &Path) -> (Arc<DiskControl>, ProgressReporter) {
+        let device = path.display().to_string();
+        let control = CancelRegistry::default()
+            .register(std::slice::from_ref(&device))
+            .disk(&device)
+            .unwrap();
+        (control, ProgressReporter::new(None, "test", &device))
+    }
+
+    /// テスト用の上書き消去方式
+    fn scheme(id: &str, passes: Vec<PassSpec>) -> OverwriteScheme {
+        OverwriteScheme {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            passes,
+        }
+    }
+
+    /// バッファサイズの倍数でも論理セクタサイズの倍数でもないイメージのサイズ
+    const IMAGE_SIZE: usize = 2 * CHUNK_SIZE + 3 * 512 + 100;
+
+    /// 各論理セクタをそのLBAで埋めた内容
+    fn lba_stamped(size: usize) -> Vec<u8> {
+        (0..size)
+            .map(|i| ((i / 512) as u64).to_le_bytes()[i % 512 % 8])
+            .collect()
+    }
+
+    #[test]
+    fn writes_every_byte_of_each_pattern_including_tail() {
+        let seed_hex = "0123456789abcdef".repeat(4);
+        let cases = vec![
+            (FillPattern::Byte(0xFF), vec![0xFF; IMAGE_SIZE]),
+            (
+                FillPattern::Repeat(vec![0x92, 0x49, 0x24]),
+                [0x92, 0x49, 0x24].iter().copied().cycle().take(IMAGE_SIZE).collect(),
+            ),
+            (FillPattern::LbaStamp, lba_stamped(IMAGE_SIZE)),
+            (FillPattern::Random, {
+                let mut expected = vec![0u8; IMAGE_SIZE];
+                RandomStream::new(&PassSeed::from_hex(&seed_hex).unwrap()).fill_at(&mut expected, 0);
+                expected
+            }),
+        ];
+
+        for (pattern, expected) in cases {
+            let file = image(&vec![0x5A; IMAGE_SIZE]);
+            let (control, mut reporter) = job(file.path());
+            let seed = PassSeed::from_hex(&seed_hex).unwrap();
+            let scheme = scheme("test-write", vec![PassSpec { pattern: pattern.clone(), verify: false }]);
+            let mut checkpoint = CheckpointWriter::new(
+                &file.path().display().to_string(),
+                DiskIdentity::read(file.path()).unwrap(),
+                &scheme,
+                VerificationLevel::Full,
+            );
+
+            let mut target = OverwriteTarget::open(file.path()).unwrap();
+            let written = target
+                .write_pass(&pattern, Some(&seed), 0, &mut checkpoint, &control, &mut reporter)
+                .unwrap();
+
+            assert_eq!(written, IMAGE_SIZE as u64, "{}", pattern.description());
+            let content = std::fs::read(file.path()).unwrap();
+            assert_eq!(content.len(), IMAGE_SIZE);
+            assert!(content == expected, "{}の書き込み内容が一致しません", pattern.description());
+        }
+    }
+
+    #[test]
+    fn overwrites_sparse_image_with_every_pass() {
+        let file = NamedTempFile::new().unwrap();
+        file.as_file().set_len(IMAGE_SIZE as u64).unwrap();
+        let (control, mut reporter) = job(file.path());
+        let scheme = scheme(
+            "test-sparse",
+            vec![
+                PassSpec { pattern: FillPattern::Byte(0xFF), verify: true },
+                PassSpec { pattern: FillPattern::Random, verify: false },
+                PassSpec { pattern: FillPattern::LbaStamp, verify: true },
+            ],
+        );
+
+        let report = overwrite(file.path(), &scheme, VerificationLevel::Full, None, &control, &mut reporter).unwrap();
+
+        assert_eq!(report.size, IMAGE_SIZE as u64);
+        assert_eq!(report.bytes_per_pass, vec![IMAGE_SIZE as u64; 3]);
+        assert_eq!(report.verifications.len(), 2);
+        assert!(report.verifications.iter().all(|v| v.is_clean() && v.bytes_checked == IMAGE_SIZE as u64));
+        assert_eq!(report.bad_sector_count, 0);
+        assert!(std::fs::read(file.path()).unwrap() == lba_stamped(IMAGE_SIZE));
+        // 正常に完了した場合はチェックポイントを残さない
+        assert!(checkpoint::find(&file.path().display().to_string()).unwrap().is_none());
+    }
+
+    #[test]
+    fn reports_corrupted_block_as_mismatch() {
+        let mut content = vec![0u8; 64 * 512];
+        content[5 * 512 + 100] = 0xAA;
+        let file = image(&content);
+        let (control, mut reporter) = job(file.path());
+
+        let mut target = OverwriteTarget::open(file.path()).unwrap();
+        let report = target
+            .verify_pass(1, &FillPattern::Byte(0), None, VerificationLevel::Full, &control, &mut reporter)
+            .unwrap();
+
+        assert!(!report.is_clean());
+        assert_eq!(report.mismatched_sectors, 1);
+        assert_eq!(report.mismatches, vec![LbaRange { start: 5, end: 5 }]);
+        assert_eq!(report.bytes_checked, content.len() as u64);
+    }
+
+    #[test]
+    fn fails_erase_when_verification_finds_residual_data() {
+        let mut content = vec![0u8; 64 * 512];
+        content[40 * 512..42 * 512].fill(0x5A);
+        let file = image(&content);
+        let (control, mut reporter) = job(file.path());
+        let scheme = scheme("test-verify", vec![PassSpec { pattern: FillPattern::Byte(0), verify: true }]);
+
+        // 書き込みを終えて検証中に中断した状態から再開し、検証のみを実行する
+        let resume = Checkpoint {
+            device: file.path().display().to_string(),
+            identity: DiskIdentity::read(file.path()).unwrap(),
+            scheme_id: scheme.id.clone(),
+            scheme_name: scheme.name.clone(),
+            passes: vec![FillPattern::Byte(0).description()],
+            verification: VerificationLevel::Full,
+            pass: 0,
+            phase: CheckpointPhase::Verify,
+            offset: content.len() as u64,
+            seed: None,
+            bad_sectors: Vec::new(),
+            updated_at: 0,
+        };
+        let error = overwrite(
+            file.path(),
+            &scheme,
+            VerificationLevel::Full,
+            Some(&resume),
+            &control,
+            &mut reporter,
+        )
+        .err()
+        .unwrap();
+
+        assert!(!error.allows_fallback());
+        assert!(error.message().contains("残存データ"), "{}", error.message());
+        assert!(error.message().contains("40-41"), "{}", error.message());
+    }
+
+    #[test]
+    fn resumes_mid_pass_with_same_result_as_uninterrupted_run() {
+        let seed_hex = "fedcba9876543210".repeat(4);
+        let scheme = scheme(
+            "test-resume",
+            vec![
+                PassSpec { pattern: FillPattern::Byte(0xFF), verify: false },
+                PassSpec { pattern: FillPattern::Random, verify: true },
+            ],
+        );
+
+        // 中断しなかった場合: 同じシードで2パス目を最初から最後まで書き込む
+        let uninterrupted = image(&vec![0xFF; IMAGE_SIZE]);
+        {
+            let (control, mut reporter) = job(uninterrupted.path());
+            let mut checkpoint = CheckpointWriter::new(
+                &uninterrupted.path().display().to_string(),
+                DiskIdentity::read(uninterrupted.path()).unwrap(),
+                &scheme,
+                VerificationLevel::Full,
+            );
+            let seed = PassSeed::from_hex(&seed_hex).unwrap();
+            OverwriteTarget::open(uninterrupted.path())
+                .unwrap()
+                .write_pass(&FillPattern::Random, Some(&seed), 0, &mut checkpoint, &control, &mut reporter)
+                .unwrap();
+        }
+        let expected = std::fs::read(uninterrupted.path()).unwrap();
+
+        // 2パス目の途中で中断した状態: 中断位置より前は乱数列、後ろは1パス目の内容が残っている
+        let offset = CHUNK_SIZE + 3 * 512;
+        let mut content = vec![0xFF; IMAGE_SIZE];
+        content[..offset].copy_from_slice(&expected[..offset]);
+        let file = image(&content);
+        let (control, mut reporter) = job(file.path());
+        let resume = Checkpoint {
+            device: file.path().display().to_string(),
+            identity: DiskIdentity::read(file.path()).unwrap(),
+            scheme_id: scheme.id.clone(),
+            scheme_name: scheme.name.clone(),
+            passes: scheme.passes.iter().map(|p| p.pattern.description()).collect(),
+            verification: VerificationLevel::Full,
+            pass: 1,
+            phase: CheckpointPhase::Write,
+            offset: offset as u64,
+            seed: Some(seed_hex.clone()),
+            bad_sectors: Vec::new(),
+            updated_at: 0,
+        };
+
+        let report = overwrite(
+            file.path(),
+            &scheme,
+            VerificationLevel::Full,
+            Some(&resume),
+            &control,
+            &mut reporter,
+        )
+        .unwrap();
+
+        // 再開したパスのみを実行し、中断した位置から末尾までを書き込む
+        assert_eq!(report.bytes_per_pass, vec![(IMAGE_SIZE - offset) as u64]);
+        assert_eq!(report.verifications.len(), 1);
+        assert!(report.verifications[0].is_clean());
+        assert!(std::fs::read(file.path()).unwrap() == expected);
+        assert!(checkpoint::find(&resume.device).unwrap().is_none());
+    }
+
+    /// 指定したLBAを含む書き込みにメディアエラーを返すテスト用のディスク
+    struct FaultyDisk {
+        data: Vec<u8>,
+        bad_lbas: std::ops::Range<u64>,
+        writes: Vec<(u64, usize)>,
+    }
+
+    impl TargetIo for FaultyDisk {
+        fn write_at(&mut self, data: &[u8], offset: u64) -> std::io::Result<()> {
+            self.writes.push((offset, data.len()));
+            let first = offset / 512;
+            let last = (offset + data.len() as u64 - 1) / 512;
+            if first < self.bad_lbas.end && self.bad_lbas.start <= last {
+                return Err(std::io::Error::from_raw_os_error(libc::EIO));
+            }
+            self.data[offset as usize..offset as usize + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+
+        fn read_at(&mut self, buffer: &mut [u8], offset: u64) -> std::io::Result<()> {
+            buffer.copy_from_slice(&self.data[offset as usize..offset as usize + buffer.len()]);
+            Ok(())
+        }
+
+        fn sync_data(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn sync_all(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+
+        fn discard_cache(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn narrows_failed_write_to_sectors_and_reports_bad_sectors() {
+        let size = 1024 * 1024;
+        let mut target = OverwriteTarget {
+            io: FaultyDisk {
+                data: vec![0u8; size],
+                bad_lbas: 1001..1003,
+                writes: Vec::new(),
+            },
+            path: "faulty".to_string(),
+            size: size as u64,
+            logical_sector_size: 512,
+            physical_sector_size: 4096,
+            is_block_device: false,
+            bad_sectors: BTreeSet::new(),
+        };
+        let device = "faulty".to_string();
+        let control = CancelRegistry::default()
+            .register(std::slice::from_ref(&device))
+            .disk(&device)
+            .unwrap();
+        let mut reporter = ProgressReporter::new(None, "test", &device);
+        let scheme = scheme("test-faulty", vec![PassSpec { pattern: FillPattern::Byte(0xFF), verify: false }]);
+        let identity = DiskIdentity { serial: None, wwn: None, size: size as u64 };
+        let mut checkpoint = CheckpointWriter::new(&device, identity, &scheme, VerificationLevel::Full);
+
+        let written = target
+            .write_pass(&FillPattern::Byte(0xFF), None, 0, &mut checkpoint, &control, &mut reporter)
+            .unwrap();
+        assert_eq!(written, size as u64);
+
+        // 失敗した領域は64KiB、物理セクタ、論理セクタの順に狭めて再試行する
+        let sizes: Vec<usize> = target.io.writes.iter().map(|&(_, len)| len).collect();
+        assert_eq!(sizes[0], size);
+        assert!(sizes.contains(&RETRY_IO_SIZE) && sizes.contains(&4096));
+        let sector_writes: Vec<u64> = target
+            .io
+            .writes
+            .iter()
+            .filter(|&&(_, len)| len == 512)
+            .map(|&(offset, _)| offset / 512)
+            .collect();
+        assert_eq!(sector_writes, (1000..1008).collect::<Vec<_>>());
+
+        // 書き込めなかったセクタのみが残り、それ以外はすべて上書きされる
+        assert_eq!(target.bad_sector_ranges(), vec![LbaRange { start: 1001, end: 1002 }]);
+        for (lba, sector) in target.io.data.chunks(512).enumerate() {
+            let expected = if (1001..1003).contains(&lba) { 0x00 } else { 0xFF };
+            assert!(sector.iter().all(|&b| b == expected), "LBA {}", lba);
+        }
+
+        let mut reported = 0;
+        report_bad_sectors(&target, &mut reported, &device, &mut reporter);
+        assert_eq!(reported, 2);
+        assert_eq!(reporter.bad_sector_count(), 2);
+        assert_eq!(target.bad_sector_spans(), vec![LbaRange { start: 1001, end: 1002 }]);
+    }
+
+    #[test]
+    fn keeps_bad_sectors_recorded_before_interruption() {
+        let file = image(&vec![0u8; 64 * 512]);
+        let (control, mut reporter) = job(file.path());
+        let scheme = scheme("test-bad-resume", vec![PassSpec { pattern: FillPattern::Byte(0), verify: true }]);
+
+        let resume = Checkpoint {
+            device: file.path().display().to_string(),
+            identity: DiskIdentity::read(file.path()).unwrap(),
+            scheme_id: scheme.id.clone(),
+            scheme_name: scheme.name.clone(),
+            passes: vec![FillPattern::Byte(0).description()],
+            verification: VerificationLevel::Full,
+            pass: 0,
+            phase: CheckpointPhase::Write,
+            offset: 32 * 512,
+            seed: None,
+            bad_sectors: vec![LbaRange { start: 3, end: 4 }, LbaRange { start: 10, end: 10 }],
+            updated_at: 0,
+        };
+        let report = overwrite(
+            file.path(),
+            &scheme,
+            VerificationLevel::Full,
+            Some(&resume),
+            &control,
+            &mut reporter,
+        )
+        .unwrap();
+
+        // 再開後の書き込みが成功しても、中断前の不良セクタは結果に残る
+        assert_eq!(report.bad_sector_count, 3);
+        assert_eq!(
+            report.bad_sectors,
+            vec![LbaRange { start: 3, end: 4 }, LbaRange { start: 10, end: 10 }]
+        );
+        assert_eq!(reporter.bad_sector_count(), 3);
+
+        let mut invalid = resume.clone();
+        invalid.bad_sectors = vec![LbaRange { start: 60, end: 64 }];
+        let error = overwrite(file.path(), &scheme, VerificationLevel::Full, Some(&invalid), &control, &mut reporter)
+            .err()
+            .unwrap();
+        assert!(error.allows_fallback(), "{}", error.message());
+    }
+}
@@ -0,0 +1,297 @@
+/// # 進捗通知モジュール
+///
+/// このモジュールは、消去処理の進捗状況をフロントエンドへ通知する機能を提供します。
+/// ディスクごとに現在のパス、書き込み済みバイト数、総バイト数、スループット、
+/// 残り時間の見積もりを`erase-progress`イベントとして送信します。
+/// 送信した進捗はジョブの状態にも記録され、再接続したUIから照会できます。
+use crate::erase_method::{EraseMethod, SanitizeReport};
+use crate::erase_policy::MethodAttempt;
+use crate::verification::{LbaRange, VerificationReport};
+use crate::AppState;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// 進捗イベントの名前
+pub const PROGRESS_EVENT: &str = "erase-progress";
+
+/// 進捗イベントを送信する最小間隔
+const EMIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 消去処理の状態
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressState {
+    /// 消去処理中
+    Running,
+    /// 消去が完了した
+    Completed,
+    /// 消去に失敗した
+    Failed,
+}
+
+/// フロントエンドへ送信される進捗情報
+#[derive(Serialize, Clone, Debug)]
+pub struct ProgressEvent {
+    /// 消去ジョブのID
+    pub job_id: String,
+    /// 対象のデバイス名（例: /dev/sda）
+    pub device: String,
+    /// 実行中の消去方式
+    pub method: String,
+    /// 現在のパス番号（1始まり）
+    pub current_pass: u32,
+    /// 総パス数
+    pub total_passes: u32,
+    /// 現在のパスで書き込み済みのバイト数
+    pub bytes_written: u64,
+    /// 1パスあたりの総バイト数（不明な場合は0）
+    pub total_bytes: u64,
+    /// 現在のパスのスループット（バイト/秒）
+    pub throughput: u64,
+    /// 全パス完了までの残り時間の見積もり（秒）
+    pub eta_seconds: Option<u64>,
+    /// 消去処理の状態
+    pub state: ProgressState,
+    /// 補足メッセージ（進捗率を取得できない消去方式など）
+    pub message: Option<String>,
+}
+
+/// ディスク1台分の進捗を集計し、イベントとして送信する構造体
+pub struct ProgressReporter {
+    app_handle: Option<AppHandle>,
+    job_id: String,
+    device: String,
+    method: String,
+    total_passes: u32,
+    total_bytes: u64,
+    current_pass: u32,
+    bytes_written: u64,
+    pass_started: Instant,
+    last_emit: Option<Instant>,
+    phase: Option<String>,
+    bad_sector_count: u64,
+}
+
+impl ProgressReporter {
+    /// 新しい進捗レポーターを作成する
+    ///
+    /// # 引数
+    ///
+    /// * `app_handle` - イベント送信に使用するアプリケーションハンドル（`None`の場合は送信しない）
+    /// * `job_id` - 消去ジョブのID
+    /// * `device` - 対象のデバイス名
+    pub fn new(app_handle: Option<AppHandle>, job_id: &str, device: &str) -> Self {
+        ProgressReporter {
+            app_handle,
+            job_id: job_id.to_string(),
+            device: device.to_string(),
+            method: String::new(),
+            total_passes: 1,
+            total_bytes: 0,
+            current_pass: 0,
+            bytes_written: 0,
+            pass_started: Instant::now(),
+            last_emit: None,
+            phase: None,
+            bad_sector_count: 0,
+        }
+    }
+
+    /// 消去方式とパス構成を設定する
+    ///
+    /// # 引数
+    ///
+    /// * `method` - 消去方式の名前
+    /// * `total_passes` - 総パス数
+    /// * `total_bytes` - 1パスあたりの総バイト数（不明な場合は0）
+    pub fn set_method(&mut self, method: &str, total_passes: u32, total_bytes: u64) {
+        self.method = method.to_string();
+        self.total_passes = total_passes.max(1);
+        self.total_bytes = total_bytes;
+    }
+
+    /// 新しいパスの開始を通知する
+    ///
+    /// # 引数
+    ///
+    /// * `pass` - 開始するパス番号（1始まり）
+    pub fn start_pass(&mut self, pass: u32) {
+        self.current_pass = pass;
+        self.bytes_written = 0;
+        self.pass_started = Instant::now();
+        self.phase = None;
+        self.emit(ProgressState::Running, None, true);
+    }
+
+    /// パスの読み戻し検証の開始を通知する
+    ///
+    /// 検証中は`bytes_written`が読み戻し済みの位置を表し、メッセージで検証中であることを示します。
+    ///
+    /// # 引数
+    ///
+    /// * `pass` - 検証するパス番号（1始まり）
+    pub fn start_verification(&mut self, pass: u32) {
+        self.current_pass = pass;
+        self.bytes_written = 0;
+        self.pass_started = Instant::now();
+        self.phase = Some(format!("パス{}を読み戻して検証中です。", pass));
+        self.emit(ProgressState::Running, None, true);
+    }
+
+    /// 実行する消去方式をジョブの状態に記録する
+    pub fn record_method(&self, method: EraseMethod) {
+        if let Some(state) = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+        {
+            state.jobs.record_method(&self.job_id, &self.device, method);
+        }
+    }
+
+    /// 消去方式の試行結果をジョブの状態に記録する
+    pub fn record_attempt(&self, attempt: MethodAttempt) {
+        if let Some(state) = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+        {
+            state.jobs.record_attempt(&self.job_id, &self.device, attempt);
+        }
+    }
+
+    /// Sanitizeの完了状態をジョブの状態に記録する
+    pub fn record_sanitize(&self, report: &SanitizeReport) {
+        if let Some(state) = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+        {
+            state.jobs.record_sanitize(&self.job_id, &self.device, report);
+        }
+    }
+
+    /// 読み戻し検証の結果をジョブの状態に記録する
+    pub fn record_verification(&self, report: &VerificationReport) {
+        if let Some(state) = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+        {
+            state.jobs.record_verification(&self.job_id, &self.device, report);
+        }
+    }
+
+    /// 現在のパスで書き込み済みのバイト数を更新する
+    ///
+    /// イベントは一定間隔ごとにのみ送信されます。
+    pub fn update(&mut self, bytes_written: u64) {
+        self.bytes_written = bytes_written;
+        self.emit(ProgressState::Running, None, false);
+    }
+
+    /// 進捗率を取得できない処理の状況をメッセージとして通知する
+    pub fn report_message(&mut self, message: &str) {
+        self.emit(ProgressState::Running, Some(message.to_string()), true);
+    }
+
+    /// 書き込みまたは読み込みのできなかった不良セクタをジョブの状態に記録する
+    ///
+    /// # 引数
+    ///
+    /// * `ranges` - 不良セクタのLBA範囲（これまでに見つかったすべての範囲）
+    /// * `count` - 不良セクタの総数
+    pub fn record_bad_sectors(&mut self, ranges: &[LbaRange], count: u64) {
+        self.bad_sector_count = count;
+        if let Some(state) = self
+            .app_handle
+            .as_ref()
+            .and_then(|app_handle| app_handle.try_state::<AppState>())
+        {
+            state.jobs.record_bad_sectors(&self.job_id, &self.device, ranges, count);
+        }
+    }
+
+    /// これまでに報告された不良セクタ数
+    pub fn bad_sector_count(&self) -> u64 {
+        self.bad_sector_count
+    }
+
+    /// 消去処理の終了を通知する
+    ///
+    /// # 引数
+    ///
+    /// * `result` - 消去処理の結果
+    pub fn finish(&mut self, result: &Result<(), String>) {
+        match result {
+            Ok(_) => {
+                if self.total_bytes > 0 {
+                    self.bytes_written = self.total_bytes;
+                }
+                self.emit(ProgressState::Completed, None, true);
+            }
+            Err(e) => self.emit(ProgressState::Failed, Some(e.clone()), true),
+        }
+    }
+
+    /// 現在のパスのスループット（バイト/秒）を計算する
+    fn throughput(&self) -> u64 {
+        let elapsed = self.pass_started.elapsed().as_secs_f64();
+        if elapsed <= 0.0 {
+            return 0;
+        }
+        (self.bytes_written as f64 / elapsed) as u64
+    }
+
+    /// 全パス完了までの残り時間を見積もる
+    fn eta_seconds(&self, throughput: u64) -> Option<u64> {
+        if self.total_bytes == 0 || throughput == 0 {
+            return None;
+        }
+        let remaining_passes = u64::from(self.total_passes.saturating_sub(self.current_pass));
+        let remaining_bytes = self.total_bytes.saturating_sub(self.bytes_written)
+            + remaining_passes * self.total_bytes;
+        Some(remaining_bytes / throughput)
+    }
+
+    /// 進捗イベントを送信する
+    fn emit(&mut self, state: ProgressState, message: Option<String>, force: bool) {
+        let now = Instant::now();
+        if !force {
+            if let Some(last) = self.last_emit {
+                if now.duration_since(last) < EMIT_INTERVAL {
+                    return;
+                }
+            }
+        }
+        self.last_emit = Some(now);
+
+        let Some(app_handle) = &self.app_handle else {
+            return;
+        };
+
+        let throughput = self.throughput();
+        let event = ProgressEvent {
+            job_id: self.job_id.clone(),
+            device: self.device.clone(),
+            method: self.method.clone(),
+            current_pass: self.current_pass,
+            total_passes: self.total_passes,
+            bytes_written: self.bytes_written,
+            total_bytes: self.total_bytes,
+            throughput,
+            eta_seconds: self.eta_seconds(throughput),
+            state,
+            message: message.or_else(|| self.phase.clone()),
+        };
+
+        if let Some(state) = app_handle.try_state::<AppState>() {
+            state.jobs.record_progress(&self.job_id, &event);
+        }
+
+        if let Err(e) = app_handle.emit(PROGRESS_EVENT, event) {
+            eprintln!("進捗イベントの送信に失敗しました: {}", e);
+        }
+    }
+}
@@ -0,0 +1,648 @@
+/// # 安全保護モジュール
+///
+/// このモジュールは、実行中のシステムが使用しているディスクを誤って消去しないための機能を提供します。
+/// ルートファイルシステム、/boot、有効なスワップ領域、マウント中のパーティションを
+/// LVM、MD RAID、dm-crypt、マルチパスなどの上位デバイスを経由して物理ディスクまで辿り、
+/// それらのディスクを保護対象として`DiskInfo`に記録します。
+/// アクティブなRAIDやボリュームグループの構成ディスクも保護対象になります。
+///
+/// 保護対象のディスクは、設定ファイルの`[safety]`セクションで明示的に許可しない限り消去できません。
+/// 使用中のディスクを確認できない場合は、すべてのディスクを保護対象として扱います。
+///
+/// ```toml
+/// [safety]
+/// allow_protected_disks = ["/dev/sdb"]
+/// ```
+use crate::config;
+use crate::logger::log_message;
+use crate::DiskInfo;
+use std::collections::HashMap;
+
+/// 保護対象のディスクを保護理由とともに取得する関数
+///
+/// # 戻り値
+///
+/// * `Result<HashMap<String, Vec<String>>, String>` - デバイス名（例: /dev/sda）と保護理由のリスト、
+///   使用中のデバイスを確認できない場合は`Err`
+#[cfg(target_os = "linux")]
+pub fn protected_disks() -> Result<HashMap<String, Vec<String>>, String> {
+    protected_disks_under(&linux::SystemRoots::default())
+}
+
+/// 指定した/procと/sysから保護対象のディスクを保護理由とともに取得する関数
+///
+/// # 引数
+///
+/// * `roots` - 読み込む/procと/sysのディレクトリ
+///
+/// # 戻り値
+///
+/// * `Result<HashMap<String, Vec<String>>, String>` - デバイス名（例: /dev/sda）と保護理由のリスト、
+///   使用中のデバイスを確認できない場合は`Err`
+#[cfg(target_os = "linux")]
+fn protected_disks_under(roots: &linux::SystemRoots) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut protected: HashMap<String, Vec<String>> = HashMap::new();
+    let mut add = |disk: &str, reason: &String| {
+        let reasons = protected.entry(format!("/dev/{}", disk)).or_default();
+        if !reasons.contains(reason) {
+            reasons.push(reason.clone());
+        }
+    };
+
+    for (device_id, reason) in linux::devices_in_use(roots)? {
+        for disk in linux::physical_disks_of(roots, &device_id) {
+            add(&disk, &reason);
+        }
+    }
+
+    for (disk, reason) in linux::disks_with_holders(roots) {
+        add(&disk, &reason);
+    }
+
+    Ok(protected)
+}
+
+/// 保護対象のディスクを保護理由とともに取得する関数
+#[cfg(not(target_os = "linux"))]
+pub fn protected_disks() -> Result<HashMap<String, Vec<String>>, String> {
+    Ok(HashMap::new())
+}
+
+/// ディスク情報の一覧に保護状態を設定する関数
+///
+/// 使用中のディスクを確認できない場合は、システムのディスクを誤って消去しないよう、すべてのディスクを保護対象にします。
+pub fn mark_protected(disks: &mut [DiskInfo]) {
+    apply_protection(disks, protected_disks());
+}
+
+/// 保護対象のディスクの確認結果をディスク情報の一覧に設定する関数
+///
+/// # 引数
+///
+/// * `disks` - 保護状態を設定するディスク情報の一覧
+/// * `protected` - 保護対象のディスクと保護理由、確認できなかった場合は`Err`
+fn apply_protection(disks: &mut [DiskInfo], protected: Result<HashMap<String, Vec<String>>, String>) {
+    let protected = match protected {
+        Ok(protected) => protected,
+        Err(e) => {
+            log_message("使用中のディスクの確認", "エラー", &e);
+            let reason = format!("使用中のディスクを確認できないため保護しています: {}", e);
+            for disk in disks.iter_mut() {
+                disk.protected = true;
+                disk.protection_reasons = vec![reason.clone()];
+                for sibling in disk.sibling_namespaces.iter_mut() {
+                    sibling.protected = true;
+                }
+            }
+            return;
+        }
+    };
+    for disk in disks.iter_mut() {
+        if let Some(reasons) = protected.get(&disk.device_name) {
+            disk.protected = true;
+            disk.protection_reasons = reasons.clone();
+        }
+        // NVMe FormatやSanitizeは同じコントローラ上の他のネームスペースにも影響する
+        for sibling in disk.sibling_namespaces.iter_mut() {
+            sibling.protected = protected.contains_key(&sibling.device_name);
+        }
+    }
+}
+
+/// ディスクを消去してよいかを確認する関数
+///
+/// 保護対象のディスクは、設定ファイルの`allow_protected_disks`に含まれる場合のみ消去できます。
+/// 読み取り専用のディスクは消去できません。
+///
+/// # 戻り値
+///
+/// * `Result<(), String>` - 消去できない場合は保護理由を含む`Err`
+pub fn ensure_erasable(disk: &DiskInfo) -> Result<(), String> {
+    if disk.read_only {
+        return Err(format!("{}は読み取り専用のため消去できません。", disk.device_name));
+    }
+    if !disk.protected {
+        return Ok(());
+    }
+
+    let reasons = disk.protection_reasons.join("、");
+    let allowed = config::load()?
+        .safety
+        .allow_protected_disks
+        .contains(&disk.device_name);
+
+    if allowed {
+        log_message(
+            &format!("{}の保護を解除", disk.device_name),
+            "警告",
+            &format!(
+                "設定ファイルで許可されているため、使用中のディスクを消去します: {}",
+                reasons
+            ),
+        );
+        return Ok(());
+    }
+
+    Err(format!(
+        "{}はシステムで使用中のため消去できません（{}）。",
+        disk.device_name, reasons
+    ))
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    use std::path::{Path, PathBuf};
+
+    /// 上位デバイスを辿る深さの上限（循環参照への備え）
+    const MAX_DEPTH: usize = 16;
+
+    /// 使用中のデバイスの確認に読み込む/procと/sysのディレクトリ
+    pub struct SystemRoots {
+        /// procfsのマウント先（通常は/proc）
+        pub proc: PathBuf,
+        /// sysfsのマウント先（通常は/sys）
+        pub sys: PathBuf,
+    }
+
+    impl Default for SystemRoots {
+        fn default() -> Self {
+            SystemRoots {
+                proc: PathBuf::from("/proc"),
+                sys: PathBuf::from("/sys"),
+            }
+        }
+    }
+
+    impl SystemRoots {
+        /// デバイス番号（"major:minor"）に対応するsysfsのパス
+        fn sysfs_path(&self, device_id: &str) -> PathBuf {
+            self.sys.join("dev/block").join(device_id)
+        }
+    }
+
+    /// 使用中のブロックデバイス（"major:minor"）と使用理由を列挙する
+    ///
+    /// マウント情報またはスワップ領域の一覧を読み込めない場合は`Err`を返します。
+    pub fn devices_in_use(roots: &SystemRoots) -> Result<Vec<(String, String)>, String> {
+        let mut devices = Vec::new();
+
+        let mountinfo_path = roots.proc.join("self/mountinfo");
+        let mountinfo = fs::read_to_string(&mountinfo_path)
+            .map_err(|e| format!("{}を読み込めませんでした: {}", mountinfo_path.display(), e))?;
+        for line in mountinfo.lines() {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let Some(separator) = fields.iter().position(|f| *f == "-") else {
+                continue;
+            };
+            if fields.len() < 5 || fields.len() < separator + 3 {
+                continue;
+            }
+            let mount_point = fields[4];
+            let source = fields[separator + 2];
+
+            // btrfsなどはmountinfoのデバイス番号が実デバイスを指さないため、マウント元から解決する
+            let device_id = if roots.sysfs_path(fields[2]).exists() {
+                Some(fields[2].to_string())
+            } else if source.starts_with("/dev/") {
+                fs::metadata(source).ok().map(|m| device_id(m.rdev()))
+            } else {
+                None
+            };
+
+            if let Some(device_id) = device_id {
+                devices.push((device_id, mount_reason(mount_point)));
+            }
+        }
+
+        let swaps_path = roots.proc.join("swaps");
+        let swaps = fs::read_to_string(&swaps_path)
+            .map_err(|e| format!("{}を読み込めませんでした: {}", swaps_path.display(), e))?;
+        for line in swaps.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [name, kind, ..] = fields.as_slice() else {
+                continue;
+            };
+            let Ok(metadata) = fs::metadata(name) else {
+                continue;
+            };
+            // スワップファイルの場合はそのファイルが置かれているデバイスを対象にする
+            let dev = if *kind == "partition" {
+                metadata.rdev()
+            } else {
+                metadata.dev()
+            };
+            devices.push((device_id(dev), format!("スワップ領域として使用中（{}）", name)));
+        }
+
+        Ok(devices)
+    }
+
+    /// マウントポイントから保護理由の説明を作成する
+    fn mount_reason(mount_point: &str) -> String {
+        match mount_point {
+            "/" => "ルートファイルシステム（/）".to_string(),
+            "/boot" | "/boot/efi" | "/efi" => format!("ブートパーティション（{}）", mount_point),
+            _ => format!("{}にマウント中", mount_point),
+        }
+    }
+
+    /// デバイスを構成する物理ディスク名（例: sda、nvme0n1）を列挙する
+    pub fn physical_disks_of(roots: &SystemRoots, device_id: &str) -> BTreeSet<String> {
+        let mut disks = BTreeSet::new();
+        if let Ok(path) = fs::canonicalize(roots.sysfs_path(device_id)) {
+            collect_physical_disks(roots, &path, &mut disks, 0);
+        }
+        disks
+    }
+
+    /// sysfsのブロックデバイスのディレクトリから、下位デバイスを物理ディスクまで辿る
+    ///
+    /// * `slaves`を持つデバイス（LVM、MD RAID、dm-crypt、マルチパス）は構成デバイスを辿る
+    /// * パーティションは親のディスクを辿る
+    /// * ループデバイスはバックアップファイルが置かれているデバイスを辿る
+    fn collect_physical_disks(roots: &SystemRoots, path: &Path, disks: &mut BTreeSet<String>, depth: usize) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        let slaves: Vec<PathBuf> = fs::read_dir(path.join("slaves"))
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| fs::canonicalize(e.path()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !slaves.is_empty() {
+            for slave in slaves {
+                collect_physical_disks(roots, &slave, disks, depth + 1);
+            }
+            return;
+        }
+
+        if path.join("partition").exists() {
+            if let Some(parent) = path.parent() {
+                collect_physical_disks(roots, parent, disks, depth + 1);
+            }
+            return;
+        }
+
+        if let Ok(backing_file) = fs::read_to_string(path.join("loop/backing_file")) {
+            if let Ok(metadata) = fs::metadata(backing_file.trim()) {
+                if let Ok(backing) = fs::canonicalize(roots.sysfs_path(&device_id(metadata.dev()))) {
+                    collect_physical_disks(roots, &backing, disks, depth + 1);
+                }
+            }
+        }
+
+        if let Some(name) = path.file_name() {
+            disks.insert(name.to_string_lossy().to_string());
+        }
+    }
+
+    /// ディスク自身またはそのパーティションが上位デバイスに使用されているディスクを列挙する
+    ///
+    /// アクティブなMD RAIDやLVMのボリュームグループ、dm-cryptの構成ディスクが該当します。
+    pub fn disks_with_holders(roots: &SystemRoots) -> Vec<(String, String)> {
+        let mut result = Vec::new();
+        let Ok(entries) = fs::read_dir(roots.sys.join("block")) else {
+            return result;
+        };
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let disk = entry.file_name().to_string_lossy().to_string();
+            let disk_path = entry.path();
+
+            let mut devices = vec![disk_path.clone()];
+            if let Ok(children) = fs::read_dir(&disk_path) {
+                devices.extend(
+                    children
+                        .filter_map(|e| e.ok())
+                        .map(|e| e.path())
+                        .filter(|p| p.join("partition").exists()),
+                );
+            }
+
+            for device in devices {
+                let Ok(holders) = fs::read_dir(device.join("holders")) else {
+                    continue;
+                };
+                for holder in holders.filter_map(|e| e.ok()) {
+                    let holder_name = holder.file_name().to_string_lossy().to_string();
+                    let device_name = device
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    result.push((
+                        disk.clone(),
+                        format!(
+                            "{}が{}の構成デバイスとして使用中",
+                            device_name,
+                            holder_display_name(roots, &holder_name)
+                        ),
+                    ));
+                }
+            }
+        }
+
+        result
+    }
+
+    /// 上位デバイスの表示名を取得する（device-mapperの場合はLVMやdm-cryptの名前を付加する）
+    fn holder_display_name(roots: &SystemRoots, holder: &str) -> String {
+        match fs::read_to_string(roots.sys.join("block").join(holder).join("dm/name")) {
+            Ok(name) => format!("{}（{}）", holder, name.trim()),
+            Err(_) => holder.to_string(),
+        }
+    }
+
+    /// `dev_t`を"major:minor"形式の文字列に変換する
+    pub fn device_id(dev: u64) -> String {
+        let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0x0000_0fff);
+        let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0x0000_00ff);
+        format!("{}:{}", major, minor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MediaKind, SiblingNamespace, Transport};
+
+    fn disk(device_name: &str) -> DiskInfo {
+        DiskInfo::for_test(device_name, MediaKind::Ssd, Transport::Sata)
+    }
+
+    #[test]
+    fn protects_every_disk_when_devices_in_use_cannot_be_read() {
+        let mut disks = vec![disk("/dev/sda"), disk("/dev/nvme0n1")];
+        disks[1].sibling_namespaces.push(SiblingNamespace {
+            device_name: "/dev/nvme0n2".to_string(),
+            protected: false,
+        });
+
+        apply_protection(&mut disks, Err("/proc/swapsを読み込めませんでした".to_string()));
+
+        for disk in &disks {
+            assert!(disk.protected, "{}が保護されていません", disk.device_name);
+            assert_eq!(disk.protection_reasons.len(), 1);
+            assert!(disk.protection_reasons[0].contains("使用中のディスクを確認できない"));
+        }
+        assert!(disks[1].sibling_namespaces[0].protected);
+    }
+
+    #[test]
+    fn marks_listed_disks_and_siblings_only() {
+        let mut disks = vec![disk("/dev/sda"), disk("/dev/nvme0n1")];
+        disks[1].sibling_namespaces.push(SiblingNamespace {
+            device_name: "/dev/nvme0n2".to_string(),
+            protected: false,
+        });
+        let protected = HashMap::from([(
+            "/dev/nvme0n2".to_string(),
+            vec!["ルートファイルシステム（/）".to_string()],
+        )]);
+
+        apply_protection(&mut disks, Ok(protected));
+
+        assert!(!disks[0].protected);
+        assert!(!disks[1].protected);
+        assert!(disks[1].sibling_namespaces[0].protected);
+    }
+
+    #[cfg(target_os = "linux")]
+    mod linux {
+        use super::super::linux::{device_id, SystemRoots};
+        use super::super::*;
+        use super::disk;
+        use std::fs;
+        use std::os::unix::fs::{symlink, MetadataExt};
+        use std::path::{Path, PathBuf};
+        use tempfile::TempDir;
+
+        /// 一時ディレクトリに作成した/procと/sys
+        struct FakeSystem {
+            dir: TempDir,
+        }
+
+        impl FakeSystem {
+            fn new() -> Self {
+                let system = FakeSystem {
+                    dir: TempDir::new().unwrap(),
+                };
+                system.write("proc/self/mountinfo", "");
+                system.write("proc/swaps", "Filename Type Size Used Priority\n");
+                fs::create_dir_all(system.path("sys/dev/block")).unwrap();
+                fs::create_dir_all(system.path("sys/block")).unwrap();
+                system
+            }
+
+            fn roots(&self) -> SystemRoots {
+                SystemRoots {
+                    proc: self.path("proc"),
+                    sys: self.path("sys"),
+                }
+            }
+
+            fn path(&self, relative: &str) -> PathBuf {
+                self.dir.path().join(relative)
+            }
+
+            fn write(&self, relative: &str, contents: &str) {
+                let path = self.path(relative);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, contents).unwrap();
+            }
+
+            /// /sys/devices以下にブロックデバイスを作成し、/sys/blockと/sys/dev/blockから参照する
+            fn block_device(&self, relative: &str, id: &str) -> PathBuf {
+                let path = self.path(&format!("sys/devices/{}", relative));
+                fs::create_dir_all(&path).unwrap();
+                symlink(&path, self.path(&format!("sys/dev/block/{}", id))).unwrap();
+                let name = path.file_name().unwrap();
+                if !path.join("partition").exists() {
+                    symlink(&path, self.path("sys/block").join(name)).unwrap();
+                }
+                path
+            }
+
+            fn partition(&self, relative: &str, id: &str) -> PathBuf {
+                let path = self.path(&format!("sys/devices/{}", relative));
+                fs::create_dir_all(&path).unwrap();
+                fs::write(path.join("partition"), "1\n").unwrap();
+                self.block_device(relative, id)
+            }
+
+            /// 上位デバイスを作成し、構成デバイスのslavesとholdersを結ぶ
+            fn holder(&self, relative: &str, id: &str, slaves: &[&Path]) -> PathBuf {
+                let path = self.block_device(relative, id);
+                let name = path.file_name().unwrap();
+                fs::create_dir_all(path.join("slaves")).unwrap();
+                for slave in slaves {
+                    symlink(slave, path.join("slaves").join(slave.file_name().unwrap())).unwrap();
+                    fs::create_dir_all(slave.join("holders")).unwrap();
+                    symlink(&path, slave.join("holders").join(name)).unwrap();
+                }
+                path
+            }
+
+            fn mount(&self, id: &str, mount_point: &str, source: &str) {
+                let path = self.path("proc/self/mountinfo");
+                let mut mountinfo = fs::read_to_string(&path).unwrap();
+                mountinfo.push_str(&format!("30 1 {} / {} rw,relatime shared:1 - ext4 {} rw\n", id, mount_point, source));
+                fs::write(path, mountinfo).unwrap();
+            }
+
+            fn swap(&self, name: &str, kind: &str) {
+                let path = self.path("proc/swaps");
+                let mut swaps = fs::read_to_string(&path).unwrap();
+                swaps.push_str(&format!("{} {} 1048572 0 -2\n", name, kind));
+                fs::write(path, swaps).unwrap();
+            }
+        }
+
+        fn protected(system: &FakeSystem) -> HashMap<String, Vec<String>> {
+            protected_disks_under(&system.roots()).unwrap()
+        }
+
+        #[test]
+        fn resolves_partition_to_parent_disk() {
+            let system = FakeSystem::new();
+            system.block_device("block/sda", "8:0");
+            system.partition("block/sda/sda1", "8:1");
+            system.partition("block/sda/sda2", "8:2");
+            system.block_device("block/sdb", "8:16");
+            system.mount("8:1", "/boot/efi", "/dev/sda1");
+            system.mount("8:2", "/", "/dev/sda2");
+
+            let protected = protected(&system);
+            assert_eq!(protected.len(), 1);
+            assert_eq!(
+                protected["/dev/sda"],
+                vec!["ブートパーティション（/boot/efi）".to_string(), "ルートファイルシステム（/）".to_string()]
+            );
+        }
+
+        #[test]
+        fn follows_lvm_and_md_slaves_to_physical_disks() {
+            let system = FakeSystem::new();
+            system.block_device("block/sda", "8:0");
+            let sda2 = system.partition("block/sda/sda2", "8:2");
+            let sdb = system.block_device("block/sdb", "8:16");
+            let sdc = system.block_device("block/sdc", "8:32");
+            let dm = system.holder("virtual/block/dm-0", "250:0", &[&sda2]);
+            fs::create_dir_all(dm.join("dm")).unwrap();
+            fs::write(dm.join("dm/name"), "vg0-root\n").unwrap();
+            system.holder("virtual/block/md0", "9:0", &[&sdb, &sdc]);
+            system.mount("250:0", "/", "/dev/mapper/vg0-root");
+            system.mount("9:0", "/home", "/dev/md0");
+
+            let protected = protected(&system);
+            assert_eq!(protected.len(), 3);
+            assert_eq!(
+                protected["/dev/sda"],
+                vec![
+                    "ルートファイルシステム（/）".to_string(),
+                    "sda2がdm-0（vg0-root）の構成デバイスとして使用中".to_string(),
+                ]
+            );
+            assert_eq!(
+                protected["/dev/sdb"],
+                vec!["/homeにマウント中".to_string(), "sdbがmd0の構成デバイスとして使用中".to_string()]
+            );
+            assert_eq!(
+                protected["/dev/sdc"],
+                vec!["/homeにマウント中".to_string(), "sdcがmd0の構成デバイスとして使用中".to_string()]
+            );
+        }
+
+        #[test]
+        fn protects_active_holders_even_when_not_mounted() {
+            let system = FakeSystem::new();
+            let sdb = system.block_device("block/sdb", "8:16");
+            system.holder("virtual/block/md0", "9:0", &[&sdb]);
+
+            let protected = protected(&system);
+            assert_eq!(protected["/dev/sdb"], vec!["sdbがmd0の構成デバイスとして使用中".to_string()]);
+            assert_eq!(protected.len(), 1);
+        }
+
+        #[test]
+        fn follows_loop_device_to_disk_holding_backing_file() {
+            let system = FakeSystem::new();
+            let image = system.path("disk.img");
+            fs::write(&image, b"image").unwrap();
+            let backing_id = device_id(fs::metadata(&image).unwrap().dev());
+            system.block_device("block/sdd", &backing_id);
+            let loop0 = system.block_device("virtual/block/loop0", "7:0");
+            fs::create_dir_all(loop0.join("loop")).unwrap();
+            fs::write(loop0.join("loop/backing_file"), format!("{}\n", image.display())).unwrap();
+            system.mount("7:0", "/mnt/image", "/dev/loop0");
+
+            let protected = protected(&system);
+            assert_eq!(protected.len(), 2);
+            assert_eq!(protected["/dev/sdd"], vec!["/mnt/imageにマウント中".to_string()]);
+            assert_eq!(protected["/dev/loop0"], vec!["/mnt/imageにマウント中".to_string()]);
+        }
+
+        #[test]
+        fn protects_disks_of_swapfile_and_swap_partition() {
+            let system = FakeSystem::new();
+            let swapfile = system.path("swapfile");
+            fs::write(&swapfile, b"swap").unwrap();
+            let swapfile_id = device_id(fs::metadata(&swapfile).unwrap().dev());
+            system.block_device("block/sde", &swapfile_id);
+            // スワップパーティションはデバイスファイル自身のデバイス番号（/dev/zeroは1:5）で解決する
+            system.block_device("block/sdf", "1:5");
+            system.swap(&swapfile.display().to_string(), "file");
+            system.swap("/dev/zero", "partition");
+            system.swap("/nonexistent/swapfile", "file");
+
+            let protected = protected(&system);
+            assert_eq!(protected.len(), 2);
+            assert_eq!(
+                protected["/dev/sde"],
+                vec![format!("スワップ領域として使用中（{}）", swapfile.display())]
+            );
+            assert_eq!(protected["/dev/sdf"], vec!["スワップ領域として使用中（/dev/zero）".to_string()]);
+        }
+
+        #[test]
+        fn falls_back_to_mount_source_when_device_number_is_not_in_sysfs() {
+            let system = FakeSystem::new();
+            // btrfsの匿名デバイス番号（0:45）はsysfsに存在しないため、マウント元（/dev/nullは1:3）から解決する
+            system.block_device("block/sdg", "1:3");
+            system.block_device("block/sdh", "8:112");
+            system.mount("0:45", "/data", "/dev/null");
+            system.mount("0:46", "/run", "tmpfs");
+
+            let protected = protected(&system);
+            assert_eq!(protected.len(), 1);
+            assert_eq!(protected["/dev/sdg"], vec!["/dataにマウント中".to_string()]);
+        }
+
+        #[test]
+        fn fails_closed_when_proc_cannot_be_read() {
+            let system = FakeSystem::new();
+            system.block_device("block/sda", "8:0");
+            fs::remove_file(system.path("proc/swaps")).unwrap();
+
+            let result = protected_disks_under(&system.roots());
+            assert!(result.as_ref().unwrap_err().contains("swaps"));
+
+            let mut disks = vec![disk("/dev/sda")];
+            apply_protection(&mut disks, result);
+            assert!(disks[0].protected);
+            assert!(disks[0].protection_reasons[0].contains("使用中のディスクを確認できない"));
+
+            let missing = SystemRoots {
+                proc: system.path("missing"),
+                sys: system.path("sys"),
+            };
+            assert!(protected_disks_under(&missing).unwrap_err().contains("mountinfo"));
+        }
+    }
+}
@@ -0,0 +1,758 @@
+/// # シミュレーションモジュール
+///
+/// このモジュールは、実際のディスクに触れずに消去処理を試すためのシミュレーションバックエンドを提供します。
+/// シミュレーションは設定ファイルの`[simulation]`セクションまたは環境変数`DISK_ERASER_SIMULATION`で
+/// 明示的に有効にした場合のみ使用され、その間は実際のディスクは一覧に表示されません。
+///
+/// 仮想ディスクは`sim://`で始まるデバイス名とモデル名の「（仮想ディスク）」で区別され、
+/// ディスクのサイズとスループットに基づいた現実的な所要時間で消去を再現します。
+/// また、書き込みエラー、不良セクタ、検証の不一致を指定したパスと位置で発生させることができます。
+///
+/// ```toml
+/// [simulation]
+/// enabled = true
+///
+/// [[simulation.disks]]
+/// name = "hdd0"
+/// model = "WD Blue 2TB"
+/// device_type = "hdd"
+/// transport = "sata"
+/// size_gib = 8
+///
+/// [[simulation.failures]]
+/// device = "hdd0"
+/// pass = 2
+/// at_percent = 40
+/// type = "bad_sectors"
+/// count = 16
+/// ```
+use crate::cancellation::DiskControl;
+use crate::config;
+use crate::erase_plan::ErasePlan;
+use crate::erase_standards::OverwriteScheme;
+use crate::logger::log_message;
+use crate::progress::ProgressReporter;
+use crate::erase_method::{EraseError, EraseMethod, SanitizeAction, SanitizeReport};
+use crate::verification::{LbaRange, LbaRangeCollector, VerificationLevel};
+use crate::{DeviceKind, DiskInfo, EraseCapabilities, MediaKind, Transport};
+use serde::Deserialize;
+use std::thread;
+use std::time::Duration;
+
+/// シミュレーションを有効にする環境変数（"1"または"true"で有効）
+pub const SIMULATION_ENV: &str = "DISK_ERASER_SIMULATION";
+
+/// 仮想ディスクのデバイス名の接頭辞
+pub const DEVICE_PREFIX: &str = "sim://";
+
+/// 進捗を更新する間隔
+const TICK: Duration = Duration::from_millis(250);
+
+/// 仮想ディスクの論理セクタサイズ
+const SECTOR_SIZE: u64 = 512;
+
+/// シミュレーションの設定
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationConfig {
+    /// シミュレーションを有効にするか
+    #[serde(default)]
+    pub enabled: bool,
+    /// 仮想ディスク（省略時は既定のHDD、SATA SSD、NVMe SSDの3台）
+    #[serde(default)]
+    pub disks: Vec<VirtualDiskDefinition>,
+    /// 発生させる障害
+    #[serde(default)]
+    pub failures: Vec<FailureDefinition>,
+}
+
+/// 仮想ディスクの定義
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct VirtualDiskDefinition {
+    /// 仮想ディスクの名前（デバイス名は`sim://<name>`になる）
+    pub name: String,
+    /// モデル名
+    pub model: String,
+    /// ディスクの種類（hdd/ssd）
+    pub device_type: MediaKind,
+    /// 接続方式（SATA/NVMe/USB）
+    pub transport: Transport,
+    /// 容量（GiB）
+    pub size_gib: u64,
+    /// 上書きのスループット（MiB/秒、省略時はディスクの種類から決定）
+    #[serde(default)]
+    pub throughput_mib_per_sec: Option<u64>,
+    /// ディスクが対応している消去機能（省略時は接続方式から決定）
+    #[serde(default)]
+    pub capabilities: Option<VirtualCapabilities>,
+}
+
+/// 仮想ディスクが対応している消去機能
+#[derive(Deserialize, Default, Debug, Clone)]
+#[serde(deny_unknown_fields, default)]
+pub struct VirtualCapabilities {
+    pub ata_security_supported: bool,
+    pub ata_enhanced_erase_supported: bool,
+    pub ata_security_frozen: bool,
+    pub ata_security_enabled: bool,
+    pub ata_security_locked: bool,
+    pub ata_sanitize_block_supported: bool,
+    pub ata_sanitize_crypto_supported: bool,
+    pub ata_sanitize_overwrite_supported: bool,
+    pub nvme_format_supported: bool,
+    pub nvme_crypto_erase_supported: bool,
+    pub nvme_format_affects_other_namespaces: bool,
+    pub nvme_sanitize_crypto_supported: bool,
+    pub nvme_sanitize_block_supported: bool,
+    pub nvme_sanitize_overwrite_supported: bool,
+    pub nvme_sanitize_affects_other_namespaces: bool,
+    pub security_protocol_supported: bool,
+    pub discard_supported: bool,
+}
+
+/// 発生させる障害の定義
+#[derive(Deserialize, Debug, Clone)]
+pub struct FailureDefinition {
+    /// 対象の仮想ディスクの名前
+    pub device: String,
+    /// 障害を発生させるパス（1始まり）
+    #[serde(default = "default_failure_pass")]
+    pub pass: u32,
+    /// 障害を発生させる位置（パスの進捗率、%）
+    #[serde(default = "default_failure_percent")]
+    pub at_percent: u8,
+    /// 障害の種類
+    #[serde(flatten)]
+    pub kind: FailureKind,
+}
+
+/// 障害の種類
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FailureKind {
+    /// 書き込みエラーでパスが失敗する
+    IoError,
+    /// 指定した数の不良セクタが見つかり、記録したうえでパスを続行する
+    BadSectors { count: u64 },
+    /// 読み戻し検証で残存データが見つかる
+    VerifyMismatch,
+}
+
+fn default_failure_pass() -> u32 {
+    1
+}
+
+fn default_failure_percent() -> u8 {
+    50
+}
+
+/// シミュレーションが有効な場合はその設定を返す関数
+///
+/// # 戻り値
+///
+/// * `Result<Option<SimulationConfig>, String>` - 無効な場合は`Ok(None)`、設定ファイルの誤りは`Err`
+pub fn active_config() -> Result<Option<SimulationConfig>, String> {
+    let mut simulation = config::load()?.simulation;
+    let from_env = std::env::var(SIMULATION_ENV)
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    simulation.enabled |= from_env;
+
+    Ok(simulation.enabled.then_some(simulation))
+}
+
+/// 仮想ディスクの一覧を作成する関数
+pub fn virtual_disks(config: &SimulationConfig) -> Vec<DiskInfo> {
+    definitions(config)
+        .iter()
+        .map(|definition| {
+            let capabilities = definition
+                .capabilities
+                .clone()
+                .unwrap_or_else(|| default_capabilities(definition.transport));
+            DiskInfo {
+                device_name: format!("{}{}", DEVICE_PREFIX, definition.name),
+                model: format!("{}（仮想ディスク）", definition.model),
+                device_type: definition.device_type,
+                transport: definition.transport,
+                kind: DeviceKind::Disk,
+                size: definition.size_gib * 1024 * 1024 * 1024,
+                logical_sector_size: SECTOR_SIZE as u32,
+                physical_sector_size: if definition.device_type == MediaKind::Hdd { 4096 } else { SECTOR_SIZE as u32 },
+                rotational: definition.device_type == MediaKind::Hdd,
+                removable: false,
+                read_only: false,
+                vendor: None,
+                serial: Some(format!("SIM-{}", definition.name.to_uppercase())),
+                wwn: None,
+                firmware: Some("SIM1.0".to_string()),
+                capabilities: EraseCapabilities {
+                    ata_security_supported: capabilities.ata_security_supported,
+                    ata_enhanced_erase_supported: capabilities.ata_enhanced_erase_supported,
+                    ata_security_frozen: capabilities.ata_security_frozen,
+                    ata_security_enabled: capabilities.ata_security_enabled,
+                    ata_security_locked: capabilities.ata_security_locked,
+                    ata_sanitize_block_supported: capabilities.ata_sanitize_block_supported,
+                    ata_sanitize_crypto_supported: capabilities.ata_sanitize_crypto_supported,
+                    ata_sanitize_overwrite_supported: capabilities.ata_sanitize_overwrite_supported,
+                    nvme_format_supported: capabilities.nvme_format_supported,
+                    nvme_crypto_erase_supported: capabilities.nvme_crypto_erase_supported,
+                    nvme_format_affects_other_namespaces: capabilities.nvme_format_affects_other_namespaces,
+                    nvme_sanitize_crypto_supported: capabilities.nvme_sanitize_crypto_supported,
+                    nvme_sanitize_block_supported: capabilities.nvme_sanitize_block_supported,
+                    nvme_sanitize_overwrite_supported: capabilities.nvme_sanitize_overwrite_supported,
+                    nvme_sanitize_affects_other_namespaces: capabilities
+                        .nvme_sanitize_affects_other_namespaces,
+                    security_protocol_supported: capabilities.security_protocol_supported,
+                    discard_supported: capabilities.discard_supported,
+                },
+                sibling_namespaces: Vec::new(),
+                protected: false,
+                protection_reasons: Vec::new(),
+                simulated: true,
+            }
+        })
+        .collect()
+}
+
+/// 設定された仮想ディスク（未設定の場合は既定の仮想ディスク）
+fn definitions(config: &SimulationConfig) -> Vec<VirtualDiskDefinition> {
+    if config.disks.is_empty() {
+        default_disks()
+    } else {
+        config.disks.clone()
+    }
+}
+
+/// 既定の仮想ディスク
+fn default_disks() -> Vec<VirtualDiskDefinition> {
+    let disk = |name: &str, model: &str, device_type, transport, size_gib| {
+        VirtualDiskDefinition {
+            name: name.to_string(),
+            model: model.to_string(),
+            device_type,
+            transport,
+            size_gib,
+            throughput_mib_per_sec: None,
+            capabilities: None,
+        }
+    };
+    vec![
+        disk("hdd0", "WD Blue 2TB", MediaKind::Hdd, Transport::Sata, 8),
+        disk("ssd0", "Samsung SSD 870 EVO 1TB", MediaKind::Ssd, Transport::Sata, 4),
+        disk("nvme0", "Samsung PM9A1 NVMe 512GB", MediaKind::Ssd, Transport::Nvme, 4),
+    ]
+}
+
+/// 接続方式から既定の消去機能を決定する
+fn default_capabilities(transport: Transport) -> VirtualCapabilities {
+    match transport {
+        Transport::Sata => VirtualCapabilities {
+            ata_security_supported: true,
+            ata_enhanced_erase_supported: true,
+            ata_sanitize_block_supported: true,
+            ata_sanitize_crypto_supported: true,
+            discard_supported: true,
+            ..Default::default()
+        },
+        Transport::Nvme => VirtualCapabilities {
+            nvme_format_supported: true,
+            nvme_crypto_erase_supported: true,
+            nvme_sanitize_crypto_supported: true,
+            nvme_sanitize_block_supported: true,
+            discard_supported: true,
+            ..Default::default()
+        },
+        _ => VirtualCapabilities::default(),
+    }
+}
+
+/// 仮想ディスクの消去をシミュレーションする関数
+///
+/// 消去計画で検証済みの消去方式をシミュレーションします。
+///
+/// # 引数
+///
+/// * `disk` - 消去対象の仮想ディスク
+/// * `method` - 消去方式
+/// * `plan` - 上書き消去方式、読み戻し検証の範囲、NVMe Sanitizeのオプションを含む消去計画
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
+/// # 戻り値
+///
+/// * `Result<(), EraseError>` - 成功時は`Ok(())`、失敗時は消去を開始したかどうかとエラーメッセージを含む`Err`
+pub fn erase(
+    disk: &DiskInfo,
+    method: EraseMethod,
+    plan: &ErasePlan,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let config = active_config()
+        .map_err(EraseError::NotStarted)?
+        .ok_or_else(|| {
+            EraseError::NotStarted("シミュレーションが無効なため、仮想ディスクは消去できません。".to_string())
+        })?;
+    erase_with_config(config, disk, method, plan, control, reporter)
+}
+
+/// 指定したシミュレーションの設定で仮想ディスクの消去をシミュレーションする関数
+fn erase_with_config(
+    config: SimulationConfig,
+    disk: &DiskInfo,
+    method: EraseMethod,
+    plan: &ErasePlan,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let mut simulator = Simulator::new(config, disk, control, reporter)?;
+    let (size, throughput) = (simulator.size, simulator.throughput);
+
+    match method {
+        EraseMethod::Overwrite => simulator
+            .overwrite(&plan.scheme, plan.verification)
+            .map_err(EraseError::Started),
+        EraseMethod::AtaSecureErase { enhanced } => {
+            // ドライブが報告する所要時間に相当する時間（Enhancedは暗号鍵の破棄のみのため短い）
+            let duration = if enhanced {
+                Duration::from_secs(20)
+            } else {
+                Duration::from_secs(size / throughput)
+            };
+            simulator.device_command(method, duration)
+        }
+        EraseMethod::NvmeFormat { ses } => {
+            let duration = if ses == 2 {
+                Duration::from_secs(5)
+            } else {
+                Duration::from_secs(30)
+            };
+            simulator.device_command(method, duration)
+        }
+        EraseMethod::AtaSanitize { action } => {
+            let duration = match action {
+                SanitizeAction::CryptoErase => Duration::from_secs(5),
+                SanitizeAction::BlockErase => Duration::from_secs(30),
+                SanitizeAction::Overwrite => Duration::from_secs(size / throughput),
+            };
+            simulator.sanitize(method, duration)
+        }
+        EraseMethod::NvmeSanitize { action } => {
+            let duration = match action {
+                SanitizeAction::CryptoErase => Duration::from_secs(5),
+                SanitizeAction::BlockErase => Duration::from_secs(20),
+                SanitizeAction::Overwrite => {
+                    Duration::from_secs(size / throughput * u64::from(plan.nvme_sanitize.overwrite_passes))
+                }
+            };
+            simulator.sanitize(method, duration)
+        }
+        EraseMethod::Discard
+        | EraseMethod::OpalRevert => Err(EraseError::NotStarted(format!(
+            "{}の実行にはまだ対応していません。",
+            method.name()
+        ))),
+    }
+}
+
+/// 1台の仮想ディスクの消去を再現する構造体
+struct Simulator<'a> {
+    device: &'a str,
+    size: u64,
+    throughput: u64,
+    failures: Vec<FailureDefinition>,
+    /// これまでに見つかった不良セクタのLBA範囲
+    bad_sectors: Vec<LbaRange>,
+    control: &'a DiskControl,
+    reporter: &'a mut ProgressReporter,
+}
+
+impl<'a> Simulator<'a> {
+    /// 設定から仮想ディスクの定義と障害を探し、消去を再現する準備をする
+    ///
+    /// `sim://`で始まらないデバイス名は仮想ディスクではないため`Err`を返します。
+    fn new(
+        config: SimulationConfig,
+        disk: &'a DiskInfo,
+        control: &'a DiskControl,
+        reporter: &'a mut ProgressReporter,
+    ) -> Result<Self, EraseError> {
+        let name = disk.device_name.strip_prefix(DEVICE_PREFIX).ok_or_else(|| {
+            EraseError::NotStarted(format!("{}は仮想ディスクではありません。", disk.device_name))
+        })?;
+        let definition = definitions(&config)
+            .into_iter()
+            .find(|d| d.name == name)
+            .ok_or_else(|| EraseError::NotStarted(format!("仮想ディスクが見つかりません: {}", disk.device_name)))?;
+        let failures: Vec<FailureDefinition> = config
+            .failures
+            .into_iter()
+            .filter(|f| f.device == name)
+            .collect();
+
+        let throughput = definition
+            .throughput_mib_per_sec
+            .unwrap_or(match disk.device_type {
+                MediaKind::Hdd => 150,
+                MediaKind::Ssd | MediaKind::Unknown => 450,
+            })
+            .max(1)
+            * 1024
+            * 1024;
+        Ok(Simulator {
+            device: &disk.device_name,
+            size: definition.size_gib * 1024 * 1024 * 1024,
+            throughput,
+            failures,
+            bad_sectors: Vec::new(),
+            control,
+            reporter,
+        })
+    }
+
+    /// 上書き消去を再現する
+    fn overwrite(&mut self, scheme: &OverwriteScheme, verification: VerificationLevel) -> Result<(), String> {
+        let total = scheme.passes.len();
+        self.reporter.set_method(&scheme.name, total as u32, self.size);
+        log_message(
+            &format!("{}の上書き開始", self.device),
+            "進行中",
+            &format!("方式: {}、サイズ: {}バイト（シミュレーション）", scheme.name, self.size),
+        );
+
+        let mut bad_sector_count = 0;
+
+        for (i, pass) in scheme.passes.iter().enumerate() {
+            let pass_number = i as u32 + 1;
+            log_message(
+                &format!(
+                    "パス {}/{}: {}に{}を書き込み中",
+                    pass_number,
+                    total,
+                    self.device,
+                    pass.pattern.description()
+                ),
+                "進行中",
+                "シミュレーションモード",
+            );
+            self.reporter.start_pass(pass_number);
+
+            let failure = self.failure_for(pass_number);
+            let failure_offset = failure
+                .as_ref()
+                .map(|f| self.size / 100 * u64::from(f.at_percent.min(100)));
+
+            let mut written = 0;
+            while written < self.size {
+                self.control.check()?;
+                thread::sleep(TICK);
+                let step = self.throughput / (1000 / TICK.as_millis() as u64);
+                let next = (written + step).min(self.size);
+
+                if let (Some(failure), Some(offset)) = (&failure, failure_offset) {
+                    if written <= offset && offset < next {
+                        match failure.kind {
+                            FailureKind::IoError => {
+                                return Err(format!(
+                                    "パス {}/{} の実行に失敗しました: {}のオフセット{}への書き込みに失敗しました: Input/output error（シミュレーション）",
+                                    pass_number, total, self.device, offset
+                                ));
+                            }
+                            FailureKind::BadSectors { count } if count > 0 => {
+                                let start = offset / SECTOR_SIZE;
+                                if !self.bad_sectors.iter().any(|r| r.start == start) {
+                                    self.bad_sectors.push(LbaRange { start, end: start + count - 1 });
+                                    bad_sector_count += count;
+                                    log_message(
+                                        &format!("{}の不良セクタ", self.device),
+                                        "警告",
+                                        &format!(
+                                            "書き込みのできないセクタが{}個あります（シミュレーション）。LBA範囲: {}-{}",
+                                            count,
+                                            start,
+                                            start + count - 1
+                                        ),
+                                    );
+                                    self.reporter.record_bad_sectors(&self.bad_sectors, bad_sector_count);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                written = next;
+                self.reporter.update(written);
+            }
+
+            if !pass.verify && i + 1 < total {
+                continue;
+            }
+
+            self.reporter.start_verification(pass_number);
+            let checked = match verification {
+                VerificationLevel::Full => self.size,
+                VerificationLevel::Sample { percent } => self.size / 100 * u64::from(percent),
+            };
+            let mut verified = 0;
+            while verified < checked {
+                self.control.check()?;
+                thread::sleep(TICK);
+                verified = (verified + self.throughput / (1000 / TICK.as_millis() as u64)).min(checked);
+                self.reporter
+                    .update((u128::from(verified) * u128::from(self.size) / u128::from(checked.max(1))) as u64);
+            }
+
+            let mut mismatches = LbaRangeCollector::default();
+            if matches!(&failure, Some(f) if f.kind == FailureKind::VerifyMismatch) {
+                let start = failure_offset.unwrap_or(0) / SECTOR_SIZE;
+                for lba in start..start + 8 {
+                    mismatches.add(lba);
+                }
+            }
+            let report = mismatches.into_report(pass_number, pass.pattern.description(), verification, checked);
+            self.reporter.record_verification(&report);
+            if !report.is_clean() {
+                log_message(&format!("パス {}/{} の検証", pass_number, total), "失敗", &report.summary());
+                return Err(format!("残存データが見つかりました: {}", report.summary()));
+            }
+            log_message(&format!("パス {}/{} の検証", pass_number, total), "成功", &report.summary());
+        }
+
+        log_message(
+            &format!("{}消去完了: {}", scheme.name, self.device),
+            "成功",
+            "シミュレーションモードでの消去が完了しました。",
+        );
+        Ok(())
+    }
+
+    /// ATA Secure EraseやNVMe Formatなど、ディスク内部で実行される消去を再現する
+    ///
+    /// パス1に`io_error`の障害を指定すると、ドライブがコマンドを拒否した場合を再現します。
+    fn device_command(&mut self, method: EraseMethod, duration: Duration) -> Result<(), EraseError> {
+        self.reporter.set_method(method.name(), 1, 0);
+        self.reporter.start_pass(1);
+        self.reporter
+            .report_message(&format!("{}を実行中です（シミュレーション）。", method.name()));
+
+        if let Some(failure) = self.failure_for(1) {
+            if failure.kind == FailureKind::IoError {
+                thread::sleep(duration / 2);
+                return Err(EraseError::NotStarted(format!(
+                    "{}の{}に失敗しました: Input/output error（シミュレーション）",
+                    self.device,
+                    method.name()
+                )));
+            }
+        }
+
+        // ディスク内部で実行される消去は中断できない
+        let _guard = self
+            .control
+            .enter_non_interruptible(method.name())
+            .map_err(EraseError::NotStarted)?;
+        thread::sleep(duration);
+
+        log_message(
+            &format!("{}完了: {}", method.name(), self.device),
+            "成功",
+            "シミュレーションモードでの消去が完了しました。",
+        );
+        Ok(())
+    }
+
+    /// Sanitizeを再現する（ドライブが報告する進捗を一定間隔で通知する）
+    ///
+    /// パス1に`io_error`の障害を指定すると、途中で失敗してドライブが失敗状態に残る場合を再現します。
+    fn sanitize(&mut self, method: EraseMethod, duration: Duration) -> Result<(), EraseError> {
+        const STEPS: u32 = 20;
+
+        // ドライブが報告する最終状態（ATAはSANITIZE STATUS EXT、NVMeはSanitize StatusログのSSTAT）
+        let (succeeded_state, failed_state) = match method {
+            EraseMethod::NvmeSanitize { .. } => ("SSTAT 1: 正常に完了", "SSTAT 3: 失敗"),
+            _ => ("SD4 Sanitize Operation succeeded", "SD3 Sanitize Operation Failed"),
+        };
+
+        self.reporter.set_method(method.name(), 1, self.size);
+        self.reporter.start_pass(1);
+        let failure = self
+            .failure_for(1)
+            .filter(|failure| failure.kind == FailureKind::IoError);
+
+        // Sanitizeは開始すると中断できない
+        let _guard = self
+            .control
+            .enter_non_interruptible(method.name())
+            .map_err(EraseError::NotStarted)?;
+        for step in 1..=STEPS {
+            thread::sleep(duration / STEPS);
+            if failure.is_some() && step == STEPS / 2 {
+                let report = SanitizeReport {
+                    method,
+                    completed: false,
+                    state: format!("{}（シミュレーション）", failed_state),
+                    failure_persists: true,
+                };
+                self.reporter.record_sanitize(&report);
+                return Err(EraseError::Started(format!(
+                    "{}の{}が失敗しました（{}）。ドライブは電源を入れ直しても失敗状態のまま残るため、\
+                     再度Sanitizeを実行して成功させる必要があります。",
+                    self.device,
+                    method.name(),
+                    report.state
+                )));
+            }
+            self.reporter.update(self.size / u64::from(STEPS) * u64::from(step));
+        }
+
+        self.reporter.record_sanitize(&SanitizeReport {
+            method,
+            completed: true,
+            state: format!("{}（シミュレーション）", succeeded_state),
+            failure_persists: false,
+        });
+        log_message(
+            &format!("{}完了: {}", method.name(), self.device),
+            "成功",
+            "シミュレーションモードでの消去が完了しました。",
+        );
+        Ok(())
+    }
+
+    /// 指定したパスで発生させる障害
+    fn failure_for(&self, pass: u32) -> Option<FailureDefinition> {
+        self.failures.iter().find(|f| f.pass == pass).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cancellation::CancelRegistry;
+    use crate::erase_standards::{builtin_schemes, DEFAULT_SCHEME_ID};
+
+    /// テスト用の仮想ディスクの容量（バイト）
+    const SIZE: u64 = 1 << 30;
+
+    /// 1パスを1回の更新で書き込める速さの1GiBの仮想ディスク
+    fn config(failures: Vec<FailureDefinition>) -> SimulationConfig {
+        SimulationConfig {
+            enabled: true,
+            disks: vec![VirtualDiskDefinition {
+                name: "hdd0".to_string(),
+                model: "TEST HDD".to_string(),
+                device_type: MediaKind::Hdd,
+                transport: Transport::Sata,
+                size_gib: 1,
+                throughput_mib_per_sec: Some(1 << 20),
+                capabilities: None,
+            }],
+            failures,
+        }
+    }
+
+    fn failure(pass: u32, at_percent: u8, kind: FailureKind) -> FailureDefinition {
+        FailureDefinition {
+            device: "hdd0".to_string(),
+            pass,
+            at_percent,
+            kind,
+        }
+    }
+
+    /// 3パスのDoD 5220.22-M（最終パスのみ検証）で仮想ディスクを上書きし、結果と不良セクタを返す
+    fn overwrite(failures: Vec<FailureDefinition>) -> (Result<(), String>, Vec<LbaRange>, u64) {
+        let disk = virtual_disks(&config(failures.clone())).remove(0);
+        let control = CancelRegistry::default()
+            .register(std::slice::from_ref(&disk.device_name))
+            .disk(&disk.device_name)
+            .unwrap();
+        let mut reporter = ProgressReporter::new(None, "test", &disk.device_name);
+        let scheme = builtin_schemes()
+            .into_iter()
+            .find(|scheme| scheme.id == DEFAULT_SCHEME_ID)
+            .unwrap();
+
+        let mut simulator = Simulator::new(config(failures), &disk, &control, &mut reporter).unwrap();
+        let result = simulator.overwrite(&scheme, VerificationLevel::Full);
+        let bad_sectors = simulator.bad_sectors.clone();
+        (result, bad_sectors, reporter.bad_sector_count())
+    }
+
+    #[test]
+    fn io_error_fails_at_configured_pass_and_offset() {
+        let (result, bad_sectors, _) = overwrite(vec![failure(2, 40, FailureKind::IoError)]);
+
+        let message = result.unwrap_err();
+        assert!(message.contains("パス 2/3"), "{}", message);
+        assert!(message.contains(&format!("オフセット{}", SIZE / 100 * 40)), "{}", message);
+        assert!(bad_sectors.is_empty());
+    }
+
+    #[test]
+    fn bad_sectors_are_recorded_at_configured_offset_and_pass_continues() {
+        let (result, bad_sectors, count) = overwrite(vec![failure(2, 25, FailureKind::BadSectors { count: 16 })]);
+
+        assert!(result.is_ok());
+        let start = SIZE / 100 * 25 / SECTOR_SIZE;
+        assert_eq!(bad_sectors, vec![LbaRange { start, end: start + 15 }]);
+        assert_eq!(count, 16);
+    }
+
+    #[test]
+    fn failures_do_not_fire_before_their_pass() {
+        let (result, bad_sectors, count) = overwrite(vec![
+            failure(2, 10, FailureKind::IoError),
+            failure(3, 10, FailureKind::BadSectors { count: 4 }),
+        ]);
+
+        assert!(result.unwrap_err().contains("パス 2/3"));
+        assert!(bad_sectors.is_empty());
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn verify_mismatch_is_reported_at_configured_pass_and_offset() {
+        let (result, bad_sectors, _) = overwrite(vec![failure(3, 60, FailureKind::VerifyMismatch)]);
+
+        let message = result.unwrap_err();
+        let start = SIZE / 100 * 60 / SECTOR_SIZE;
+        assert!(message.contains("パス3（"), "{}", message);
+        assert!(message.contains(&format!("LBA範囲: {}-{}", start, start + 7)), "{}", message);
+        assert!(bad_sectors.is_empty());
+    }
+
+    #[test]
+    fn uses_default_virtual_disks_when_none_are_configured() {
+        let disks = virtual_disks(&SimulationConfig::default());
+
+        let names: Vec<&str> = disks.iter().map(|disk| disk.device_name.as_str()).collect();
+        assert_eq!(names, vec!["sim://hdd0", "sim://ssd0", "sim://nvme0"]);
+        assert!(disks.iter().all(|disk| disk.simulated && disk.model.ends_with("（仮想ディスク）")));
+        assert_eq!(disks[0].device_type, MediaKind::Hdd);
+        assert!(disks[1].capabilities.ata_security_supported);
+        assert!(disks[2].capabilities.nvme_format_supported);
+    }
+
+    #[test]
+    fn never_erases_a_real_device_in_simulation() {
+        let mut config = config(Vec::new());
+        config.disks[0].name = "/dev/sda".to_string();
+        let disk = DiskInfo::for_test("/dev/sda", MediaKind::Hdd, Transport::Sata);
+        let control = CancelRegistry::default()
+            .register(std::slice::from_ref(&disk.device_name))
+            .disk(&disk.device_name)
+            .unwrap();
+        let mut reporter = ProgressReporter::new(None, "test", &disk.device_name);
+
+        let plan = ErasePlan::resume(builtin_schemes().remove(0), VerificationLevel::Full);
+        let result = erase_with_config(config, &disk, EraseMethod::Overwrite, &plan, &control, &mut reporter);
+        match result {
+            Err(EraseError::NotStarted(message)) => assert!(message.contains("仮想ディスクではありません")),
+            other => panic!("実際のデバイスを消去しようとしました: {:?}", other),
+        }
+    }
+}
@@ -1,183 +1,103 @@
 /// # SSD消去モジュール
-/// 
+///
 /// このモジュールは、SSDのデータを安全に消去するための機能を提供します。
-/// ATA Secure Eraseコマンドを使用して、SSDのデータを完全に消去します。
+/// 消去計画で選択されたATA Secure Erase、ATA Sanitize、NVMe Format、NVMe Sanitizeなど、
+/// ドライブのコマンドによる消去方式を実行します。
 
-use crate::logger::log_message;
-use std::process::Command;
-use std::thread;
-use std::time::Duration;
+#[cfg(target_os = "linux")]
+use crate::ata_sanitize;
+#[cfg(target_os = "linux")]
+use crate::ata_security;
+use crate::cancellation::DiskControl;
+#[cfg(target_os = "linux")]
+use crate::device_backend::{self, Privilege};
+use crate::erase_method::{EraseError, EraseMethod};
+use crate::erase_plan::ErasePlan;
+#[cfg(target_os = "linux")]
+use crate::{nvme_format, nvme_sanitize};
+use crate::progress::ProgressReporter;
+use crate::DiskInfo;
 
-/// SSDをSecure Eraseで消去する関数
-/// 
+/// ドライブのコマンドによる消去方式でSSDを消去する関数
+///
+/// 上書き消去は`hdd_erase`で実行するため、ここでは扱いません。
+///
 /// # 引数
-/// 
-/// * `device` - 消去対象のデバイス名（例: /dev/sda）
-/// 
+///
+/// * `disk` - 消去対象のディスク情報
+/// * `method` - 消去計画で検証済みの消去方式
+/// * `plan` - 読み戻し検証の範囲とNVMe Sanitizeのオプションを含む消去計画
+/// * `control` - キャンセル要求を受け取る制御オブジェクト
+/// * `reporter` - 進捗の通知先
+///
 /// # 戻り値
-/// 
-/// * `Result<(), String>` - 成功時は`Ok(())`、失敗時はエラーメッセージを含む`Err`
-pub fn secure_erase_ssd(device: &str) -> Result<(), String> {
-    let device_name = device.split_whitespace().next().unwrap_or(device);
+///
+/// * `Result<(), EraseError>` - 成功時は`Ok(())`、失敗時は消去のコマンドを発行したかどうかとエラーメッセージを含む`Err`
+pub fn secure_erase_ssd(
+    disk: &DiskInfo,
+    method: EraseMethod,
+    plan: &ErasePlan,
+    control: &DiskControl,
+    reporter: &mut ProgressReporter,
+) -> Result<(), EraseError> {
+    let device_name = disk.device_name.as_str();
 
-    log_message(
-        &format!("Secure Erase開始: {}", device_name),
-        "進行中",
-        "デバイスタイプに基づいてSecure Eraseを実行します。",
-    );
-
-    // Windowsでの開発環境では実際の消去処理をシミュレーションする
+    // Linux以外では実際のディスクを消去できない（動作確認にはシミュレーションモードを使用する）
     #[cfg(not(target_os = "linux"))]
     {
-        // 開発環境では消去をシミュレーション
-        log_message(
-            &format!("{}のSecure Eraseをシミュレーション中", device_name),
-            "進行中",
-            "シミュレーションモード",
-        );
-        
-        // 進行状況のシミュレーション
-        thread::sleep(Duration::from_secs(5));
-        
-        log_message(
-            &format!("Secure Erase完了: {}", device_name),
-            "成功",
-            "シミュレーションモードでの消去が完了しました。",
-        );
-        
-        return Ok(());
+        let _ = (method, plan, control, reporter);
+        Err(EraseError::NotStarted(format!(
+            "この環境では{}を消去できません。実際の消去はLinuxでのみ対応しています。動作確認には環境変数{}=1でシミュレーションモードを有効にしてください。",
+            device_name,
+            crate::simulation::SIMULATION_ENV
+        )))
     }
 
     // 実際のLinux環境での消去処理
     #[cfg(target_os = "linux")]
     {
-        if device.contains("SATA") {
-            // パスワードを設定（ATA Secure Erase）
-            let output = Command::new("hdparm")
-                .arg("--user-master")
-                .arg("u")
-                .arg("--security-set-pass")
-                .arg("0000")
-                .arg(device_name)
-                .output()
-                .map_err(|e| format!("パスワード設定に失敗しました: {}", e))?;
-
-            if !output.status.success() {
-                let error_message = format!(
-                    "{}のパスワード設定に失敗しました: {}",
-                    device_name,
-                    String::from_utf8_lossy(&output.stderr)
-                );
-                log_message("ATA Secure Erase", "失敗", &error_message);
-                return Err(error_message);
-            }
-
-            // Enhanced Secure Eraseを試みる
-            let output = Command::new("hdparm")
-                .arg("--user-master")
-                .arg("u")
-                .arg("--security-erase-enhanced")
-                .arg("0000")
-                .arg(device_name)
-                .output();
-
-            match output {
-                Ok(result) => {
-                    if result.status.success() {
-                        log_message(
-                            "ATA Secure Erase",
-                            "成功",
-                            "Enhanced Secure Eraseが正常に完了しました。",
-                        );
-                        return Ok(());
-                    }
-                }
-                Err(_) => {}
-            }
-
-            // 通常のSecure Eraseを試みる
-            let output = Command::new("hdparm")
-                .arg("--user-master")
-                .arg("u")
-                .arg("--security-erase")
-                .arg("0000")
-                .arg(device_name)
-                .output()
-                .map_err(|e| format!("Secure Eraseの実行に失敗しました: {}", e))?;
-
-            if !output.status.success() {
-                let error_message = format!(
-                    "{}のSecure Eraseに失敗しました: {}",
-                    device_name,
-                    String::from_utf8_lossy(&output.stderr)
-                );
-                log_message("ATA Secure Erase", "失敗", &error_message);
-                return Err(error_message);
-            }
-
-            log_message(
-                "ATA Secure Erase",
-                "成功",
-                "Secure Eraseが正常に完了しました。",
-            );
-            Ok(())
-        } else if device.contains("NVMe") {
-            // NVMe用のSecure Erase
-            let output = Command::new("nvme")
-                .arg("format")
-                .arg("--ses=1")
-                .arg(device_name)
-                .output()
-                .map_err(|e| format!("NVMe formatの実行に失敗しました: {}", e))?;
-
-            if !output.status.success() {
-                let error_message = format!(
-                    "{}のNVMe formatに失敗しました: {}",
-                    device_name,
-                    String::from_utf8_lossy(&output.stderr)
-                );
-                log_message("NVMe Secure Erase", "失敗", &error_message);
-                return Err(error_message);
-            }
-
-            log_message(
-                "NVMe Secure Erase",
-                "成功",
-                "NVMe formatが正常に完了しました。",
-            );
-            Ok(())
-        } else {
-            // その他のSSD（USB接続など）
-            log_message(
-                &format!("不明なSSDタイプ: {}", device),
-                "警告",
-                "標準的なブロックワイプを実行します。",
-            );
-            
-            // ブロックデバイスにゼロを書き込む
-            let output = Command::new("dd")
-                .arg("if=/dev/zero")
-                .arg(format!("of={}", device_name))
-                .arg("bs=4M")
-                .arg("status=progress")
-                .output()
-                .map_err(|e| format!("ddコマンドの実行に失敗しました: {}", e))?;
-
-            if !output.status.success() {
-                let error_message = format!(
-                    "{}のブロックワイプに失敗しました",
-                    device_name
-                );
-                log_message("ブロックワイプ", "失敗", &error_message);
-                return Err(error_message);
-            }
-
-            log_message(
-                "ブロックワイプ",
-                "成功",
-                "ブロックワイプが正常に完了しました。",
-            );
-            Ok(())
+        match method {
+            EraseMethod::AtaSecureErase { enhanced } => ata_security::secure_erase(
+                device_name,
+                device_backend::open_ata(device_name, Privilege::Inherit),
+                enhanced,
+                control,
+                reporter,
+            ),
+            EraseMethod::AtaSanitize { action } => ata_sanitize::sanitize(
+                device_name,
+                device_backend::open_ata(device_name, Privilege::Inherit),
+                disk.size,
+                action,
+                control,
+                reporter,
+            ),
+            EraseMethod::NvmeFormat { ses } => nvme_format::format(
+                device_name,
+                device_backend::open_nvme(device_name, Privilege::Inherit),
+                ses,
+                plan.verification,
+                control,
+                reporter,
+            ),
+            EraseMethod::Overwrite => Err(EraseError::NotStarted(format!(
+                "{}はドライブのコマンドによる消去方式ではありません。",
+                method.name()
+            ))),
+            EraseMethod::NvmeSanitize { action } => nvme_sanitize::sanitize(
+                device_name,
+                device_backend::open_nvme(device_name, Privilege::Inherit),
+                disk.size,
+                action,
+                &plan.nvme_sanitize,
+                control,
+                reporter,
+            ),
+            EraseMethod::Discard
+            | EraseMethod::OpalRevert => Err(EraseError::NotStarted(format!(
+                "{}の実行にはまだ対応していません。",
+                method.name()
+            ))),
         }
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,294 @@
+/// # 検証モジュール
+///
+/// このモジュールは、上書き後の読み戻し検証に使用する型と補助関数を提供します。
+/// NIST SP 800-88に従い、全領域の検証または指定した割合のサンプリング検証を選択できます。
+/// 期待したパターンと一致しなかったセクタは連続するLBA範囲としてまとめて報告されます。
+/// 読み込めなかったセクタは不良セクタとして扱い、不一致には含めません。
+use serde::{Deserialize, Serialize};
+
+/// 報告するLBA範囲の最大数（これを超えた分はセクタ数のみ集計する）
+const MAX_REPORTED_RANGES: usize = 256;
+
+/// 読み戻し検証の範囲
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum VerificationLevel {
+    /// 全領域を読み戻して検証する
+    #[default]
+    Full,
+    /// 全領域に均等に分散した指定割合（%）の領域を読み戻して検証する
+    Sample { percent: u8 },
+}
+
+impl VerificationLevel {
+    /// 検証範囲の指定が妥当かどうかを確認する
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            VerificationLevel::Sample { percent } if !(1..=100).contains(percent) => Err(format!(
+                "サンプリング検証の割合は1〜100%で指定してください: {}%",
+                percent
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// ログに記録する検証範囲の説明
+    pub fn description(&self) -> String {
+        match self {
+            VerificationLevel::Full => "全領域".to_string(),
+            VerificationLevel::Sample { percent } => format!("{}%サンプリング", percent),
+        }
+    }
+
+    /// `total`個の領域のうち`index`番目の領域を検証するかどうかを判定する
+    ///
+    /// サンプリング検証では、先頭と末尾の領域に加えて全体に均等に分散した領域を選択します。
+    pub fn includes(&self, index: u64, total: u64) -> bool {
+        match self {
+            VerificationLevel::Full => true,
+            VerificationLevel::Sample { percent } => {
+                if index == 0 || index + 1 == total {
+                    return true;
+                }
+                let percent = u64::from(*percent);
+                (index + 1) * percent / 100 > index * percent / 100
+            }
+        }
+    }
+}
+
+/// 論理セクタ番号の範囲（両端を含む）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LbaRange {
+    /// 先頭のLBA
+    pub start: u64,
+    /// 末尾のLBA
+    pub end: u64,
+}
+
+/// 1パス分の検証結果
+#[derive(Serialize, Clone, Debug)]
+pub struct VerificationReport {
+    /// 検証したパス番号（1始まり）
+    pub pass: u32,
+    /// 期待したパターンの説明
+    pub pattern: String,
+    /// 検証範囲
+    pub level: VerificationLevel,
+    /// 読み戻したバイト数
+    pub bytes_checked: u64,
+    /// 期待したパターンと一致しなかったセクタ数
+    pub mismatched_sectors: u64,
+    /// 一致しなかったLBA範囲（最大256件）
+    pub mismatches: Vec<LbaRange>,
+}
+
+impl VerificationReport {
+    /// 残存データが見つからなかったかどうか
+    pub fn is_clean(&self) -> bool {
+        self.mismatched_sectors == 0
+    }
+
+    /// ログやエラーメッセージに使用する結果の概要
+    pub fn summary(&self) -> String {
+        if self.is_clean() {
+            return format!(
+                "パス{}（{}）の検証に成功しました。{}で{}バイトを確認しました。",
+                self.pass,
+                self.pattern,
+                self.level.description(),
+                self.bytes_checked
+            );
+        }
+
+        let ranges = self
+            .mismatches
+            .iter()
+            .map(|r| format!("{}-{}", r.start, r.end))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            "パス{}（{}）の検証で{}セクタの不一致が見つかりました。LBA範囲: {}",
+            self.pass, self.pattern, self.mismatched_sectors, ranges
+        )
+    }
+}
+
+/// セクタを連続するLBA範囲にまとめる構造体
+///
+/// 検証で一致しなかったセクタや、書き込めなかった不良セクタの集計に使用します。
+/// 既定では報告用に範囲の数を256件までに制限し、超えた分はセクタ数のみ集計します。
+pub struct LbaRangeCollector {
+    ranges: Vec<LbaRange>,
+    sectors: u64,
+    max_ranges: usize,
+}
+
+impl Default for LbaRangeCollector {
+    fn default() -> Self {
+        LbaRangeCollector {
+            ranges: Vec::new(),
+            sectors: 0,
+            max_ranges: MAX_REPORTED_RANGES,
+        }
+    }
+}
+
+impl LbaRangeCollector {
+    /// 範囲の数を制限せずにまとめる集計を作成する
+    ///
+    /// チェックポイントへの記録など、すべての範囲を失わずに保持する必要がある場合に使用します。
+    pub fn uncapped() -> Self {
+        LbaRangeCollector {
+            max_ranges: usize::MAX,
+            ..LbaRangeCollector::default()
+        }
+    }
+
+    /// 昇順のLBAの列から範囲をまとめる
+    pub fn from_sorted(lbas: impl IntoIterator<Item = u64>) -> Self {
+        LbaRangeCollector::default().extend_sorted(lbas)
+    }
+
+    /// 昇順のLBAの列を追加する
+    pub fn extend_sorted(mut self, lbas: impl IntoIterator<Item = u64>) -> Self {
+        for lba in lbas {
+            self.add(lba);
+        }
+        self
+    }
+
+    /// まとめたLBA範囲（既定では最大256件）
+    pub fn ranges(&self) -> &[LbaRange] {
+        &self.ranges
+    }
+
+    /// まとめたLBA範囲を取り出す
+    pub fn into_ranges(self) -> Vec<LbaRange> {
+        self.ranges
+    }
+
+    /// セクタを追加する（LBAは昇順で追加すること）
+    pub fn add(&mut self, lba: u64) {
+        self.sectors += 1;
+        if let Some(last) = self.ranges.last_mut() {
+            if last.end + 1 == lba {
+                last.end = lba;
+                return;
+            }
+        }
+        if self.ranges.len() < self.max_ranges {
+            self.ranges.push(LbaRange { start: lba, end: lba });
+        }
+    }
+
+    /// 集計した結果から検証結果を作成する
+    pub fn into_report(
+        self,
+        pass: u32,
+        pattern: String,
+        level: VerificationLevel,
+        bytes_checked: u64,
+    ) -> VerificationReport {
+        VerificationReport {
+            pass,
+            pattern,
+            level,
+            bytes_checked,
+            mismatched_sectors: self.sectors,
+            mismatches: self.ranges,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_sample_percent() {
+        assert!(VerificationLevel::Full.validate().is_ok());
+        assert!(VerificationLevel::Sample { percent: 1 }.validate().is_ok());
+        assert!(VerificationLevel::Sample { percent: 100 }.validate().is_ok());
+        assert!(VerificationLevel::Sample { percent: 0 }.validate().is_err());
+        assert!(VerificationLevel::Sample { percent: 101 }.validate().is_err());
+    }
+
+    #[test]
+    fn samples_requested_share_including_both_ends() {
+        let total = 1000;
+        for percent in [1u8, 10, 33, 50] {
+            let level = VerificationLevel::Sample { percent };
+            let selected: Vec<u64> = (0..total).filter(|&i| level.includes(i, total)).collect();
+            let expected = total * u64::from(percent) / 100;
+
+            assert_eq!(selected.first(), Some(&0));
+            assert_eq!(selected.last(), Some(&(total - 1)));
+            assert!(
+                (expected..=expected + 2).contains(&(selected.len() as u64)),
+                "{}%で{}個の領域を選択しました",
+                percent,
+                selected.len()
+            );
+            // 選択した領域が先頭に偏らず全体に分散していること
+            let second_half = selected.iter().filter(|&&i| i >= total / 2).count() as u64;
+            assert!(second_half + 1 >= expected / 2);
+        }
+
+        let full_sample = VerificationLevel::Sample { percent: 100 };
+        assert!((0..total).all(|i| full_sample.includes(i, total)));
+        assert!((0..total).all(|i| VerificationLevel::Full.includes(i, total)));
+        // 領域が1つしかない場合もその領域を検証する
+        assert!(VerificationLevel::Sample { percent: 1 }.includes(0, 1));
+    }
+
+    #[test]
+    fn merges_consecutive_lbas() {
+        let collector = LbaRangeCollector::from_sorted([1, 2, 3, 7, 8, 10]);
+        assert_eq!(
+            collector.ranges(),
+            &[
+                LbaRange { start: 1, end: 3 },
+                LbaRange { start: 7, end: 8 },
+                LbaRange { start: 10, end: 10 },
+            ]
+        );
+
+        let report = collector.into_report(2, "0x00".to_string(), VerificationLevel::Full, 4096);
+        assert!(!report.is_clean());
+        assert_eq!(report.mismatched_sectors, 6);
+        assert!(report.summary().contains("1-3, 7-8, 10-10"));
+    }
+
+    #[test]
+    fn caps_reported_ranges_but_counts_every_sector() {
+        let collector = LbaRangeCollector::from_sorted((0..300).map(|i| i * 2));
+        assert_eq!(collector.ranges().len(), MAX_REPORTED_RANGES);
+        assert_eq!(collector.ranges().last(), Some(&LbaRange { start: 510, end: 510 }));
+
+        let report = collector.into_report(1, "0x00".to_string(), VerificationLevel::Full, 0);
+        assert_eq!(report.mismatched_sectors, 300);
+        assert_eq!(report.mismatches.len(), MAX_REPORTED_RANGES);
+    }
+
+    #[test]
+    fn uncapped_collector_keeps_every_range() {
+        let ranges = LbaRangeCollector::uncapped()
+            .extend_sorted((0..300).map(|i| i * 2))
+            .into_ranges();
+        assert_eq!(ranges.len(), 300);
+        assert_eq!(ranges.last(), Some(&LbaRange { start: 598, end: 598 }));
+    }
+
+    #[test]
+    fn reports_clean_pass() {
+        let report = LbaRangeCollector::default().into_report(
+            1,
+            "0x00".to_string(),
+            VerificationLevel::Sample { percent: 10 },
+            1024,
+        );
+        assert!(report.is_clean());
+        assert!(report.summary().contains("10%サンプリング"));
+    }
+}
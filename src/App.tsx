@@ -7,14 +7,266 @@
 
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import "./App.css";
 
+// ディスクの種類と接続方式の型定義（バックエンドのMediaKind/Transportに対応）
+type MediaKind = "hdd" | "ssd" | "unknown";
+type Transport = "sata" | "sas" | "nvme" | "usb" | "mmc" | "virtio" | "unknown";
+
+const MEDIA_KIND_LABELS: Record<MediaKind, string> = {
+  hdd: "HDD",
+  ssd: "SSD",
+  unknown: "不明",
+};
+
+const TRANSPORT_LABELS: Record<Transport, string> = {
+  sata: "SATA",
+  sas: "SAS",
+  nvme: "NVMe",
+  usb: "USB",
+  mmc: "MMC",
+  virtio: "virtio",
+  unknown: "不明",
+};
+
 // ディスク情報の型定義
 interface DiskInfo {
   device_name: string;
   model: string;
-  device_type: string;
-  transport: string;
+  device_type: MediaKind;
+  transport: Transport;
+  capabilities: EraseCapabilities;
+  sibling_namespaces: { device_name: string; protected: boolean }[];
+  protected: boolean;
+  protection_reasons: string[];
+  simulated: boolean;
+  kind: "disk" | "cdrom" | "loop" | "mmc" | "zram";
+  size: number;
+  logical_sector_size: number;
+  physical_sector_size: number;
+  rotational: boolean;
+  removable: boolean;
+  read_only: boolean;
+  vendor: string | null;
+  serial: string | null;
+  wwn: string | null;
+  firmware: string | null;
+}
+
+// ディスクが対応している消去機能の型定義
+interface EraseCapabilities {
+  ata_security_supported: boolean;
+  ata_enhanced_erase_supported: boolean;
+  ata_security_frozen: boolean;
+  ata_security_enabled: boolean;
+  ata_security_locked: boolean;
+  ata_sanitize_block_supported: boolean;
+  ata_sanitize_crypto_supported: boolean;
+  ata_sanitize_overwrite_supported: boolean;
+  nvme_format_supported: boolean;
+  nvme_crypto_erase_supported: boolean;
+  nvme_format_affects_other_namespaces: boolean;
+  nvme_sanitize_crypto_supported: boolean;
+  nvme_sanitize_block_supported: boolean;
+  nvme_sanitize_overwrite_supported: boolean;
+  nvme_sanitize_affects_other_namespaces: boolean;
+  security_protocol_supported: boolean;
+  discard_supported: boolean;
+}
+
+// 上書き消去方式の型定義
+interface SchemeSummary {
+  id: string;
+  name: string;
+  description: string;
+  passes: string[];
+  verified_passes: number[];
+}
+
+// 消去進捗イベントの型定義
+interface ProgressEvent {
+  device: string;
+  method: string;
+  current_pass: number;
+  total_passes: number;
+  bytes_written: number;
+  total_bytes: number;
+  throughput: number;
+  eta_seconds: number | null;
+  state: "running" | "completed" | "failed";
+  message: string | null;
+}
+
+// 読み戻し検証の範囲の型定義
+type VerificationLevel = { mode: "full" } | { mode: "sample"; percent: number };
+
+// 読み戻し検証の結果の型定義
+interface VerificationReport {
+  pass: number;
+  pattern: string;
+  level: VerificationLevel;
+  bytes_checked: number;
+  mismatched_sectors: number;
+  mismatches: { start: number; end: number }[];
+}
+
+// 消去方式の型定義（バックエンドのEraseMethodに対応）
+type SanitizeAction = "block_erase" | "crypto_erase" | "overwrite";
+type EraseMethod =
+  | { method: "overwrite" }
+  | { method: "ata_secure_erase"; enhanced: boolean }
+  | { method: "ata_sanitize"; action: SanitizeAction }
+  | { method: "nvme_format"; ses: number }
+  | { method: "nvme_sanitize"; action: SanitizeAction }
+  | { method: "discard" }
+  | { method: "opal_revert" };
+
+// 消去後の処理の型定義
+type PostEraseAction = "none" | "spin_down" | "power_off";
+
+// NVMe Sanitizeのオプションの型定義
+interface NvmeSanitizeOptions {
+  overwrite_passes: number;
+  overwrite_pattern: number;
+  invert_pattern: boolean;
+  no_deallocate: boolean;
+}
+
+// ディスクごとの消去計画の型定義（バックエンドのDiskPlanに対応）
+interface DiskPlan {
+  device: string;
+  method: EraseMethod | null;
+  verification: VerificationLevel;
+  scheme: string | null;
+  nvme_sanitize: NvmeSanitizeOptions;
+  post_erase: PostEraseAction;
+}
+
+// ディスクが対応している消去方式の型定義
+interface MethodSupport {
+  method: EraseMethod;
+  name: string;
+  available: boolean;
+  reasons: string[];
+  warnings: string[];
+}
+
+interface DiskCapabilityReport {
+  device_name: string;
+  model: string;
+  device_type: MediaKind;
+  transport: Transport;
+  methods: MethodSupport[];
+  policy_chain: EraseMethod[];
+  policy_error: string | null;
+}
+
+// 消去方式の試行結果の型定義
+interface MethodAttempt {
+  method: EraseMethod;
+  outcome: "skipped" | "failed" | "cancelled" | "succeeded";
+  reason: string | null;
+}
+
+const ATTEMPT_OUTCOME_LABELS: Record<MethodAttempt["outcome"], string> = {
+  skipped: "スキップ",
+  failed: "失敗",
+  cancelled: "中断",
+  succeeded: "成功",
+};
+
+// Sanitizeの完了状態の型定義
+interface SanitizeReport {
+  method: EraseMethod;
+  completed: boolean;
+  state: string;
+  failure_persists: boolean;
+}
+
+// ディスクごとの消去状況の型定義
+interface DiskJobStatus {
+  device: string;
+  state: string;
+  error: string | null;
+  method: EraseMethod | null;
+  attempts: MethodAttempt[];
+  sanitize: SanitizeReport | null;
+  progress: ProgressEvent | null;
+  verification: VerificationReport[];
+  bad_sector_count: number;
+  bad_sectors: { start: number; end: number }[];
+}
+
+// 消去ジョブの状態の型定義
+interface JobStatus {
+  job_id: string;
+  state: "running" | "completed" | "failed" | "cancelled";
+  started_at: number;
+  finished_at: number | null;
+  disks: DiskJobStatus[];
+}
+
+// 消去ジョブの結果の型定義
+interface JobResult {
+  job_id: string;
+  state: string;
+  message: string;
+  disks: DiskJobStatus[];
+}
+
+// 中断した上書き消去の型定義
+interface ResumableErase {
+  device: string;
+  scheme_name: string;
+  pass: number;
+  total_passes: number;
+  phase: "write" | "verify";
+  offset: number;
+  size: number;
+  updated_at: number;
+  resumable: boolean;
+  reason: string | null;
+}
+
+// バイト数を読みやすい単位に変換する関数
+function formatBytes(bytes: number): string {
+  const units = ["B", "KiB", "MiB", "GiB", "TiB"];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit++;
+  }
+  return `${value.toFixed(1)} ${units[unit]}`;
+}
+
+// 実行した消去方式の試行結果を要約する関数（スキップした方式は含めない）
+function describeAttempts(disk: DiskJobStatus, names: Map<string, string>): string {
+  return disk.attempts
+    .filter((a) => a.outcome !== "skipped")
+    .map((a) => {
+      const name = names.get(JSON.stringify(a.method)) ?? a.method.method;
+      return `${name}（${ATTEMPT_OUTCOME_LABELS[a.outcome]}${a.reason ? `: ${a.reason}` : ""}）`;
+    })
+    .join(" → ");
+}
+
+// Sanitizeの完了状態を要約する関数
+function describeSanitize(report: SanitizeReport): string {
+  const result = report.completed ? "完了" : "未完了";
+  const note = report.failure_persists
+    ? "（ドライブは失敗状態のままです。再度Sanitizeを実行してください）"
+    : "";
+  return `Sanitize ${result}: ${report.state}${note}`;
+}
+
+// 秒数を「時:分:秒」形式に変換する関数
+function formatDuration(seconds: number): string {
+  const h = Math.floor(seconds / 3600);
+  const m = Math.floor((seconds % 3600) / 60);
+  const s = seconds % 60;
+  return `${h}:${String(m).padStart(2, "0")}:${String(s).padStart(2, "0")}`;
 }
 
 function App() {
@@ -25,10 +277,41 @@ function App() {
   const [isErasing, setIsErasing] = useState(false);
   const [message, setMessage] = useState("");
   const [error, setError] = useState("");
+  const [progress, setProgress] = useState<Record<string, ProgressEvent>>({});
+  const [jobId, setJobId] = useState<string | null>(null);
+  const [schemes, setSchemes] = useState<SchemeSummary[]>([]);
+  const [selectedScheme, setSelectedScheme] = useState<string | null>(null);
+  const [samplePercent, setSamplePercent] = useState(100);
+  const [resumables, setResumables] = useState<ResumableErase[]>([]);
+  const [capabilities, setCapabilities] = useState<DiskCapabilityReport | null>(null);
+  const [selectedMethod, setSelectedMethod] = useState("auto");
+  const [postErase, setPostErase] = useState<PostEraseAction>("none");
+  const [sanitizePasses, setSanitizePasses] = useState(1);
+  const [noDeallocate, setNoDeallocate] = useState(false);
 
-  // コンポーネントマウント時にディスク情報を取得
+  // コンポーネントマウント時にディスク情報を取得し、実行中のジョブがあれば再接続
   useEffect(() => {
     fetchDisks();
+    fetchSchemes();
+    fetchResumables();
+    reconnectToRunningJob();
+  }, []);
+
+  // 実行中のジョブの状態を定期的に確認
+  useEffect(() => {
+    if (!jobId) return;
+    const timer = setInterval(() => pollJob(jobId), 1000);
+    return () => clearInterval(timer);
+  }, [jobId]);
+
+  // 消去進捗イベントを購読
+  useEffect(() => {
+    const unlisten = listen<ProgressEvent>("erase-progress", (event) => {
+      setProgress((prev) => ({ ...prev, [event.payload.device]: event.payload }));
+    });
+    return () => {
+      unlisten.then((f) => f());
+    };
   }, []);
 
   // ディスク情報を取得する関数
@@ -39,15 +322,128 @@ function App() {
       setError("");
     } catch (e) {
       console.error("ディスク情報の取得に失敗しました:", e);
-      setError("ディスク情報の取得に失敗しました。");
+      setError(`ディスク情報の取得に失敗しました: ${e}`);
+    }
+  }
+
+  // 上書き消去方式の一覧を取得する関数
+  async function fetchSchemes() {
+    try {
+      setSchemes(await invoke<SchemeSummary[]>("get_overwrite_schemes"));
+    } catch (e) {
+      console.error("上書き消去方式の取得に失敗しました:", e);
+      setError(`上書き消去方式の取得に失敗しました: ${e}`);
+    }
+  }
+
+  // ディスクが対応している消去方式を取得する関数
+  async function fetchCapabilities(device: string) {
+    try {
+      setCapabilities(await invoke<DiskCapabilityReport>("get_disk_capabilities", { device }));
+    } catch (e) {
+      console.error("消去方式の取得に失敗しました:", e);
+      setError(`消去方式の取得に失敗しました: ${e}`);
+    }
+  }
+
+  // 中断した上書き消去の一覧を取得する関数
+  async function fetchResumables() {
+    try {
+      setResumables(await invoke<ResumableErase[]>("get_resumable_erasures"));
+    } catch (e) {
+      console.error("中断した消去の取得に失敗しました:", e);
+    }
+  }
+
+  // 中断した上書き消去を再開するハンドラ
+  async function handleResume(device: string) {
+    setIsErasing(true);
+    setMessage("消去中...");
+    setError("");
+    setProgress({});
+
+    try {
+      const id = await invoke<string>("resume_erase", { device });
+      setResumables((prev) => prev.filter((r) => r.device !== device));
+      setJobId(id);
+    } catch (e: any) {
+      console.error("消去の再開に失敗しました:", e);
+      setError(`消去の再開に失敗しました: ${e.toString()}`);
+      setIsErasing(false);
+    }
+  }
+
+  // 中断した上書き消去のチェックポイントを破棄するハンドラ
+  async function handleDiscardCheckpoint(device: string) {
+    try {
+      await invoke("discard_checkpoint", { device });
+      setResumables((prev) => prev.filter((r) => r.device !== device));
+    } catch (e: any) {
+      setError(`チェックポイントの破棄に失敗しました: ${e.toString()}`);
+    }
+  }
+
+  // ウィンドウ再読み込み後に実行中のジョブへ再接続する関数
+  async function reconnectToRunningJob() {
+    try {
+      const jobs = await invoke<JobStatus[]>("list_jobs");
+      const running = jobs.find((job) => job.state === "running");
+      if (!running) return;
+
+      const snapshot: Record<string, ProgressEvent> = {};
+      running.disks.forEach((disk) => {
+        if (disk.progress) snapshot[disk.device] = disk.progress;
+      });
+      setProgress(snapshot);
+      setIsErasing(true);
+      setMessage("消去中...");
+      setJobId(running.job_id);
+    } catch (e) {
+      console.error("ジョブ一覧の取得に失敗しました:", e);
+    }
+  }
+
+  // ジョブの状態を確認し、終了していれば結果を表示する関数
+  async function pollJob(id: string) {
+    try {
+      const status = await invoke<JobStatus>("get_job_status", { jobId: id });
+      if (status.state === "running") return;
+
+      const result = await invoke<JobResult>("get_job_result", { jobId: id });
+      const names = new Map(
+        (capabilities?.methods ?? []).map((m) => [JSON.stringify(m.method), m.name])
+      );
+      const attempts = result.disks
+        .filter((disk) => disk.attempts.length > 0)
+        .map((disk) => {
+          const summary = `${disk.device}: ${describeAttempts(disk, names)}`;
+          return disk.sanitize ? `${summary}\n${describeSanitize(disk.sanitize)}` : summary;
+        })
+        .join("\n");
+      if (result.state === "completed") {
+        setMessage(attempts ? `${result.message}\n${attempts}` : result.message);
+      } else {
+        setMessage(attempts);
+        setError(`消去中にエラーが発生しました: ${result.message}`);
+      }
+      setJobId(null);
+      setIsErasing(false);
+    } catch (e: any) {
+      console.error("ジョブ状態の取得に失敗しました:", e);
+      setError(e.toString());
+      setJobId(null);
+      setIsErasing(false);
     }
   }
 
   // ディスク選択ハンドラ
   function handleDiskSelect(deviceName: string) {
     setSelectedDisk(deviceName);
+    setCapabilities(null);
+    setSelectedMethod("auto");
     setMessage("");
     setError("");
+    fetchCapabilities(deviceName);
   }
 
   // 消去確認ハンドラ
@@ -68,6 +464,10 @@ function App() {
     setError("");
   }
 
+  // 確認画面で選択した消去方式（自動選択の場合はnull）
+  const chosenMethod =
+    capabilities?.methods.find((m) => m.name === selectedMethod)?.method ?? null;
+
   // 消去実行ハンドラ
   async function handleErase() {
     if (!selectedDisk) return;
@@ -76,36 +476,95 @@ function App() {
     setIsConfirming(false);
     setMessage("消去中...");
     setError("");
+    setProgress({});
 
     try {
-      const result = await invoke<string>("erase_disks", {
-        disks: [selectedDisk],
-      });
-      setMessage(result);
+      const plan: DiskPlan = {
+        device: selectedDisk,
+        method: chosenMethod,
+        verification:
+          samplePercent >= 100
+            ? { mode: "full" }
+            : { mode: "sample", percent: samplePercent },
+        scheme: selectedScheme,
+        nvme_sanitize: {
+          overwrite_passes: sanitizePasses,
+          overwrite_pattern: 0,
+          invert_pattern: false,
+          no_deallocate: noDeallocate,
+        },
+        post_erase: postErase,
+      };
+      const id = await invoke<string>("erase_disks", { plans: [plan] });
+      setJobId(id);
     } catch (e: any) {
       console.error("消去中にエラーが発生しました:", e);
       setError(`消去中にエラーが発生しました: ${e.toString()}`);
-    } finally {
       setIsErasing(false);
     }
   }
 
+  // 実行中の消去を中断するハンドラ
+  async function handleAbortErase() {
+    try {
+      const result = await invoke<string>("cancel_erase", { jobId });
+      setMessage(result);
+    } catch (e: any) {
+      setError(e.toString());
+    }
+  }
+
   return (
     <div className="container">
       <h1>ディスク消去ユーティリティ</h1>
       <p className="description">
         このアプリケーションは、HDDとSSDのデータを安全に消去するためのツールです。
         <br />
-        HDDにはDoD 5220.22-M方式、SSDにはSecure Erase方式を使用します。
+        HDDには選択した上書き消去方式、SSDにはドライブが対応している消去方式を使用します。
       </p>
 
       {error && <div className="error">{error}</div>}
       {message && <div className="message">{message}</div>}
 
+      {!isConfirming && !isErasing && resumables.length > 0 && (
+        <div className="resumable-erasures">
+          <h2>中断した消去</h2>
+          {resumables.map((r) => (
+            <div key={r.device} className="resumable-item">
+              <div className="disk-name">
+                {r.device} - {r.scheme_name}（パス {r.pass}/{r.total_passes}、
+                {r.phase === "verify" ? "検証中" : `${formatBytes(r.offset)} / ${formatBytes(r.size)}`}）
+              </div>
+              {r.reason && <div className="progress-details">{r.reason}</div>}
+              <div className="actions">
+                <button
+                  onClick={() => handleDiscardCheckpoint(r.device)}
+                  className="cancel-button"
+                >
+                  破棄
+                </button>
+                <button
+                  onClick={() => handleResume(r.device)}
+                  disabled={!r.resumable}
+                  className="confirm-button"
+                >
+                  再開
+                </button>
+              </div>
+            </div>
+          ))}
+        </div>
+      )}
+
       {!isConfirming && !isErasing && (
         <div className="disk-selection">
           <h2>ディスク選択</h2>
           <p>消去するディスクを選択してください：</p>
+          {disks.some((disk) => disk.simulated) && (
+            <div className="simulation-banner">
+              シミュレーションモード: 表示されているのは仮想ディスクです。実際のディスクには書き込みません。
+            </div>
+          )}
 
           <div className="disk-list">
             {disks.length === 0 ? (
@@ -116,14 +575,33 @@ function App() {
                   key={disk.device_name}
                   className={`disk-item ${
                     selectedDisk === disk.device_name ? "selected" : ""
+                  } ${disk.protected ? "protected" : ""} ${
+                    disk.simulated ? "simulated" : ""
                   }`}
                   onClick={() => handleDiskSelect(disk.device_name)}
                 >
-                  <div className="disk-name">{disk.device_name}</div>
+                  <div className="disk-name">
+                    {disk.device_name}
+                    {disk.simulated && <span className="disk-virtual-tag">仮想</span>}
+                  </div>
                   <div className="disk-model">{disk.model}</div>
                   <div className="disk-type">
-                    {disk.device_type} - {disk.transport}
+                    {MEDIA_KIND_LABELS[disk.device_type]} - {TRANSPORT_LABELS[disk.transport]} -{" "}
+                    {formatBytes(disk.size)}
+                    {disk.removable && "（リムーバブル）"}
+                    {disk.read_only && "（読み取り専用）"}
                   </div>
+                  <div className="disk-details">
+                    セクタ: {disk.logical_sector_size}/{disk.physical_sector_size}バイト
+                    {disk.serial && ` / シリアル: ${disk.serial}`}
+                    {disk.wwn && ` / WWN: ${disk.wwn}`}
+                    {disk.firmware && ` / FW: ${disk.firmware}`}
+                  </div>
+                  {disk.protected && (
+                    <div className="disk-protection">
+                      使用中のため保護されています: {disk.protection_reasons.join("、")}
+                    </div>
+                  )}
                 </div>
               ))
             )}
@@ -155,6 +633,130 @@ function App() {
           <p>
             消去するディスク: <strong>{selectedDisk}</strong>
           </p>
+          {capabilities && capabilities.device_name === selectedDisk && (
+            <div className="disk-capabilities">
+              <p>
+                自動選択で試す消去方式:{" "}
+                <strong>
+                  {capabilities.policy_error ??
+                    capabilities.policy_chain
+                      .map(
+                        (method) =>
+                          capabilities.methods.find(
+                            (m) => JSON.stringify(m.method) === JSON.stringify(method)
+                          )?.name
+                      )
+                      .join(" → ")}
+                </strong>
+              </p>
+              <ul className="method-list">
+                {capabilities.methods.map((m) => (
+                  <li key={m.name} className={m.available ? "method-available" : "method-unavailable"}>
+                    {m.available ? "✓" : "✗"} {m.name}
+                    {m.reasons.length > 0 && (
+                      <span className="method-reasons">: {m.reasons.join(" ")}</span>
+                    )}
+                    {m.available && m.warnings.length > 0 && (
+                      <span className="method-reasons">⚠ {m.warnings.join(" ")}</span>
+                    )}
+                  </li>
+                ))}
+              </ul>
+            </div>
+          )}
+          <div className="scheme-selection">
+            <label>
+              消去方式:{" "}
+              <select
+                value={selectedMethod}
+                onChange={(e) => setSelectedMethod(e.target.value)}
+              >
+                <option value="auto">自動選択</option>
+                {capabilities?.methods
+                  .filter((m) => m.available)
+                  .map((m) => (
+                    <option key={m.name} value={m.name}>
+                      {m.name}
+                    </option>
+                  ))}
+              </select>
+            </label>
+            {capabilities?.methods
+              .find((m) => m.name === selectedMethod)
+              ?.warnings.map((warning) => (
+                <div key={warning} className="warning">
+                  {warning}
+                </div>
+              ))}
+            {chosenMethod?.method === "nvme_sanitize" && (
+              <>
+                {chosenMethod.action === "overwrite" && (
+                  <label>
+                    Sanitizeの上書きのパス数:{" "}
+                    <select
+                      value={sanitizePasses}
+                      onChange={(e) => setSanitizePasses(Number(e.target.value))}
+                    >
+                      {Array.from({ length: 16 }, (_, i) => i + 1).map((n) => (
+                        <option key={n} value={n}>
+                          {n}
+                        </option>
+                      ))}
+                    </select>
+                  </label>
+                )}
+                <label>
+                  <input
+                    type="checkbox"
+                    checked={noDeallocate}
+                    onChange={(e) => setNoDeallocate(e.target.checked)}
+                  />
+                  Sanitize後に割り当てを解除しない（No-Deallocate）
+                </label>
+              </>
+            )}
+            <label>
+              上書き消去方式:{" "}
+              <select
+                value={selectedScheme ?? ""}
+                onChange={(e) => setSelectedScheme(e.target.value || null)}
+              >
+                <option value="">既定</option>
+                {schemes.map((scheme) => (
+                  <option key={scheme.id} value={scheme.id}>
+                    {scheme.name}
+                  </option>
+                ))}
+              </select>
+            </label>
+            <p className="scheme-description">
+              {selectedScheme === null
+                ? "消去方式を自動選択した場合とSSDはNIST SP 800-88 Clear（1パス + 検証）、HDDの上書き消去はDoD 5220.22-M（3パス + 検証）を使用します。"
+                : schemes.find((scheme) => scheme.id === selectedScheme)?.description}
+            </p>
+            <label>
+              読み戻し検証:{" "}
+              <select
+                value={samplePercent}
+                onChange={(e) => setSamplePercent(Number(e.target.value))}
+              >
+                <option value={100}>全領域</option>
+                <option value={10}>10%サンプリング</option>
+                <option value={1}>1%サンプリング</option>
+              </select>
+            </label>
+            <label>
+              消去後の処理:{" "}
+              <select
+                value={postErase}
+                onChange={(e) => setPostErase(e.target.value as PostEraseAction)}
+              >
+                <option value="none">なし</option>
+                <option value="spin_down">スピンダウン</option>
+                <option value="power_off">電源切断（USB/リムーバブル）</option>
+              </select>
+            </label>
+          </div>
           <div className="actions">
             <button onClick={handleCancelErase} className="cancel-button">
               キャンセル
@@ -169,10 +771,44 @@ function App() {
       {isErasing && (
         <div className="erasing">
           <h2>消去中...</h2>
-          <div className="progress-bar">
-            <div className="progress-indicator"></div>
-          </div>
+          {Object.keys(progress).length === 0 ? (
+            <div className="progress-bar">
+              <div className="progress-indicator"></div>
+            </div>
+          ) : (
+            Object.values(progress).map((p) => (
+              <div key={p.device} className="disk-progress">
+                <div className="disk-name">
+                  {p.device} - {p.method}（パス {p.current_pass}/{p.total_passes}）
+                </div>
+                <div className="progress-bar">
+                  {p.total_bytes > 0 ? (
+                    <div
+                      className="progress-fill"
+                      style={{ width: `${(p.bytes_written / p.total_bytes) * 100}%` }}
+                    ></div>
+                  ) : (
+                    <div className="progress-indicator"></div>
+                  )}
+                </div>
+                {p.total_bytes > 0 && (
+                  <div className="progress-details">
+                    {formatBytes(p.bytes_written)} / {formatBytes(p.total_bytes)}
+                    {" ・ "}
+                    {formatBytes(p.throughput)}/s
+                    {p.eta_seconds !== null && ` ・ 残り ${formatDuration(p.eta_seconds)}`}
+                  </div>
+                )}
+                {p.message && <div className="progress-details">{p.message}</div>}
+              </div>
+            ))
+          )}
           <p>ディスクの消去中です。このプロセスには時間がかかる場合があります。</p>
+          <div className="actions">
+            <button onClick={handleAbortErase} className="cancel-button">
+              消去を中断
+            </button>
+          </div>
         </div>
       )}
     </div>